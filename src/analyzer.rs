@@ -5,12 +5,18 @@ use std::env;
 use std::io;
 use clap::Parser;
 use std::process::Command;
+use std::thread;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
 
 use stones::engine::{Board, Color::*};
-use stones::gametree::{GameTree, Symbol, Symbol::*, Turn::*};
+use stones::engine::Color as StoneColor;
+use stones::gametree::{GameTree, Symbol, Symbol::*, Turn, Turn::*, TurnResult::*};
 use stones::boards::*;
 use stones::san::*;
 use stones::layout::*;
+use stones::player::choose_move;
+use stones::analysis::{analyze, analysis_at, store_analysis, Analysis, Evaluation};
+use stones::cgt::RegionReport;
 
 use std::time::{Instant, Duration};
 use sfml::window::*;
@@ -32,6 +38,8 @@ const BOARD_COLOR    : Color = Color {r: 212, g: 140, b:  30, a: 255};  // wood
 //const BOARD_COLOR    : Color = Color {r: 128, g: 128, b:  128, a: 255}; // grey
 const BOARD_COLOR_SR : Color = Color {r: 106, g:  70, b:  15, a: 0};
 const EDGE_COLOR     : Color = Color {r:   0, g:   0, b:   0, a: 255};
+const GHOST_EDGE     : Color = Color {r:   0, g:   0, b:   0, a:  90};
+const GHOST_VEIL     : Color = Color {r: 212, g: 140, b:  30, a: 130};
 const MARKER_COLOR   : Color = Color {r:   0, g: 150, b: 255, a: 255};
 const SYMBOL_COLOR   : Color = Color {r:   0, g: 130, b:   0, a: 255};
 const BLACK_COLOR    : Color = Color {r:   0, g:   0, b:   0, a: 255};
@@ -39,10 +47,19 @@ const BLACK_HOVER    : Color = Color {r:   0, g:   0, b:   0, a:  80};
 const WHITE_COLOR    : Color = Color {r: 255, g: 255, b: 255, a: 255}; // white
 //const WHITE_COLOR    : Color = Color {r: 255, g: 244, b:  204, a: 255}; // cream
 const WHITE_HOVER    : Color = Color {r: 255, g: 255, b: 255, a:  80};
+const BLACK_FADED    : Color = Color {r:   0, g:   0, b:   0, a:  60};
+const WHITE_FADED    : Color = Color {r: 255, g: 255, b: 255, a:  90};
 //const BLACK_IMMORTAL : Color = Color {r: 255, g: 255, b: 255, a:  40};
 //const WHITE_IMMORTAL : Color = Color {r:   0, g:   0, b:   0, a:  40};
 const BUTTON_COLOR   : Color = Color {r: 200, g: 200, b: 200, a:  80};
 const BUTTON_HOVER   : Color = Color {r: 200, g: 200, b: 200, a: 160};
+const HEATMAP_ALPHA  : u8    = 150;
+const HEATMAP_TEXT   : Color = Color {r:   0, g:   0, b:   0, a: 255};
+const REGION_ALPHA   : u8    = 110;
+const REGION_COLORS  : [(u8, u8, u8); 6] = [
+    (230,  25,  75), ( 60, 180,  75), (  0, 130, 200),
+    (145,  30, 180), (245, 130,  48), ( 70, 240, 240),
+];
 
 // Command-line arguments.
 
@@ -52,6 +69,9 @@ struct CLI {
     #[arg(short, long)]                          create:   Option<String>,
     #[arg(short, long, default_value_t = false)] set_root: bool,
     #[arg(short, long, default_value_t = false)] no_open:  bool,
+    #[arg(long)]                                 computer: Option<String>,
+    #[arg(long, default_value_t = 2.0)]          think:    f64,
+    #[arg(long, default_value_t = false)]        local_sizing: bool,
 }
 
 fn main() -> io::Result<()> {
@@ -70,6 +90,18 @@ fn main() -> io::Result<()> {
         return Ok(());
     }
 
+    // If the --computer flag is given, work out which color the computer plays.
+
+    let computer = match args.computer.as_deref() {
+        None          => None,
+        Some("black") => Some(Computer::new(Black, Duration::from_secs_f64(args.think))),
+        Some("white") => Some(Computer::new(White, Duration::from_secs_f64(args.think))),
+        Some(other)   => {
+            eprintln!("Error: --computer must be 'black' or 'white' (got '{}').", other);
+            return Ok(());
+        }
+    };
+
     // If the --create flag is given, create the file in question or exit on error.
 
     if let Some(spec) = args.create {
@@ -99,7 +131,9 @@ fn main() -> io::Result<()> {
     // Read the file and open the interactive app.
 
     let (mut gametree, layout) = read_san_file(&args.filename)?;
-    interactive_app(&mut gametree, &layout, args.set_root);
+    let analyst = Analyst::new(Duration::from_secs_f64(args.think));
+    let regions = Regions::new();
+    interactive_app(&mut gametree, &layout, args.set_root, args.local_sizing, computer, analyst, regions);
     write_san_file(&args.filename, gametree, layout)?;
     Ok(())
 }
@@ -111,7 +145,9 @@ enum Mode {
     SymbolSelect(usize),
 }
 
-pub fn interactive_app(gametree: &mut GameTree, au_layout: &Layout, mut set_root: bool) {
+pub fn interactive_app(gametree: &mut GameTree, au_layout: &Layout, mut set_root: bool,
+                       local_sizing: bool, mut computer: Option<Computer>, mut analyst: Analyst,
+                       mut regions: Regions) {
     assert!(
         gametree.board().point_count() == au_layout.len(),
         "Interative app: board has {} points but layout has {} points.",
@@ -133,20 +169,39 @@ pub fn interactive_app(gametree: &mut GameTree, au_layout: &Layout, mut set_root
 
     // Stuff we track.
 
-    let (mut layout, mut stone_size) = sizing_in_px(&au_layout, &window);
+    // On a 3D board, the layers can be shown one at a time, with the points in
+    // the other layers faded (and not clickable).
+
+    let layers = au_layout.layers();
+    let mut layer: Option<usize> = None;
+
+    let (mut layout, mut stone_sizes) = sizing_in_px(au_layout, &window, local_sizing, None);
     let mut mode = Normal(None);
 
     // Event loop.
 
     'outer: while window.is_open() {
+        if let Some(computer) = &mut computer {
+            computer.update(gametree);
+        }
+
+        analyst.update(gametree);
+        regions.update(gametree);
+
+        let humans_turn = computer.as_ref().is_none_or(|c| c.color != gametree.whose_turn());
+        let faded: Vec<bool> = match layer {
+            Some(l) => au_layout.space.iter().map(|p| p.2 != layers[l]).collect(),
+            None    => vec![false; au_layout.len()],
+        };
+
         let mouse_pos = window.mouse_position();
         let hover_point = match mode {
-            Normal(_)       => get_hover_point(&layout, stone_size, mouse_pos.x, mouse_pos.y),
-            SymbolSelect(_) => None,
+            Normal(_) if humans_turn => get_hover_point(&layout, &stone_sizes, &faded, mouse_pos.x, mouse_pos.y),
+            _                        => None,
         };
         let hover_quad  = match mode {
             Normal(_)       => None,
-            SymbolSelect(p) => get_hover_quad(&layout, p, stone_size, mouse_pos.x, mouse_pos.y),
+            SymbolSelect(p) => get_hover_quad(&layout, p, stone_sizes[p], mouse_pos.x, mouse_pos.y),
         };
 
         while let Some(event) = window.poll_event() {
@@ -158,7 +213,7 @@ pub fn interactive_app(gametree: &mut GameTree, au_layout: &Layout, mut set_root
 
                 (_, _, Resized {..}) => {
                     update_view(&mut window);
-                    (layout, stone_size) = sizing_in_px(&au_layout, &window);
+                    (layout, stone_sizes) = sizing_in_px(au_layout, &window, local_sizing, layer.map(|l| layers[l]));
                 }
 
                 // Normal-mode event handling.
@@ -171,13 +226,24 @@ pub fn interactive_app(gametree: &mut GameTree, au_layout: &Layout, mut set_root
                     mode = Normal(Some((hp, Instant::now())));
                 }
 
+                // When playing against the computer, undoing takes back the
+                // computer's reply as well, so that it's your turn again.
+
                 (Normal(_), _, MouseButtonPressed {button: Right, ..}) => {
                     gametree.undo();
+
+                    if let Some(computer) = &computer {
+                        if computer.color == gametree.whose_turn() && gametree.last_turn().is_some() {
+                            gametree.undo();
+                        }
+                    }
                 }
 
                 (Normal(_), _, MouseButtonReleased {button: Middle, ..}) => {
                     mode = Normal(None);
-                    gametree.turn(gametree.whose_turn(), Pass);
+                    if humans_turn {
+                        gametree.turn(gametree.whose_turn(), Pass);
+                    }
                 }
                 
                 (Normal(_), _, KeyPressed {code: Key::Escape, ..}) => {
@@ -191,6 +257,26 @@ pub fn interactive_app(gametree: &mut GameTree, au_layout: &Layout, mut set_root
                     }
                 }
 
+                (Normal(_), _, KeyPressed {code: Key::A, ..}) => {
+                    analyst.enabled = !analyst.enabled;
+                }
+
+                (Normal(_), _, KeyPressed {code: Key::R, ..}) => {
+                    regions.enabled = !regions.enabled;
+                }
+
+                // The L key steps through the layers of a 3D board, and then
+                // goes back to showing all of them.
+
+                (Normal(_), _, KeyPressed {code: Key::L, ..}) => {
+                    layer = match layer {
+                        None if !layers.is_empty()     => Some(0),
+                        Some(l) if l + 1 < layers.len() => Some(l + 1),
+                        _                               => None,
+                    };
+                    (layout, stone_sizes) = sizing_in_px(au_layout, &window, local_sizing, layer.map(|l| layers[l]));
+                }
+
                 (Normal(_), _, KeyPressed {code: Key::S, ..}) => {
                     println!("S key pressed!");
 
@@ -201,11 +287,7 @@ pub fn interactive_app(gametree: &mut GameTree, au_layout: &Layout, mut set_root
                         texture.update_from_render_window(&window, 0, 0);
                     }
 
-                    let (mut left, mut right, mut top, mut bottom) = layout.bounds();
-                    left   -= stone_size * STONE_MARGIN_SCREENSHOT;
-                    right  += stone_size * STONE_MARGIN_SCREENSHOT;
-                    top    -= stone_size * STONE_MARGIN_SCREENSHOT;
-                    bottom += stone_size * STONE_MARGIN_SCREENSHOT;
+                    let (left, right, top, bottom) = padded_bounds(&layout, &stone_sizes, STONE_MARGIN_SCREENSHOT);
 
                     let w = right - left;
                     let h = bottom - top;
@@ -280,15 +362,22 @@ pub fn interactive_app(gametree: &mut GameTree, au_layout: &Layout, mut set_root
         }
 
         draw_bg              (&mut window, set_root);
-        draw_board           (&mut window, &gametree, &layout, stone_size);
-        draw_stones          (&mut window, &gametree, &layout, stone_size); // * 0.95);
-        draw_move_marker     (&mut window, &gametree, &layout, stone_size);
+        draw_board           (&mut window, gametree, &layout, &stone_sizes, &faded);
+        draw_stones          (&mut window, gametree, &layout, &stone_sizes, &faded); // * 0.95);
+        draw_ghost_veil      (&mut window, &layout, &stone_sizes);
+        if regions.enabled {
+            draw_regions     (&mut window, gametree, &regions, &layout, &stone_sizes);
+        }
+        draw_move_marker     (&mut window, gametree, &layout, &stone_sizes);
         //draw_immortal_markers(&mut window, &gametree, &layout, stone_size);
-        draw_symbols         (&mut window, &gametree, &layout, stone_size);
+        if analyst.enabled {
+            draw_analysis    (&mut window, gametree, &layout, &stone_sizes);
+        }
+        draw_symbols         (&mut window, gametree, &layout, &stone_sizes);
 
         match mode {
-            Normal(None) => {draw_hover_stone(&mut window, &gametree, &layout, stone_size, hover_point);}
-            SymbolSelect(pt) => {draw_symbol_select_overlay(&mut window, layout[pt], stone_size, hover_quad);}
+            Normal(None) => {draw_hover_stone(&mut window, gametree, &layout, &stone_sizes, hover_point);}
+            SymbolSelect(pt) => {draw_symbol_select_overlay(&mut window, layout[pt], stone_sizes[pt], hover_quad);}
             _ => {}
         }
 
@@ -306,27 +395,56 @@ fn draw_bg(win: &mut RenderWindow, set_root: bool) {
     win.clear(if set_root {BOARD_COLOR_SR} else {BOARD_COLOR});
 }
 
-// Draw the edges of the board.
+// Draw the board. Every copy of a point (see layout.rs) gets an edge to the
+// nearest copy of each of its neighbors, and edges that touch a ghost or a
+// faded point are drawn faded.
 
-fn draw_board(win: &mut RenderWindow, gametree: &GameTree, layout: &Layout, stone_size: f32) {
-    for i in 0..gametree.board().point_count() {
+fn draw_board(win: &mut RenderWindow, gametree: &GameTree, layout: &Layout, stone_sizes: &[f32],
+              faded: &[bool]) {
+    let extent = wrap_extent(layout);
+
+    for (i, a, a_ghost) in layout.all_positions() {
         for j in gametree.board().get_neighbors(i) {
-            draw_line(win, layout[i], layout[j], EDGE_COLOR, stone_size / EDGE_WIDTH_RATIO);
+            let b = layout.positions(j).into_iter()
+                          .min_by(|p, q| distance(a, *p).total_cmp(&distance(a, *q)))
+                          .unwrap();
+            let b_ghost = b != layout[j];
+            let color = if a_ghost || b_ghost || faded[i] || faded[j] {GHOST_EDGE} else {EDGE_COLOR};
+            let stone_size = f32::min(stone_sizes[i], stone_sizes[j]);
+
+            for (a, b) in edge_segments(a, b, stone_size * 2.0, extent) {
+                draw_line(win, a, b, color, stone_size / EDGE_WIDTH_RATIO);
+            }
         }
     }
 }
 
-// Draw the stones on the board.
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    f32::hypot(a.0 - b.0, a.1 - b.1)
+}
+
+// Draw the stones on the board. Faded stones are drawn first, so that the
+// others are drawn over them.
+
+fn draw_stones(win: &mut RenderWindow, gametree: &GameTree, layout: &Layout, stone_sizes: &[f32],
+               faded: &[bool]) {
+    for (i, position, _) in layout.all_positions() {
+        if faded[i] && gametree.color_at(i) != Empty {
+            let color = if gametree.color_at(i) == Black {BLACK_FADED} else {WHITE_FADED};
+            draw_circle_plain(win, position, stone_sizes[i], color);
+        }
+    }
+
+    for (i, position, _) in layout.all_positions() {
+        let stone_size = stone_sizes[i];
 
-fn draw_stones(win: &mut RenderWindow, gametree: &GameTree, layout: &Layout, stone_size: f32) {
-    for i in 0..gametree.board().point_count() {
-        if gametree.color_at(i) != Empty {
+        if !faded[i] && gametree.color_at(i) != Empty {
             if gametree.color_at(i) == Black {
-                draw_circle_plain(win, layout[i], stone_size, BLACK_COLOR);
+                draw_circle_plain(win, position, stone_size, BLACK_COLOR);
             } else {
-                //draw_circle_plain(win, layout[i], stone_size, BLACK_COLOR);
-                //draw_circle_plain(win, layout[i], stone_size * 0.90, WHITE_COLOR);
-                draw_circle_plain(win, layout[i], stone_size, WHITE_COLOR);
+                //draw_circle_plain(win, position, stone_size, BLACK_COLOR);
+                //draw_circle_plain(win, position, stone_size * 0.90, WHITE_COLOR);
+                draw_circle_plain(win, position, stone_size, WHITE_COLOR);
             }
 
             //let color = if gametree.color_at(i) == Black {BLACK_COLOR} else {WHITE_COLOR};
@@ -334,11 +452,22 @@ fn draw_stones(win: &mut RenderWindow, gametree: &GameTree, layout: &Layout, sto
     }
 }
 
+// Fade the ghosts a little, so that it's clear which copy of each point is
+// the real one.
+
+fn draw_ghost_veil(win: &mut RenderWindow, layout: &Layout, stone_sizes: &[f32]) {
+    for &(i, position) in &layout.ghosts {
+        draw_circle_plain(win, position, stone_sizes[i] * 1.05, GHOST_VEIL);
+    }
+}
+
 // Draw the last-move marker.
 
-fn draw_move_marker(win: &mut RenderWindow, gametree: &GameTree, layout: &Layout, stone_size: f32) {
+fn draw_move_marker(win: &mut RenderWindow, gametree: &GameTree, layout: &Layout, stone_sizes: &[f32]) {
     if let Some(Play(point)) = gametree.last_turn() {
-        draw_square_plain(win, layout[point], stone_size * 0.4, MARKER_COLOR);
+        for position in layout.positions(point) {
+            draw_square_plain(win, position, stone_sizes[point] * 0.4, MARKER_COLOR);
+        }
     }
 }
 
@@ -364,31 +493,155 @@ fn draw_move_marker(win: &mut RenderWindow, gametree: &GameTree, layout: &Layout
 // Draw the hover stone.
 
 fn draw_hover_stone(win: &mut RenderWindow, gametree: &GameTree, layout: &Layout,
-                    stone_size: f32, hover_point: Option<usize>) {
+                    stone_sizes: &[f32], hover_point: Option<usize>) {
     if !gametree.game_over() {
         if let Some(hp) = hover_point {
             if gametree.color_at(hp) == Empty {
-                draw_circle_plain(
-                    win,
-                    layout[hp],
-                    stone_size,
-                    match gametree.whose_turn() {
-                        Black => BLACK_HOVER,
-                        White => WHITE_HOVER,
-                        _ => {panic!();}
-                    }
-                );
+                for position in layout.positions(hp) {
+                    draw_circle_plain(
+                        win,
+                        position,
+                        stone_sizes[hp],
+                        match gametree.whose_turn() {
+                            Black => BLACK_HOVER,
+                            White => WHITE_HOVER,
+                            _ => {panic!();}
+                        }
+                    );
+                }
             }
         }
     }
 }
 
 
+// Draw the analysis heatmap. Each empty point the computer evaluated gets a
+// disk colored from red (worst) to green (best) with its value written on it:
+// the final score for the player to move if the move was solved exactly, or
+// the win rate in percent if it came from MCTS (in which case the color shows
+// the visit count instead).
+
+fn draw_analysis(win: &mut RenderWindow, gametree: &GameTree, layout: &Layout, stone_sizes: &[f32]) {
+    let Some(analysis) = analysis_at(gametree) else {return};
+
+    let points: Vec<(usize, Evaluation)> =
+        analysis.moves.iter()
+            .filter_map(|&(turn, eval)| match turn {
+                Play(pt) if gametree.color_at(pt) == Empty => Some((pt, eval)),
+                _ => None,
+            })
+            .collect();
+
+    let heat = |eval: Evaluation| match eval {
+        Evaluation::Exact(v)          => v as f32,
+        Evaluation::Mcts {visits, ..} => visits as f32,
+    };
+
+    let lo = points.iter().map(|p| heat(p.1)).reduce(f32::min).unwrap_or(0.0);
+    let hi = points.iter().map(|p| heat(p.1)).reduce(f32::max).unwrap_or(0.0);
+
+    for (pt, eval) in points {
+        let t = if hi > lo {(heat(eval) - lo) / (hi - lo)} else {1.0};
+        let color = Color {
+            r: (255.0 * f32::min(1.0, 2.0 * (1.0 - t))) as u8,
+            g: (255.0 * f32::min(1.0, 2.0 * t)) as u8,
+            b: 0,
+            a: HEATMAP_ALPHA,
+        };
+
+        let text = match eval {
+            Evaluation::Exact(v)            => format!("{:+}", v),
+            Evaluation::Mcts {win_rate, ..} => format!("{:.0}", win_rate * 100.0),
+        };
+
+        for position in layout.positions(pt) {
+            draw_circle_plain(win, position, stone_sizes[pt] * 0.8, color);
+            draw_digits(win, position, stone_sizes[pt] * 0.6, &text, HEATMAP_TEXT);
+        }
+    }
+}
+
+// Draw the region overlay, shading the points of each independent region (see
+// stones::cgt) in its own translucent color.
+
+fn draw_regions(win: &mut RenderWindow, gametree: &GameTree, regions: &Regions, layout: &Layout, stone_sizes: &[f32]) {
+    let Some((node, report)) = &regions.report else {return};
+    if *node != gametree.node_id() {
+        return;
+    }
+
+    for (i, region) in report.regions.iter().enumerate() {
+        let (r, g, b) = REGION_COLORS[i % REGION_COLORS.len()];
+        let color = Color {r, g, b, a: REGION_ALPHA};
+
+        for &pt in &region.points {
+            for position in layout.positions(pt) {
+                draw_circle_plain(win, position, stone_sizes[pt] * 0.9, color);
+            }
+        }
+    }
+}
+
+// Draw a short string of digits (and plus and minus signs) centered on a given
+// point, seven-segment style, so that we don't need a font.
+
+fn draw_digits(win: &mut RenderWindow, center: (f32, f32), height: f32, text: &str, color: Color) {
+    let w = height / 2.0;
+    let h = height;
+    let gap = w / 2.0;
+    let thickness = height / 10.0;
+    let count = text.chars().count() as f32;
+    let mut x = center.0 - (count * w + (count - 1.0) * gap) / 2.0;
+    let y = center.1 - h / 2.0;
+
+    // Segments, in the usual order: top, top right, bottom right, bottom,
+    // bottom left, top left, middle.
+
+    let segments = [
+        ((0.0, 0.0), (1.0, 0.0)),
+        ((1.0, 0.0), (1.0, 0.5)),
+        ((1.0, 0.5), (1.0, 1.0)),
+        ((0.0, 1.0), (1.0, 1.0)),
+        ((0.0, 0.5), (0.0, 1.0)),
+        ((0.0, 0.0), (0.0, 0.5)),
+        ((0.0, 0.5), (1.0, 0.5)),
+    ];
+
+    for c in text.chars() {
+        let lit: &[usize] = match c {
+            '0' => &[0, 1, 2, 3, 4, 5],
+            '1' => &[1, 2],
+            '2' => &[0, 1, 6, 4, 3],
+            '3' => &[0, 1, 6, 2, 3],
+            '4' => &[5, 6, 1, 2],
+            '5' => &[0, 5, 6, 2, 3],
+            '6' => &[0, 5, 6, 4, 3, 2],
+            '7' => &[0, 1, 2],
+            '8' => &[0, 1, 2, 3, 4, 5, 6],
+            '9' => &[0, 1, 2, 3, 5, 6],
+            '-' => &[6],
+            '+' => &[6],
+            _   => &[],
+        };
+
+        for &segment in lit {
+            let ((ax, ay), (bx, by)) = segments[segment];
+            draw_line(win, (x + ax * w, y + ay * h), (x + bx * w, y + by * h), color, thickness);
+        }
+
+        if c == '+' {
+            draw_line(win, (x + w / 2.0, y + h / 4.0), (x + w / 2.0, y + h * 3.0 / 4.0), color, thickness);
+        }
+
+        x += w + gap;
+    }
+}
+
 // Draw the symbols that have been dropped on the board.
 
-fn draw_symbols(win: &mut RenderWindow, gametree: &GameTree, layout: &Layout, stone_size: f32) {
-    for pt in 0..gametree.board().point_count() {
-        draw_symbol(win, layout[pt], stone_size, gametree.symbol_at(pt));
+fn draw_symbols(win: &mut RenderWindow, gametree: &GameTree, layout: &Layout, stone_sizes: &[f32]) {
+    for (pt, position, _) in layout.all_positions() {
+        draw_symbol(win, position, stone_sizes[pt], gametree.symbol_at(pt));
     }
 }
 
@@ -527,11 +780,13 @@ fn get_hover_quad(layout: &Layout, point: usize, stone_size: f32, x: i32, y: i32
     return None;
 }
 
-// Determine which point on the board, if any, the mouse is within a stone's radius of.
+// Determine which point on the board, if any, the mouse is within a stone's
+// radius of. Hovering over a ghost counts as hovering over its point, and
+// faded points can't be hovered over.
 
-fn get_hover_point(layout: &Layout, stone_size: f32, x: i32, y: i32) -> Option<usize> {
-    for (i, point) in layout.iter().enumerate() {
-        if f32::hypot(point.0 - x as f32, point.1 - y as f32) <= stone_size {
+fn get_hover_point(layout: &Layout, stone_sizes: &[f32], faded: &[bool], x: i32, y: i32) -> Option<usize> {
+    for (i, point, _) in layout.all_positions() {
+        if !faded[i] && f32::hypot(point.0 - x as f32, point.1 - y as f32) <= stone_sizes[i] {
             return Some(i);
         }
     }
@@ -539,23 +794,30 @@ fn get_hover_point(layout: &Layout, stone_size: f32, x: i32, y: i32) -> Option<u
     return None;
 }
 
-// Compute the layout of the board in window coordinates.
+// Compute the layout of the board in window coordinates, along with the size
+// of the stone at each point.
+
+fn sizing_in_px(au_layout: &Layout, win: &RenderWindow, local_sizing: bool, layer: Option<f32>) -> (Layout, Vec<f32>) {
 
-fn sizing_in_px(au_layout: &Layout, win: &RenderWindow) -> (Layout, f32) {
+    // Compute the arbitrary-units stone sizes. When one layer of a 3D board is
+    // shown, the points in it are sized by the distances between them alone,
+    // since the points of the other layers in between them are faded.
 
-    // Compute the arbitrary-units stone size as half the minimum distance
-    // between any two points in the arbitrary-units layout.
+    let mut au_stone_sizes = separation_sizes(au_layout, local_sizing);
 
-    let au_stone_size = au_layout.min_point_separation() / 2.0;
+    if let Some(z) = layer {
+        let in_layer: Vec<usize> = (0..au_layout.len()).filter(|&p| au_layout.space[p].2 == z).collect();
+        let layer_layout: Layout = in_layer.iter().map(|&p| au_layout[p]).collect();
+
+        for (&p, size) in in_layer.iter().zip(separation_sizes(&layer_layout, local_sizing)) {
+            au_stone_sizes[p] = size;
+        }
+    }
 
     // Compute the arbitrary-units bounding box of the board's layout,
     // accounting for how far out the stones may go.
 
-    let (mut au_left, mut au_right, mut au_top, mut au_bottom) = au_layout.bounds();
-    au_left   -= au_stone_size * STONE_MARGIN;
-    au_right  += au_stone_size * STONE_MARGIN;
-    au_top    -= au_stone_size * STONE_MARGIN;
-    au_bottom += au_stone_size * STONE_MARGIN;
+    let (au_left, au_right, au_top, au_bottom) = padded_bounds(au_layout, &au_stone_sizes, STONE_MARGIN);
 
     let au_width  = au_right  - au_left;
     let au_height = au_bottom - au_top;
@@ -573,11 +835,199 @@ fn sizing_in_px(au_layout: &Layout, win: &RenderWindow) -> (Layout, f32) {
     let offset_w = (win_w - au_width  * squish_factor) / 2.0;
     let offset_h = (win_h - au_height * squish_factor) / 2.0;
 
-    let layout = au_layout.iter()
-                          .map(|(x, y)| ((x - au_left) * squish_factor + offset_w,
-                                         (y - au_top ) * squish_factor + offset_h))
-                          .collect();
-    let stone_size = au_stone_size * squish_factor;
-    (layout, stone_size)
+    let layout = au_layout.clone()
+                          .shift(-au_left, -au_top)
+                          .scale(squish_factor)
+                          .shift(offset_w, offset_h);
+    let stone_sizes = au_stone_sizes.into_iter().map(|size| size * squish_factor).collect();
+    (layout, stone_sizes)
+}
+
+// The stone sizes for a layout in arbitrary units: half the minimum distance
+// between any two points or, with local sizing, half the distance from each
+// point to the nearest other point.
+
+fn separation_sizes(au_layout: &Layout, local_sizing: bool) -> Vec<f32> {
+    if local_sizing {
+        au_layout.point_separations().into_iter().map(|dist| dist / 2.0).collect()
+    } else {
+        vec![au_layout.min_point_separation() / 2.0; au_layout.len()]
+    }
+}
+
+// The bounding box of a layout, with room around each stone (and ghost) for a
+// given multiple of its size.
+
+fn padded_bounds(layout: &Layout, stone_sizes: &[f32], margin: f32) -> (f32, f32, f32, f32) {
+    let mut bounds = (f32::INFINITY, f32::NEG_INFINITY, f32::INFINITY, f32::NEG_INFINITY);
+
+    for (i, (x, y), _) in layout.all_positions() {
+        let pad = stone_sizes[i] * margin;
+        bounds.0 = bounds.0.min(x - pad);
+        bounds.1 = bounds.1.max(x + pad);
+        bounds.2 = bounds.2.min(y - pad);
+        bounds.3 = bounds.3.max(y + pad);
+    }
+
+    bounds
+}
+
+
+// The computer opponent. Whenever it's the computer's turn, it copies the game
+// tree and picks a move on a background thread so that the window stays
+// responsive. If the cursor has moved by the time the move comes back, the
+// move is thrown away (and a new one is started if it's the computer's turn
+// again). If the move turns out to be illegal, the computer passes instead.
+
+pub struct Computer {
+    color:      StoneColor,
+    think_time: Duration,
+    job:        Option<(usize, Receiver<Turn>)>,
+}
+
+impl Computer {
+    fn new(color: StoneColor, think_time: Duration) -> Self {
+        Computer {
+            color:      color,
+            think_time: think_time,
+            job:        None,
+        }
+    }
+
+    fn update(&mut self, gametree: &mut GameTree) {
+        if let Some((node, receiver)) = &self.job {
+            match receiver.try_recv() {
+                Ok(turn) => {
+                    if gametree.node_id() == *node {
+                        let result = gametree.turn(self.color, turn);
+                        if result != Success && result != SuccessGameOver {
+                            eprintln!("The computer chose an illegal move ({:?}), so it passes instead.", turn);
+                            gametree.turn(self.color, Pass);
+                        }
+                    }
+                    self.job = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {self.job = None;}
+            }
+        }
+
+        if self.job.is_none() && !gametree.game_over() && gametree.whose_turn() == self.color {
+            let (sender, receiver) = channel();
+            let tree = gametree.clone();
+            let think_time = self.think_time;
+
+            thread::spawn(move || {
+                let _ = sender.send(choose_move(&tree, think_time, 0.0));
+            });
+
+            self.job = Some((gametree.node_id(), receiver));
+        }
+    }
 }
 
+
+// The analysis overlay. While it's turned on, any node the cursor visits that
+// doesn't have an analysis yet gets one computed on a background thread. The
+// result is stored in the node it was computed for (even if the cursor has
+// moved on since), so it's saved with the file and shows up immediately when
+// you come back to that node.
+
+pub struct Analyst {
+    enabled:    bool,
+    think_time: Duration,
+    job:        Option<(usize, Receiver<Analysis>)>,
+}
+
+impl Analyst {
+    fn new(think_time: Duration) -> Self {
+        Analyst {
+            enabled:    false,
+            think_time: think_time,
+            job:        None,
+        }
+    }
+
+    fn update(&mut self, gametree: &mut GameTree) {
+        if let Some((node, receiver)) = &self.job {
+            match receiver.try_recv() {
+                Ok(analysis) => {
+                    store_analysis(gametree, *node, &analysis);
+                    self.job = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {self.job = None;}
+            }
+        }
+
+        if self.enabled && self.job.is_none() && gametree.annotation().is_none() && !gametree.game_over() {
+            let (sender, receiver) = channel();
+            let tree = gametree.clone();
+            let think_time = self.think_time;
+
+            thread::spawn(move || {
+                let _ = sender.send(analyze(&tree, think_time));
+            });
+
+            self.job = Some((gametree.node_id(), receiver));
+        }
+    }
+}
+
+
+// The region overlay. While it's turned on, each node the cursor visits has
+// its independent regions worked out on a background thread. The report, with
+// each region's value, the predicted result and the result of playing the
+// regions out, is printed to stdout when it's ready, and the regions are
+// shaded on the board.
+
+pub struct Regions {
+    enabled: bool,
+    report:  Option<(usize, RegionReport)>,
+    job:     Option<(usize, Receiver<RegionReport>)>,
+}
+
+impl Regions {
+    fn new() -> Self {
+        Regions {
+            enabled: false,
+            report:  None,
+            job:     None,
+        }
+    }
+
+    fn update(&mut self, gametree: &GameTree) {
+        if let Some((node, receiver)) = &self.job {
+            match receiver.try_recv() {
+                Ok(report) => {
+                    self.report = Some((*node, report));
+                    self.job = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {self.job = None;}
+            }
+        }
+
+        let node = gametree.node_id();
+        let current = self.report.as_ref().is_some_and(|(n, _)| *n == node);
+        let pending = self.job.as_ref().is_some_and(|(n, _)| *n == node);
+
+        if self.enabled && !current && !pending && self.job.is_none() {
+            let (sender, receiver) = channel();
+            let tree = gametree.clone();
+
+            thread::spawn(move || {
+                let report = tree.analyze_regions();
+                let [predicted, played_out] =
+                    [report.predicted(tree.whose_turn()), report.played_out(tree.whose_turn())]
+                        .map(|score| score.map_or("unknown".to_string(), |score| score.to_string()));
+                println!("{}", report.to_text());
+                println!("Predicted result: {}", predicted);
+                println!("Played out: {}\n", played_out);
+                let _ = sender.send(report);
+            });
+
+            self.job = Some((node, receiver));
+        }
+    }
+}
@@ -334,7 +334,7 @@ impl LayoutGel {
     }
 
     fn get_lae(&self) -> (Layout, Edges) {
-        let layout = self.points.iter().map(|point| (point.x, point.y)).collect::<Vec<_>>();
+        let layout = self.points.iter().map(|point| (point.x, point.y)).collect::<Layout>();
         let mut edges = vec![];
 
         for i in 0..self.count() {
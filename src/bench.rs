@@ -0,0 +1,47 @@
+
+#![deny(warnings)]
+
+use std::env;
+use clap::Parser;
+
+use stones::boards::lae_from_spec;
+use stones::engine::Board;
+use stones::bench::{run_playouts, PlayoutConfig};
+
+// Command-line arguments. Each board argument is a board spec.
+//
+// For every board, this plays a number of seeded random games (see bench.rs)
+// and prints how fast they went along with the checksum of the positions they
+// visited. Running it before and after changing the engine should give the
+// same checksums and, with luck, a better speed.
+
+#[derive(Parser)]
+struct CLI {
+    #[arg(required = true)]                    boards: Vec<String>,
+    #[arg(short, long, default_value_t = 100)] games:  usize,
+    #[arg(short, long, default_value_t = 0)]   seed:   u64,
+}
+
+fn main() {
+    env::set_var("RUST_BACKTRACE", "1");
+    let args = CLI::parse();
+    let config = PlayoutConfig {games: args.games, seed: args.seed};
+
+    for spec in &args.boards {
+        let board = match lae_from_spec(spec) {
+            Ok((_layout, edges)) => Board::new(edges),
+            Err(err_string) => {
+                eprintln!("{}", err_string);
+                return;
+            }
+        };
+
+        let stats = run_playouts(&board, &config);
+
+        println!(
+            "{:<16} games: {}, moves: {}, captures: {}, checksum: {:016x}, {:.2}s, {:.0} moves/sec",
+            spec, stats.games, stats.moves, stats.captures, stats.checksum,
+            stats.elapsed.as_secs_f64(), stats.moves_per_second()
+        );
+    }
+}
@@ -0,0 +1,92 @@
+
+#![deny(warnings)]
+
+use std::env;
+use std::time::Instant;
+use clap::Parser;
+
+use stones::boards::lae_from_spec;
+use stones::engine::Board;
+use stones::san::read_san_file;
+use stones::count::{count_legal_positions, count_legal_positions_brute_force, BRUTE_FORCE_MAX_POINTS};
+
+// Command-line arguments. Each board argument is either a board spec or the
+// name of a .san file (whose board is used; its moves aren't).
+//
+// For every board, this prints the number of legal positions (see count.rs),
+// along with the fraction of all 3^N colorings that they make up and the
+// width of the path decomposition the count was done over. With
+// --brute-force, boards with up to BRUTE_FORCE_MAX_POINTS points are also
+// counted the slow way, and any disagreement is reported as an error.
+
+#[derive(Parser)]
+struct CLI {
+    #[arg(required = true)]                      boards:      Vec<String>,
+    #[arg(short, long, default_value_t = false)] brute_force: bool,
+}
+
+fn main() {
+    env::set_var("RUST_BACKTRACE", "1");
+    let args = CLI::parse();
+    let mut mismatches = 0;
+
+    for spec in &args.boards {
+        let board = if spec.ends_with(".san") {
+            match read_san_file(spec) {
+                Ok((tree, _layout)) => tree.board().clone(),
+                Err(err) => {
+                    eprintln!("Error: could not read '{}': {}", spec, err);
+                    return;
+                }
+            }
+        } else {
+            match lae_from_spec(spec) {
+                Ok((_layout, edges)) => Board::new(edges),
+                Err(err_string) => {
+                    eprintln!("{}", err_string);
+                    return;
+                }
+            }
+        };
+
+        let n = board.point_count();
+        let start = Instant::now();
+        let result = match count_legal_positions(&board) {
+            Ok(result) => result,
+            Err(err) => {
+                eprintln!("Error: could not count '{}': {}", spec, err);
+                return;
+            }
+        };
+        let elapsed = start.elapsed().as_secs_f64();
+
+        // The fraction of legal colorings, done with logarithms since both
+        // numbers can be far too big for an f64.
+
+        let fraction = 10f64.powf(result.count.log10() - n as f64 * 3f64.log10());
+
+        println!("{}", spec);
+        println!("    Points:          {}", n);
+        println!("    Legal positions: {}", result.count);
+        println!("    Fraction of 3^N: {:.6}", fraction);
+        println!("    Frontier width:  {} ({} states at most, {:.2}s)", result.width, result.max_states, elapsed);
+
+        if args.brute_force {
+            if n > BRUTE_FORCE_MAX_POINTS {
+                println!("    Brute force:     skipped (more than {} points)", BRUTE_FORCE_MAX_POINTS);
+            } else {
+                let brute_force = count_legal_positions_brute_force(&board);
+                let agrees = brute_force.to_string() == result.count.to_string();
+                println!("    Brute force:     {} ({})", brute_force, if agrees {"agrees"} else {"MISMATCH"});
+                if !agrees {
+                    mismatches += 1;
+                }
+            }
+        }
+    }
+
+    if mismatches > 0 {
+        eprintln!("Error: {} board(s) counted differently by brute force.", mismatches);
+        std::process::exit(1);
+    }
+}
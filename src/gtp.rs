@@ -0,0 +1,438 @@
+
+#![deny(warnings)]
+
+use std::collections::HashSet;
+use std::env;
+use std::io;
+use std::io::prelude::*;
+use std::time::Duration;
+use clap::Parser;
+
+use stones::boards::{lae_square, lae_from_spec};
+use stones::engine::{Board, Color, Edges};
+use stones::engine::Color::*;
+use stones::gametree::{GameTree, Turn};
+use stones::gametree::Turn::*;
+use stones::gametree::TurnResult::*;
+use stones::player::choose_move;
+
+// A Go Text Protocol (version 2) engine. On square boards, points are numbered
+// the way lae_square() numbers them (row by row from the top), and vertices
+// are converted to and from GTP's coordinates (columns A to T skipping I, rows
+// counted from the bottom).
+//
+// GTP lets a client play several moves of the same color in a row, but a
+// GameTree insists on alternating turns. When that happens, we put a pass for
+// the other color in between. The pass belongs to the move that needed it, so
+// it's taken back if the move turns out to be illegal, and undo takes back
+// both of them together. Two passes in a row don't end the game here, since
+// GTP leaves that to the controller, which can play on after them.
+//
+// Boards that aren't square are handled by a handful of extension commands,
+// which name points by their index instead of by vertex:
+//
+//     stones-load_board <edges|spec>   Load a board from its edge list (the
+//                                      JSON on the first line of a .san file)
+//                                      or from a board spec like "hex:4". It
+//                                      takes the rest of the line, so the spec
+//                                      can have spaces in it.
+//     stones-board                     Print the board's edge list.
+//     stones-point_count               Print the number of points.
+//     stones-neighbors <index>         Print the neighbors of a point.
+//     stones-play <color> <index>      Like play. The index can be "pass".
+//     stones-genmove <color>           Like genmove, but answers with an index.
+//
+// Once a board has been loaded this way, the commands that deal in vertices
+// fail until boardsize is used to get a square board back.
+
+const COLUMNS: &str = "ABCDEFGHJKLMNOPQRSTUVWXYZ";
+
+const COMMANDS: &[&str] = &[
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "quit",
+    "boardsize",
+    "clear_board",
+    "komi",
+    "play",
+    "genmove",
+    "undo",
+    "final_score",
+    "showboard",
+    "stones-load_board",
+    "stones-board",
+    "stones-point_count",
+    "stones-neighbors",
+    "stones-play",
+    "stones-genmove",
+];
+
+// Command-line arguments.
+
+#[derive(Parser)]
+struct CLI {
+    #[arg(short, long, default_value_t = 19)]  size:  usize,
+    #[arg(short, long, default_value_t = 2.0)] think: f64,
+}
+
+fn main() {
+    env::set_var("RUST_BACKTRACE", "1");
+    let args = CLI::parse();
+
+    if args.size < 2 || args.size > COLUMNS.len() {
+        eprintln!("Error: board size must be between 2 and {}.", COLUMNS.len());
+        return;
+    }
+
+    let mut engine = Engine::new(args.size, Duration::from_secs_f64(args.think));
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {break};
+
+        // Strip comments and control characters, and skip empty lines.
+
+        let line: String = line.split('#').next().unwrap()
+                               .chars()
+                               .map(|c| if c == '\t' {' '} else {c})
+                               .filter(|c| !c.is_control())
+                               .collect();
+
+        let mut rest = line.trim();
+        let id = match rest.split_whitespace().next() {
+            Some(word) if word.parse::<u64>().is_ok() => {
+                rest = rest[word.len()..].trim_start();
+                word.to_string()
+            }
+            Some(_) => "".to_string(),
+            None => continue,
+        };
+
+        let (command, rest) = rest.split_once(' ').map_or((rest, ""), |(command, rest)| (command, rest.trim()));
+        if command.is_empty() {
+            continue;
+        }
+
+        let args: Vec<&str> = if command == "stones-load_board" && !rest.is_empty() {
+            vec![rest]
+        } else {
+            rest.split_whitespace().collect()
+        };
+
+        match engine.handle(command, &args) {
+            Ok(response) => write!(stdout, "={} {}\n\n", id, response).unwrap(),
+            Err(message) => write!(stdout, "?{} {}\n\n", id, message).unwrap(),
+        }
+
+        stdout.flush().unwrap();
+
+        if command == "quit" {
+            break;
+        }
+    }
+}
+
+struct Engine {
+    size:       Option<usize>,    // None if the board isn't square
+    komi:       f32,
+    think_time: Duration,
+    tree:       GameTree,
+    moves:      Vec<usize>,       // how many turns each move added to the tree
+}
+
+impl Engine {
+    fn new(size: usize, think_time: Duration) -> Self {
+        Engine {
+            size:       Some(size),
+            komi:       0.0,
+            think_time: think_time,
+            tree:       GameTree::new(Board::new(lae_square(size).1)),
+            moves:      vec![],
+        }
+    }
+
+    fn handle(&mut self, command: &str, args: &[&str]) -> Result<String, String> {
+        match (command, args) {
+            ("protocol_version", []) => Ok("2".to_string()),
+            ("name", [])             => Ok("stones".to_string()),
+            ("version", [])          => Ok(env!("CARGO_PKG_VERSION").to_string()),
+            ("quit", [])             => Ok("".to_string()),
+
+            ("known_command", [name]) => Ok(COMMANDS.contains(name).to_string()),
+            ("list_commands", [])     => Ok(COMMANDS.join("\n")),
+
+            ("boardsize", [size]) => {
+                match size.parse::<usize>() {
+                    Ok(size) if size >= 2 && size <= COLUMNS.len() => {
+                        *self = Engine {komi: self.komi, ..Engine::new(size, self.think_time)};
+                        Ok("".to_string())
+                    }
+                    _ => Err("unacceptable size".to_string()),
+                }
+            }
+
+            ("clear_board", []) => {
+                self.tree = GameTree::new(self.tree.board().clone());
+                self.moves.clear();
+                Ok("".to_string())
+            }
+
+            ("komi", [komi]) => {
+                self.komi = komi.parse().map_err(|_| "syntax error".to_string())?;
+                Ok("".to_string())
+            }
+
+            ("play", [color, vertex]) => {
+                let color = parse_color(color)?;
+                let turn = self.parse_vertex(vertex)?;
+                self.play(color, turn)?;
+                Ok("".to_string())
+            }
+
+            ("genmove", [color]) => {
+                let color = parse_color(color)?;
+                self.square_size()?;
+                let turn = self.genmove(color)?;
+                self.format_vertex(turn)
+            }
+
+            ("undo", []) => {
+                let turns = self.moves.pop().ok_or("cannot undo".to_string())?;
+                for _ in 0..turns {
+                    self.tree.undo();
+                }
+                Ok("".to_string())
+            }
+
+            ("final_score", []) => {
+                let score = self.tree.score_delta_stone() as f32 - self.komi;
+                Ok(if score > 0.0 {
+                    format!("B+{}", score)
+                } else if score < 0.0 {
+                    format!("W+{}", -score)
+                } else {
+                    "0".to_string()
+                })
+            }
+
+            ("showboard", []) => Ok(self.showboard()),
+
+            ("stones-load_board", [board]) => {
+                let edges = if board.starts_with('[') {
+                    match serde_json::from_str(board) {
+                        Ok(edges) if valid_edges(&edges) => edges,
+                        _ => return Err("invalid edge list".to_string()),
+                    }
+                } else {
+                    lae_from_spec(board).map_err(|_| "unknown board spec".to_string())?.1
+                };
+
+                self.size = None;
+                self.tree = GameTree::new(Board::new(edges));
+                self.moves.clear();
+                Ok("".to_string())
+            }
+
+            ("stones-board", [])       => Ok(self.tree.board().to_string()),
+            ("stones-point_count", []) => Ok(self.tree.board().point_count().to_string()),
+
+            ("stones-neighbors", [index]) => {
+                match self.parse_index(index)? {
+                    Pass => Err("invalid index".to_string()),
+                    Play(point) => {
+                        let neighbors: Vec<String> =
+                            self.tree.board().get_neighbors(point).iter().map(|n| n.to_string()).collect();
+                        Ok(neighbors.join(" "))
+                    }
+                }
+            }
+
+            ("stones-play", [color, index]) => {
+                let color = parse_color(color)?;
+                let turn = self.parse_index(index)?;
+                self.play(color, turn)?;
+                Ok("".to_string())
+            }
+
+            ("stones-genmove", [color]) => {
+                let color = parse_color(color)?;
+                let turn = self.genmove(color)?;
+                Ok(format_index(turn))
+            }
+
+            _ if COMMANDS.contains(&command) => Err("syntax error".to_string()),
+            _ => Err("unknown command".to_string()),
+        }
+    }
+
+    // Play a turn for a given color, passing for the other color first if it
+    // isn't the given color's turn.
+
+    fn play(&mut self, color: Color, turn: Turn) -> Result<(), String> {
+        let passes = self.make_turn_for(color)?;
+        self.finish_move(color, turn, passes)
+    }
+
+    // Choose and play a turn for a given color. An illegal move should never
+    // be chosen, but if one is we pass instead, as the analyzer does. The
+    // move is only recorded for undo if one of them was played.
+
+    fn genmove(&mut self, color: Color) -> Result<Turn, String> {
+        let passes = self.make_turn_for(color)?;
+        let turn = choose_move(&self.tree, self.think_time, self.komi);
+
+        if matches!(self.tree.play_on(color, turn), Success | SuccessGameOver) {
+            self.moves.push(passes + 1);
+            return Ok(turn);
+        }
+
+        eprintln!("Warning: chose an illegal move ({:?}); passing instead.", turn);
+        self.finish_move(color, Pass, passes).map(|_| Pass)
+    }
+
+    // Pass for the other color if it's their turn, and return the number of
+    // passes that took.
+
+    fn make_turn_for(&mut self, color: Color) -> Result<usize, String> {
+        if self.tree.whose_turn() == color {
+            return Ok(0);
+        }
+
+        match self.tree.play_on(color.reverse(), Pass) {
+            Success | SuccessGameOver => Ok(1),
+            _ => Err("illegal move".to_string()),
+        }
+    }
+
+    // Play a turn after the passes make_turn_for() put in, and record the
+    // whole move so that undo can take it back. If the turn is illegal, the
+    // passes are taken back instead.
+
+    fn finish_move(&mut self, color: Color, turn: Turn, passes: usize) -> Result<(), String> {
+        match self.tree.play_on(color, turn) {
+            Success | SuccessGameOver => {
+                self.moves.push(passes + 1);
+                Ok(())
+            }
+            _ => {
+                for _ in 0..passes {
+                    self.tree.undo();
+                }
+                Err("illegal move".to_string())
+            }
+        }
+    }
+
+    fn square_size(&self) -> Result<usize, String> {
+        self.size.ok_or("board is not square; use the stones- commands".to_string())
+    }
+
+    fn parse_vertex(&self, vertex: &str) -> Result<Turn, String> {
+        let size = self.square_size()?;
+        let vertex = vertex.to_uppercase();
+
+        if vertex == "PASS" {
+            return Ok(Pass);
+        }
+
+        let mut chars = vertex.chars();
+        let column = chars.next().and_then(|c| COLUMNS.find(c));
+        let row = chars.as_str().parse::<usize>().ok();
+
+        match (column, row) {
+            (Some(x), Some(row)) if x < size && row >= 1 && row <= size => {
+                Ok(Play((size - row) * size + x))
+            }
+            _ => Err("invalid coordinate".to_string()),
+        }
+    }
+
+    fn format_vertex(&self, turn: Turn) -> Result<String, String> {
+        let size = self.square_size()?;
+
+        Ok(match turn {
+            Pass => "pass".to_string(),
+            Play(point) => {
+                let column = COLUMNS.chars().nth(point % size).unwrap();
+                format!("{}{}", column, size - point / size)
+            }
+        })
+    }
+
+    fn parse_index(&self, index: &str) -> Result<Turn, String> {
+        if index.to_lowercase() == "pass" {
+            return Ok(Pass);
+        }
+
+        match index.parse::<usize>() {
+            Ok(point) if point < self.tree.board().point_count() => Ok(Play(point)),
+            _ => Err("invalid index".to_string()),
+        }
+    }
+
+    // Square boards are shown as a grid. Other boards are shown as a list of
+    // points, ten to a line.
+
+    fn showboard(&self) -> String {
+        let symbol = |point| match self.tree.color_at(point) {
+            Black => " X",
+            White => " O",
+            Empty => " .",
+        };
+
+        let Some(size) = self.size else {
+            let points: Vec<usize> = (0..self.tree.board().point_count()).collect();
+            let mut lines = vec!["".to_string()];
+
+            for chunk in points.chunks(10) {
+                let row: String = chunk.iter().map(|&p| symbol(p)).collect();
+                lines.push(format!("{:>4} {}", chunk[0], row));
+            }
+
+            return lines.join("\n");
+        };
+
+        let header: String = COLUMNS.chars().take(size).map(|c| format!(" {}", c)).collect();
+        let mut lines = vec!["".to_string(), format!("   {}", header)];
+
+        for y in 0..size {
+            let row: String = (0..size).map(|x| symbol(y * size + x)).collect();
+            lines.push(format!("{:>2} {} {}", size - y, row, size - y));
+        }
+
+        lines.push(format!("   {}", header));
+        lines.join("\n")
+    }
+}
+
+fn format_index(turn: Turn) -> String {
+    match turn {
+        Pass => "pass".to_string(),
+        Play(point) => point.to_string(),
+    }
+}
+
+// Whether an edge list makes a board Board::new() can take, checked the way
+// boards.rs checks the boards it builds: there are edges, no point is joined
+// to itself, and the points are numbered from 0 with no gaps, so that every
+// point has an edge.
+
+fn valid_edges(edges: &Edges) -> bool {
+    let points: HashSet<usize> = edges.iter().flat_map(|&(a, b)| [a, b]).collect();
+
+    !edges.is_empty() &&
+    edges.iter().all(|&(a, b)| a != b) &&
+    points.iter().all(|&point| point < points.len())
+}
+
+fn parse_color(color: &str) -> Result<Color, String> {
+    match color.to_lowercase().as_str() {
+        "b" | "black" => Ok(Black),
+        "w" | "white" => Ok(White),
+        _ => Err("invalid color".to_string()),
+    }
+}
@@ -0,0 +1,100 @@
+
+/* analysis.rs
+ *
+ * This module provides the Analysis struct, which holds the computer's
+ * opinion of every move available in a position, and the analyze() function
+ * which produces one. On tiny boards every move is solved exactly and its
+ * evaluation is the final score (from the point of view of the player making
+ * the move); on everything else, or if solving takes too long, the moves are
+ * evaluated with MCTS and each gets a visit count and a win rate.
+ *
+ * Analyses can be stored in the nodes of a GameTree as their annotations
+ * (see analysis_at() and store_analysis()), which the tree saves along with
+ * the rest of it.
+ */
+
+use std::time::{Instant, Duration};
+use serde::{Serialize, Deserialize};
+use crate::engine::Color::*;
+use crate::gametree::{GameTree, Turn};
+use crate::gametree::Turn::*;
+use crate::gametree::TurnResult::*;
+use crate::solver::{Solver, SolverConfig};
+use crate::mcts::{Mcts, MctsConfig};
+
+pub const EXACT_MAX_POINTS: usize = 6;
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Evaluation {
+    Exact(i32),
+    Mcts {visits: u64, win_rate: f64},
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Analysis {
+    pub moves: Vec<(Turn, Evaluation)>,
+}
+
+impl Analysis {
+    pub fn evaluation(&self, turn: Turn) -> Option<Evaluation> {
+        self.moves.iter().find(|m| m.0 == turn).map(|m| m.1)
+    }
+}
+
+// The analysis stored in the node at the cursor of a given tree, if it has
+// one.
+
+pub fn analysis_at(tree: &GameTree) -> Option<Analysis> {
+    tree.annotation().and_then(|annotation| serde_json::from_value(annotation.clone()).ok())
+}
+
+// Store an analysis in a given node of a tree (see GameTree::node_id()).
+
+pub fn store_analysis(tree: &mut GameTree, node: usize, analysis: &Analysis) {
+    tree.set_annotation(node, serde_json::to_value(analysis).unwrap());
+}
+
+// Analyze the position at the cursor of a given tree, taking roughly a given
+// amount of time.
+
+pub fn analyze(tree: &GameTree, think_time: Duration) -> Analysis {
+    if tree.board().point_count() <= EXACT_MAX_POINTS {
+        if let Some(analysis) = analyze_exact(tree, Instant::now() + think_time) {
+            return analysis;
+        }
+    }
+
+    let config = MctsConfig {playouts: None, max_time: Some(think_time), ..Default::default()};
+    let result = Mcts::new(config).search(tree);
+
+    Analysis {
+        moves: result.moves.into_iter()
+                     .map(|m| (m.turn, Evaluation::Mcts {visits: m.visits, win_rate: m.win_rate}))
+                     .collect(),
+    }
+}
+
+// Solve every legal move, or give up if that can't be done before a given
+// deadline.
+
+fn analyze_exact(tree: &GameTree, deadline: Instant) -> Option<Analysis> {
+    let color = tree.whose_turn();
+    let sign = if color == Black {1} else {-1};
+    let mut tree = tree.clone();
+    let mut moves = vec![];
+
+    let turns = [Pass].into_iter().chain((0..tree.board().point_count()).map(Play));
+
+    for turn in turns {
+        let result = tree.turn(color, turn);
+        if result == Success || result == SuccessGameOver {
+            let time_left = deadline.checked_duration_since(Instant::now())?;
+            let config = SolverConfig {max_time: Some(time_left), ..Default::default()};
+            let value = Solver::new(config).solve(&tree).ok()?.value;
+            moves.push((turn, Evaluation::Exact(sign * value)));
+            tree.undo();
+        }
+    }
+
+    Some(Analysis {moves: moves})
+}
@@ -5,42 +5,110 @@
  * structures and layouts.
  */
 
+use std::collections::{HashMap, HashSet};
 use std::f32::consts::TAU;
+use std::f64::consts::PI;
+use std::rc::Rc;
 use crate::layout::*;
 use crate::engine::Edges;
+use crate::bench::SplitMix64;
+use crate::tiling::{lae_tiling, Region};
+use crate::transform::lae_induced;
+use crate::spec::build_spec;
 
 pub type Lae = (Layout, Edges); // "Layout and Edges"
 
 // Spec-based interface.
 
-fn board_specs() -> Vec<(&'static str, fn(Vec<usize>) -> Lae)> {
+fn board_specs() -> Vec<(&'static str, fn(Vec<usize>) -> Result<Lae, String>)> {
     vec![
-        ("square:N",       |args| lae_square(args[0])),
-        ("diamond:N",      |args| lae_diamond(args[0])),
-        ("grid:W:H",       |args| lae_grid(args[0], args[1])),
-        ("loop:N",         |args| lae_loop(args[0])),
-        ("hex:L",          |args| lae_trihex(args[0])),
-        ("honeycomb:L",    |args| lae_honeycomb(args[0])),
-        ("sixfourthree:L", |args| lae_sixfourthree(args[0])),
-        ("turtle:W:H",     |args| lae_turtle(args[0], args[1])),
-        ("wheels:W:H",     |args| lae_wheels(args[0], args[1])),
-        ("donut:W:H:X:Y",  |args| lae_donut(args[0], args[1], args[2], args[3])),
-        ("conga:N",        |args| lae_conga(args[0])),
-        ("pack",           |_args| lae_pack()),
-        ("pack2",          |_args| lae_pack2()),
+        ("square:N",                    |args| Ok(lae_square(args[0]))),
+        ("diamond:N",                   |args| Ok(lae_diamond(args[0]))),
+        ("grid:W:H",                    |args| Ok(lae_grid(args[0], args[1]))),
+        ("cylinder:W:H",                |args| Ok(lae_cylinder(args[0], args[1]))),
+        ("torus:W:H",                   |args| Ok(lae_torus(args[0], args[1]))),
+        ("mobius:W:H",                  |args| Ok(lae_mobius(args[0], args[1]))),
+        ("klein:W:H",                   |args| Ok(lae_klein(args[0], args[1]))),
+        ("loop:N",                      |args| Ok(lae_loop(args[0]))),
+        ("hex:L",                       |args| Ok(lae_trihex(args[0]))),
+        ("honeycomb:L",                 |args| Ok(lae_honeycomb(args[0]))),
+        ("sixfourthree:L",              |args| Ok(lae_sixfourthree(args[0]))),
+        ("turtle:W:H",                  |args| Ok(lae_turtle(args[0], args[1]))),
+        ("wheels:W:H",                  |args| Ok(lae_wheels(args[0], args[1]))),
+        ("donut:W:H:X:Y",               |args| Ok(lae_donut(args[0], args[1], args[2], args[3]))),
+        ("conga:N",                     |args| Ok(lae_conga(args[0]))),
+        ("pack",                        |_args| Ok(lae_pack())),
+        ("pack2",                       |_args| Ok(lae_pack2())),
+        ("tetrahedron",                 |_args| Ok(lae_tetrahedron())),
+        ("hexahedron",                  |_args| Ok(lae_hexahedron())),
+        ("octahedron",                  |_args| Ok(lae_octahedron())),
+        ("dodecahedron",                |_args| Ok(lae_dodecahedron())),
+        ("icosahedron",                 |_args| Ok(lae_icosahedron())),
+        ("truncated-tetrahedron",       |_args| Ok(lae_truncated_tetrahedron())),
+        ("cuboctahedron",               |_args| Ok(lae_cuboctahedron())),
+        ("truncated-cube",              |_args| Ok(lae_truncated_cube())),
+        ("truncated-octahedron",        |_args| Ok(lae_truncated_octahedron())),
+        ("rhombicuboctahedron",         |_args| Ok(lae_rhombicuboctahedron())),
+        ("truncated-cuboctahedron",     |_args| Ok(lae_truncated_cuboctahedron())),
+        ("snub-cube",                   |_args| Ok(lae_snub_cube())),
+        ("icosidodecahedron",           |_args| Ok(lae_icosidodecahedron())),
+        ("truncated-dodecahedron",      |_args| Ok(lae_truncated_dodecahedron())),
+        ("truncated-icosahedron",       |_args| Ok(lae_truncated_icosahedron())),
+        ("rhombicosidodecahedron",      |_args| Ok(lae_rhombicosidodecahedron())),
+        ("truncated-icosidodecahedron", |_args| Ok(lae_truncated_icosidodecahedron())),
+        ("snub-dodecahedron",           |_args| Ok(lae_snub_dodecahedron())),
+        ("geodesic:N",                  |args| Ok(lae_geodesic(args[0]))),
+        ("penrose:N",                   |args| Ok(lae_penrose(args[0]))),
+        ("hat:N",                       |args| Ok(lae_hat(args[0]))),
+        ("hyperbolic:P:Q:DEPTH",        |args| lae_hyperbolic(args[0], args[1], args[2])),
+        ("cube:W:H:D",                  |args| lae_cube(args[0], args[1], args[2], Camera::default())),
+        ("cube:W:H:D:YAW:PITCH",        |args| lae_cube(args[0], args[1], args[2], camera(args[3], args[4]))),
+        ("hexstack:N:D",                |args| lae_hexstack(args[0], args[1], Camera::default())),
+        ("hexstack:N:D:YAW:PITCH",      |args| lae_hexstack(args[0], args[1], camera(args[2], args[3]))),
+        ("voronoi:N:SEED",              |args| Ok(lae_voronoi(args[0], args[1] as u64, 0))),
+        ("voronoi:N:SEED:P",            |args| Ok(lae_voronoi(args[0], args[1] as u64, args[2]))),
+        ("randomgrid:W:H:P:SEED",       |args| Ok(lae_randomgrid(args[0], args[1], args[2], args[3] as u64))),
+        ("planar:N:SEED",               |args| Ok(lae_planar(args[0], args[1] as u64))),
     ]
 }
 
+// Tilings take a vertex configuration like 3.4.6.4 as their first argument
+// (see tiling.rs), and then either the radius of a disc or the width and
+// height of a rectangle. They're parsed separately from board_specs(), whose
+// arguments are all numbers.
+
+const TILING_SPECS: [&str; 2] = ["tiling:C:R", "tiling:C:W:H"];
+
+// The board types that specs can name, written as templates like "grid:W:H".
+
+pub fn board_types() -> Vec<&'static str> {
+    board_specs().into_iter().map(|spec| spec.0).chain(TILING_SPECS).collect()
+}
+
 fn valid_board_err_message() -> String {
     let mut lines = vec!["Valid board types are:"];
-    lines.extend(board_specs().into_iter().map(|spec| spec.0));
+    lines.extend(board_types());
     lines.join("\n  - ")
 }
 
+// Build the board for a spec (see spec.rs for what specs can say). Specs that
+// would make a board Board::new() can't take (a point with no edges, say, or
+// no edges at all) are turned into errors here.
+
 pub fn lae_from_spec(spec: &str) -> Result<Lae, String> {
-    let mut parts = spec.split(":");
-    let name = parts.next().unwrap();
-    let params: Vec<&str> = parts.collect();
+    build_spec(spec).and_then(|lae| checked_lae(spec, lae))
+}
+
+// Build a board of a given type, like "grid" with arguments ["9", "13"]. A
+// board type can have more than one template, as long as they take different
+// numbers of arguments.
+
+pub(crate) fn lae_from_board_type(name: &str, params: &[&str]) -> Result<Lae, String> {
+    if name == "tiling" {
+        return lae_tiling_from_spec(params);
+    }
+
+    let mut arg_counts = vec![];
 
     for template in board_specs() {
         let mut tparts = template.0.split(":");
@@ -49,10 +117,8 @@ pub fn lae_from_spec(spec: &str) -> Result<Lae, String> {
 
         if name == tname {
             if params.len() != tparams.len() {
-                return Err(format!(
-                    "Board type '{}' exists but takes {} arguments ({} given).\n{}",
-                    name, tparams.len(), params.len(), valid_board_err_message()
-                ));
+                arg_counts.push(tparams.len().to_string());
+                continue;
             }
 
             for (index, param) in params.iter().enumerate() {
@@ -64,18 +130,71 @@ pub fn lae_from_spec(spec: &str) -> Result<Lae, String> {
                 }
             }
 
-            let usizes = 
-                params.into_iter()
-                       .map(|s| s.parse().unwrap())
-                       .collect();
+            let usizes: Vec<usize> =
+                params.iter()
+                      .map(|s| s.parse().unwrap())
+                      .collect();
 
-            return Ok((template.1)(usizes));
+            return (template.1)(usizes);
         }
     }
 
+    if !arg_counts.is_empty() {
+        return Err(format!(
+            "Board type '{}' exists but takes {} arguments ({} given).\n{}",
+            name, arg_counts.join(" or "), params.len(), valid_board_err_message()
+        ));
+    }
+
     return Err(format!("Board type '{}' does not exist.\n{}", name, valid_board_err_message()));
 }
 
+fn checked_lae(spec: &str, lae: Lae) -> Result<Lae, String> {
+    let (layout, edges) = &lae;
+
+    if edges.is_empty() {
+        return Err(format!("Board spec '{}' makes a board with no edges.", spec));
+    }
+
+    if let Some(edge) = edges.iter().find(|edge| edge.0 == edge.1 || edge.0.max(edge.1) >= layout.len()) {
+        return Err(format!("Board spec '{}' makes a board with a bad edge {:?}.", spec, edge));
+    }
+
+    if let Some(point) = (0..layout.len()).find(|&p| !edges.iter().any(|&(a, b)| a == p || b == p)) {
+        return Err(format!("Board spec '{}' makes a board where point {} has no edges.", spec, point));
+    }
+
+    Ok(lae)
+}
+
+fn lae_tiling_from_spec(params: &[&str]) -> Result<Lae, String> {
+    if params.len() != 2 && params.len() != 3 {
+        return Err(format!(
+            "Board type 'tiling' exists but takes 2 or 3 arguments ({} given).\n{}",
+            params.len(), valid_board_err_message()
+        ));
+    }
+
+    let mut sizes = vec![];
+
+    for (index, param) in params.iter().enumerate().skip(1) {
+        match param.parse::<f64>() {
+            Ok(size) if size >= 0.0 => sizes.push(size),
+            _ => return Err(format!(
+                "Could not parse board spec arg {} ('{}') as a number.\n{}",
+                index + 1, param, valid_board_err_message()
+            )),
+        }
+    }
+
+    let region = match sizes[..] {
+        [radius] => Region::Disc(radius),
+        _ => Region::Rectangle(sizes[0], sizes[1]),
+    };
+
+    lae_tiling(params[0], region)
+}
+
 // GRID BOARDS
 
 pub fn lae_grid(width: usize, height: usize) -> Lae {
@@ -90,6 +209,63 @@ pub fn lae_grid(width: usize, height: usize) -> Lae {
     layout.standard_lae()
 }
 
+// WRAPPED GRID BOARDS
+//
+// These are grids with extra edges joining opposite sides. The left and right
+// sides are joined on a cylinder, and then the top and bottom too on a torus.
+// A Mobius strip joins the left and right sides with a half twist (the top
+// of one side meets the bottom of the other), and a Klein bottle is a Mobius
+// strip with its top and bottom joined as well. They keep the grid's layout,
+// plus a column of ghosts down the right side (and a row along the bottom,
+// if the top and bottom are joined) showing the points across the wrap. The
+// wrap-around edges that don't reach a ghost are drawn as stubs (see
+// edge_segments()).
+
+pub fn lae_cylinder(width: usize, height: usize) -> Lae {
+    lae_wrapped_grid(width, height, false, false)
+}
+
+pub fn lae_torus(width: usize, height: usize) -> Lae {
+    lae_wrapped_grid(width, height, false, true)
+}
+
+pub fn lae_mobius(width: usize, height: usize) -> Lae {
+    lae_wrapped_grid(width, height, true, false)
+}
+
+pub fn lae_klein(width: usize, height: usize) -> Lae {
+    lae_wrapped_grid(width, height, true, true)
+}
+
+fn lae_wrapped_grid(width: usize, height: usize, twisted: bool, wrap_vertically: bool) -> Lae {
+    let (mut layout, mut edges) = lae_grid(width, height);
+
+    for y in 0..height {
+        let other_y = if twisted {height - 1 - y} else {y};
+        edges.push((y * width + width - 1, other_y * width));
+        layout.add_ghost(other_y * width, (width as f32, y as f32));
+    }
+
+    if wrap_vertically {
+        for x in 0..width {
+            edges.push(((height - 1) * width + x, x));
+            layout.add_ghost(x, (x as f32, height as f32));
+        }
+
+        // The corner ghost only makes sense when the two wraps agree about
+        // which point belongs there, which they don't on a Klein bottle.
+
+        if !twisted {
+            layout.add_ghost(0, (width as f32, height as f32));
+        }
+    }
+
+    // On very small boards some of these edges join a point to itself.
+
+    edges.retain(|&(a, b)| a != b);
+    (layout, edges)
+}
+
 // SQUARE BOARDS
 
 pub fn lae_square(side_len: usize) -> Lae {
@@ -337,6 +513,1262 @@ pub fn lae_pack2() -> Lae {
     (layout, edges)
 }
 
+// POLYHEDRAL BOARDS
+//
+// These are the vertices and edges of the Platonic and Archimedean solids,
+// plus geodesic spheres, which are icosahedra with each face cut into N^2
+// triangles and puffed out onto a sphere. They're finite boards with no sides
+// or corners anywhere. The Platonic cube is called a hexahedron here, to leave "cube"
+// free for cubic lattices.
+//
+// The solids are built from their usual coordinates in 3D, and their edges
+// join the points that are closest together. The layout is a Schlegel
+// diagram: the solid is seen in perspective from just above its largest face,
+// which becomes the outside of the board with the rest of the solid drawn
+// inside it, and with no edges crossing.
+
+type Point3 = [f64; 3];
+type Filter = fn(bool, usize) -> bool;
+
+const PHI: f64 = 1.618033988749895;
+
+// Filters for signed_permutations(), which are given whether a permutation is
+// odd and how many minus signs it has.
+
+const ALL:  Filter = |_odd, _minus| true;
+const EVEN: Filter = |odd, _minus| !odd;
+
+pub fn lae_tetrahedron() -> Lae {
+    lae_solid(signed_permutations(&[[1.0, 1.0, 1.0]], |_odd, minus| minus % 2 == 0))
+}
+
+pub fn lae_hexahedron() -> Lae {
+    lae_solid(signed_permutations(&[[1.0, 1.0, 1.0]], ALL))
+}
+
+pub fn lae_octahedron() -> Lae {
+    lae_solid(signed_permutations(&[[1.0, 0.0, 0.0]], ALL))
+}
+
+pub fn lae_dodecahedron() -> Lae {
+    let mut vertices = signed_permutations(&[[1.0, 1.0, 1.0]], ALL);
+    vertices.extend(signed_permutations(&[[0.0, 1.0 / PHI, PHI]], EVEN));
+    lae_solid(vertices)
+}
+
+pub fn lae_icosahedron() -> Lae {
+    lae_solid(signed_permutations(&[[0.0, 1.0, PHI]], EVEN))
+}
+
+pub fn lae_truncated_tetrahedron() -> Lae {
+    lae_solid(signed_permutations(&[[3.0, 1.0, 1.0]], |_odd, minus| minus % 2 == 0))
+}
+
+pub fn lae_cuboctahedron() -> Lae {
+    lae_solid(signed_permutations(&[[1.0, 1.0, 0.0]], ALL))
+}
+
+pub fn lae_truncated_cube() -> Lae {
+    lae_solid(signed_permutations(&[[2f64.sqrt() - 1.0, 1.0, 1.0]], ALL))
+}
+
+pub fn lae_truncated_octahedron() -> Lae {
+    lae_solid(signed_permutations(&[[0.0, 1.0, 2.0]], ALL))
+}
+
+pub fn lae_rhombicuboctahedron() -> Lae {
+    lae_solid(signed_permutations(&[[1.0, 1.0, 1.0 + 2f64.sqrt()]], ALL))
+}
+
+pub fn lae_truncated_cuboctahedron() -> Lae {
+    let root2 = 2f64.sqrt();
+    lae_solid(signed_permutations(&[[1.0, 1.0 + root2, 1.0 + 2.0 * root2]], ALL))
+}
+
+// The snub cube's coordinates use the tribonacci constant, and they're the
+// even permutations with an even number of plus signs along with the odd
+// permutations with an odd number.
+
+pub fn lae_snub_cube() -> Lae {
+    let root33 = 33f64.sqrt();
+    let t = (1.0 + (19.0 + 3.0 * root33).cbrt() + (19.0 - 3.0 * root33).cbrt()) / 3.0;
+    lae_solid(signed_permutations(&[[1.0, 1.0 / t, t]], |odd, minus| odd == (minus % 2 == 0)))
+}
+
+pub fn lae_icosidodecahedron() -> Lae {
+    let mut vertices = signed_permutations(&[[0.0, 0.0, PHI]], ALL);
+    vertices.extend(signed_permutations(&[[0.5, PHI / 2.0, PHI * PHI / 2.0]], EVEN));
+    lae_solid(vertices)
+}
+
+pub fn lae_truncated_dodecahedron() -> Lae {
+    lae_solid(signed_permutations(&[
+        [0.0,       1.0 / PHI, 2.0 + PHI],
+        [1.0 / PHI, PHI,       2.0 * PHI],
+        [PHI,       2.0,       PHI + 1.0],
+    ], EVEN))
+}
+
+pub fn lae_truncated_icosahedron() -> Lae {
+    lae_solid(signed_permutations(&[
+        [0.0, 1.0,       3.0 * PHI],
+        [1.0, 2.0 + PHI, 2.0 * PHI],
+        [PHI, 2.0,       PHI.powi(3)],
+    ], EVEN))
+}
+
+pub fn lae_rhombicosidodecahedron() -> Lae {
+    lae_solid(signed_permutations(&[
+        [1.0,       1.0, PHI.powi(3)],
+        [PHI * PHI, PHI, 2.0 * PHI],
+        [2.0 + PHI, 0.0, PHI * PHI],
+    ], EVEN))
+}
+
+pub fn lae_truncated_icosidodecahedron() -> Lae {
+    lae_solid(signed_permutations(&[
+        [1.0 / PHI,       1.0 / PHI, 3.0 + PHI],
+        [2.0 / PHI,       PHI,       1.0 + 2.0 * PHI],
+        [1.0 / PHI,       PHI * PHI, 3.0 * PHI - 1.0],
+        [2.0 * PHI - 1.0, 2.0,       2.0 + PHI],
+        [PHI,             3.0,       2.0 * PHI],
+    ], EVEN))
+}
+
+// The snub dodecahedron's coordinates are the even permutations with an even
+// number of plus signs, where xi is the real root of xi^3 - 2 xi = phi.
+
+pub fn lae_snub_dodecahedron() -> Lae {
+    let root = (PHI * PHI / 4.0 - 8.0 / 27.0).sqrt();
+    let xi = (PHI / 2.0 + root).cbrt() + (PHI / 2.0 - root).cbrt();
+    let a = xi - 1.0 / xi;
+    let b = xi * PHI + PHI * PHI + PHI / xi;
+
+    lae_solid(signed_permutations(&[
+        [2.0 * a,                        2.0,                            2.0 * b],
+        [a + b / PHI + PHI,              -a * PHI + b + 1.0 / PHI,       a / PHI + b * PHI - 1.0],
+        [-a / PHI + b * PHI + 1.0,       -a + b / PHI - PHI,             a * PHI + b - 1.0 / PHI],
+        [-a / PHI + b * PHI - 1.0,       a - b / PHI - PHI,              a * PHI + b + 1.0 / PHI],
+        [a + b / PHI - PHI,              a * PHI - b + 1.0 / PHI,        a / PHI + b * PHI + 1.0],
+    ], |odd, minus| !odd && minus % 2 == 1))
+}
+
+// A geodesic sphere, made by cutting each face of an icosahedron into a
+// triangular grid of side N and pushing the grid's points out onto the
+// icosahedron's circumsphere.
+
+pub fn lae_geodesic(n: usize) -> Lae {
+    if n == 0 {
+        return (Layout::new(), Edges::new());
+    }
+
+    let corners = signed_permutations(&[[0.0, 1.0, PHI]], EVEN);
+    let radius = norm(corners[0]);
+    let mut vertices: Vec<Point3> = vec![];
+    let mut edges = Edges::new();
+
+    for face in polyhedron_faces(&corners, &nearest_pairs(&corners)) {
+        let (a, b, c) = (corners[face[0]], corners[face[1]], corners[face[2]]);
+
+        // The indices of the grid points on this face, by their position
+        // along the sides a-b and a-c.
+
+        let mut grid: Vec<Vec<usize>> = vec![];
+
+        for i in 0..=n {
+            grid.push((0..=n - i).map(|j| {
+                let along_b = scale(sub(b, a), i as f64 / n as f64);
+                let along_c = scale(sub(c, a), j as f64 / n as f64);
+                let point = scale(normalize(add(a, add(along_b, along_c))), radius);
+                index_of(&mut vertices, point)
+            }).collect());
+        }
+
+        for i in 0..n {
+            for j in 0..n - i {
+                edges.push((grid[i][j], grid[i + 1][j]));
+                edges.push((grid[i][j], grid[i][j + 1]));
+                edges.push((grid[i + 1][j], grid[i][j + 1]));
+            }
+        }
+    }
+
+    // The edges along the icosahedron's edges were added by both faces.
+
+    for edge in edges.iter_mut() {
+        *edge = (usize::min(edge.0, edge.1), usize::max(edge.0, edge.1));
+    }
+
+    edges.sort();
+    edges.dedup();
+    schlegel_lae(&vertices, edges)
+}
+
+fn lae_solid(vertices: Vec<Point3>) -> Lae {
+    let edges = nearest_pairs(&vertices);
+    schlegel_lae(&vertices, edges)
+}
+
+// All the points given by permuting the coordinates of some points and
+// changing their signs, keeping the ones that a filter accepts (see ALL and
+// EVEN), with duplicates removed.
+
+fn signed_permutations(bases: &[Point3], keep: Filter) -> Vec<Point3> {
+    let permutations = [
+        ([0, 1, 2], false), ([1, 2, 0], false), ([2, 0, 1], false),
+        ([0, 2, 1], true),  ([2, 1, 0], true),  ([1, 0, 2], true),
+    ];
+
+    let mut points = vec![];
+
+    for base in bases {
+        for (order, odd) in permutations {
+            for signs in 0..8usize {
+                if keep(odd, signs.count_ones() as usize) {
+                    let sign = |k: usize| if signs & (1 << k) != 0 {-1.0} else {1.0};
+                    let point = [0, 1, 2].map(|k| sign(k) * base[order[k]]);
+                    index_of(&mut points, point);
+                }
+            }
+        }
+    }
+
+    points
+}
+
+// The index of a point in a list, adding it to the end if it isn't there.
+
+fn index_of(points: &mut Vec<Point3>, point: Point3) -> usize {
+    match points.iter().position(|&p| norm(sub(p, point)) < 1e-9) {
+        Some(index) => index,
+        None => {
+            points.push(point);
+            points.len() - 1
+        }
+    }
+}
+
+// The pairs of points that are as close together as any two points are.
+
+fn nearest_pairs(points: &[Point3]) -> Edges {
+    let mut pairs = vec![];
+    let mut min_dist = f64::INFINITY;
+
+    for a in 0..points.len() {
+        for b in a + 1..points.len() {
+            let dist = norm(sub(points[a], points[b]));
+            if dist < min_dist * (1.0 - 1e-6) {
+                min_dist = dist;
+                pairs.clear();
+            }
+            if dist < min_dist * (1.0 + 1e-6) {
+                pairs.push((a, b));
+            }
+        }
+    }
+
+    pairs
+}
+
+// The faces of a convex polyhedron centered on the origin, as lists of
+// points. Each point's neighbors are put in order around it, and then each
+// face is found by walking around its edges, always turning the same way.
+
+fn polyhedron_faces(points: &[Point3], edges: &Edges) -> Vec<Vec<usize>> {
+    let mut around = vec![vec![]; points.len()];
+
+    for &(a, b) in edges {
+        around[a].push(b);
+        around[b].push(a);
+    }
+
+    for (p, neighbors) in around.iter_mut().enumerate() {
+        let up = normalize(points[p]);
+        let first = sub(points[neighbors[0]], points[p]);
+        let x = sub(first, scale(up, dot(first, up)));
+        let y = cross(up, x);
+        let angle = |n: &usize| {
+            let offset = sub(points[*n], points[p]);
+            f64::atan2(dot(offset, y), dot(offset, x))
+        };
+
+        neighbors.sort_by(|a, b| angle(a).partial_cmp(&angle(b)).unwrap());
+    }
+
+    let mut walked = HashSet::new();
+    let mut faces = vec![];
+
+    for &(a, b) in edges {
+        for (mut from, mut to) in [(a, b), (b, a)] {
+            let mut face = vec![];
+
+            while walked.insert((from, to)) {
+                face.push(from);
+                let index = around[to].iter().position(|&n| n == from).unwrap();
+                (from, to) = (to, around[to][(index + 1) % around[to].len()]);
+            }
+
+            if !face.is_empty() {
+                faces.push(face);
+            }
+        }
+    }
+
+    faces
+}
+
+// Lay out a convex polyhedron as a Schlegel diagram, looking down on its
+// largest face (the one whose plane is closest to the center). The eye has to
+// be below the lowest point from which another face would come into view, and
+// of the heights tried, the one that spreads the points out most evenly is
+// used. The layout is scaled so that the closest points are 1 apart.
+
+fn schlegel_lae(points: &[Point3], edges: Edges) -> Lae {
+    let faces = polyhedron_faces(points, &edges);
+    let planes: Vec<(Point3, Point3, f64)> =
+        faces.iter().map(|face| {
+            let centroid = scale(face.iter().fold([0.0; 3], |sum, &p| add(sum, points[p])), 1.0 / face.len() as f64);
+            let mut normal = normalize(face.iter().enumerate().fold([0.0; 3], |sum, (i, &p)| {
+                add(sum, cross(points[p], points[face[(i + 1) % face.len()]]))
+            }));
+
+            if dot(normal, centroid) < 0.0 {
+                normal = scale(normal, -1.0);
+            }
+
+            (centroid, normal, dot(normal, centroid))
+        }).collect();
+
+    let outer = (0..planes.len()).fold(0, |best, f| if planes[f].2 < planes[best].2 - 1e-9 {f} else {best});
+    let (center, normal, _) = planes[outer];
+
+    let ceiling =
+        planes.iter()
+              .filter(|plane| dot(plane.1, normal) > 1e-9)
+              .map(|plane| (plane.2 - dot(plane.1, center)) / dot(plane.1, normal))
+              .filter(|&height| height > 1e-9)
+              .fold(2.0 * norm(points[0]), f64::min);
+
+    let x = normalize(sub(points[faces[outer][0]], center));
+    let y = cross(normal, x);
+
+    let project = |height: f64| -> Layout {
+        let eye = add(center, scale(normal, height));
+        let layout: Layout = points.iter().map(|&p| {
+            let projected = add(eye, scale(sub(p, eye), height / dot(normal, sub(eye, p))));
+            let offset = sub(projected, center);
+            (dot(offset, x) as f32, dot(offset, y) as f32)
+        }).collect();
+
+        let separation = layout.min_point_separation();
+        layout.scale(1.0 / separation)
+    };
+
+    let spread = |layout: &Layout| {
+        let (left, right, top, bottom) = layout.bounds();
+        f32::max(right - left, bottom - top)
+    };
+
+    let layout =
+        (1..10).map(|tenths| project(ceiling * tenths as f64 / 10.0))
+               .reduce(|best, layout| if spread(&layout) < spread(&best) {layout} else {best})
+               .unwrap();
+
+    (layout, edges)
+}
+
+// Vector arithmetic in 3D.
+
+fn add(a: Point3, b: Point3) -> Point3 {[a[0] + b[0], a[1] + b[1], a[2] + b[2]]}
+fn sub(a: Point3, b: Point3) -> Point3 {[a[0] - b[0], a[1] - b[1], a[2] - b[2]]}
+fn scale(a: Point3, k: f64) -> Point3 {[a[0] * k, a[1] * k, a[2] * k]}
+fn dot(a: Point3, b: Point3) -> f64 {a[0] * b[0] + a[1] * b[1] + a[2] * b[2]}
+fn norm(a: Point3) -> f64 {dot(a, a).sqrt()}
+fn normalize(a: Point3) -> Point3 {scale(a, 1.0 / norm(a))}
+
+fn cross(a: Point3, b: Point3) -> Point3 {
+    [a[1] * b[2] - a[2] * b[1],
+     a[2] * b[0] - a[0] * b[2],
+     a[0] * b[1] - a[1] * b[0]]
+}
+
+// APERIODIC BOARDS
+//
+// These are patches of tilings that never repeat, built by substitution: a
+// few big tiles are cut into smaller tiles of the same kinds, over and over.
+// The points of the board are the corners of the tiles and the edges are
+// their sides.
+//
+// Penrose boards use rhombs (the P3 tiling), made from Robinson triangles
+// (half-rhombs) by deflation. A penrose:N board is a sun of five thick rhombs
+// deflated N times, so penrose:0 is the sun itself.
+//
+// Hat boards use the hat, the 13-sided shape found in 2023 that tiles the
+// plane only aperiodically. The hats are built from the H, T, P and F
+// metatiles of Smith, Myers, Kaplan and Goodman-Strauss, each of which is cut
+// into a patch of smaller metatiles until there are enough hats. A hat:N board
+// is the N hats nearest the middle of that patch.
+
+type Affine = [f64; 6];
+
+// A Robinson triangle is (thick, apex, base corner, base corner), where the
+// sides from the apex are sides of a rhomb, and the base is its diagonal.
+
+type Triangle = (bool, (f64, f64), (f64, f64), (f64, f64));
+
+const HALF_ROOT3: f64 = 0.8660254037844386;
+const IDENTITY: Affine = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+
+pub fn lae_penrose(n: usize) -> Lae {
+    let side = PHI.powi(n as i32);
+    let corner = |k: usize, length: f64| {
+        let angle = k as f64 * PI / 5.0;
+        (length * angle.cos(), length * angle.sin())
+    };
+
+    // Each rhomb of the sun is two thick triangles sharing the diagonal from
+    // the middle to its far corner.
+
+    let mut triangles: Vec<Triangle> = (0..5).flat_map(|k| {
+        let (middle, far) = ((0.0, 0.0), corner(2 * k, side * PHI));
+        [(true, corner(2 * k + 9, side), middle, far), (true, corner(2 * k + 1, side), middle, far)]
+    }).collect();
+
+    let between = |a: (f64, f64), b: (f64, f64), t: f64| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+
+    for _ in 0..n {
+        triangles = triangles.into_iter().flat_map(|(thick, a, b, c)| {
+            if thick {
+                let q = between(b, a, 1.0 / PHI);
+                let r = between(b, c, 1.0 / PHI);
+                vec![(true, r, c, a), (true, q, r, b), (false, r, q, a)]
+            } else {
+                let p = between(a, b, 1.0 / PHI);
+                vec![(false, c, p, b), (true, p, c, a)]
+            }
+        }).collect();
+    }
+
+    let mut points: Vec<(f64, f64)> = vec![];
+    let mut index = |point: (f64, f64)| {
+        match points.iter().position(|&p| distance2(p, point) < 1e-6) {
+            Some(index) => index,
+            None => {
+                points.push(point);
+                points.len() - 1
+            }
+        }
+    };
+
+    // A triangle on the edge of the patch can be missing the other half of its
+    // rhomb, and then its base isn't a side of any tile, so it's left out.
+
+    let mut apexes: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+
+    for (_, a, b, c) in triangles {
+        let (a, b, c) = (index(a), index(b), index(c));
+        apexes.entry((b.min(c), b.max(c))).or_default().push(a);
+    }
+
+    let mut edges = Edges::new();
+
+    for ((b, c), apexes) in apexes {
+        if apexes.len() == 2 {
+            for a in apexes {
+                edges.push((a.min(b), a.max(b)));
+                edges.push((a.min(c), a.max(c)));
+            }
+        }
+    }
+
+    edges.sort();
+    edges.dedup();
+
+    let mut corners: Vec<usize> = edges.iter().flat_map(|&(a, b)| [a, b]).collect();
+    corners.sort();
+    corners.dedup();
+
+    let layout: Layout = points.into_iter().map(|(x, y)| (x as f32, y as f32)).collect();
+    lae_induced((layout, edges), &corners)
+}
+
+pub fn lae_hat(n: usize) -> Lae {
+    let mut metatiles = [h_metatile(), t_metatile(), p_metatile(), f_metatile()];
+
+    let mut hats = loop {
+        let patch = metatile_patch(&metatiles);
+        let mut hats = vec![];
+        patch.collect_hats(IDENTITY, &mut hats);
+
+        if hats.len() >= 3 * n {
+            break hats;
+        }
+
+        metatiles = bigger_metatiles(&patch);
+    };
+
+    let center = |hat: &Vec<(f64, f64)>| {
+        let count = hat.len() as f64;
+        (hat.iter().map(|p| p.0).sum::<f64>() / count, hat.iter().map(|p| p.1).sum::<f64>() / count)
+    };
+
+    let centers: Vec<(f64, f64)> = hats.iter().map(center).collect();
+    let middle = center(&centers);
+    hats.sort_by(|a, b| distance2(center(a), middle).partial_cmp(&distance2(center(b), middle)).unwrap());
+
+    // The side of the hat from corner 2 to corner 3 is two sides of the kites
+    // it's made of, and the corner in between can be a corner of another hat,
+    // so it's always a point on the board.
+
+    let mut points: Vec<(f64, f64)> = vec![];
+    let mut edges = Edges::new();
+
+    for hat in hats.into_iter().take(n) {
+        let mut corners = hat.clone();
+        corners.insert(3, between_points(hat[2], hat[3]));
+
+        let indices: Vec<usize> = corners.into_iter().map(|corner| {
+            match points.iter().position(|&p| distance2(p, corner) < 1e-6) {
+                Some(index) => index,
+                None => {
+                    points.push(corner);
+                    points.len() - 1
+                }
+            }
+        }).collect();
+
+        for k in 0..indices.len() {
+            let (a, b) = (indices[k], indices[(k + 1) % indices.len()]);
+            edges.push((a.min(b), a.max(b)));
+        }
+    }
+
+    edges.sort();
+    edges.dedup();
+
+    (scaled_layout(&points), edges)
+}
+
+// A metatile is an outline and the tiles it's cut into, each placed by an
+// affine map. A hat is a metatile that isn't cut any further.
+
+struct Metatile {
+    outline:  Vec<(f64, f64)>,
+    children: Vec<(Affine, Rc<Metatile>)>,
+}
+
+impl Metatile {
+    fn new(outline: Vec<(f64, f64)>) -> Metatile {
+        Metatile {outline: outline, children: vec![]}
+    }
+
+    fn hat() -> Rc<Metatile> {
+        let corners = [
+            (0, 0), (-1, -1), (0, -2), (2, -2), (2, -1), (4, -2), (5, -1),
+            (4, 0), (3, 0), (2, 2), (0, 3), (0, 2), (-1, 2),
+        ];
+
+        Rc::new(Metatile::new(corners.iter().map(|&(x, y)| hex_point(x as f64, y as f64)).collect()))
+    }
+
+    // Corner K of child C, where the metatile is.
+
+    fn corner(&self, child: usize, k: usize) -> (f64, f64) {
+        let (transform, tile) = &self.children[child];
+        apply(transform, tile.outline[k])
+    }
+
+    // Move the metatile so its outline is centered on the origin.
+
+    fn recenter(&mut self) {
+        let count = self.outline.len() as f64;
+        let cx = self.outline.iter().map(|p| p.0).sum::<f64>() / count;
+        let cy = self.outline.iter().map(|p| p.1).sum::<f64>() / count;
+
+        for point in self.outline.iter_mut() {
+            *point = (point.0 - cx, point.1 - cy);
+        }
+
+        for child in self.children.iter_mut() {
+            child.0 = compose(&translation(-cx, -cy), &child.0);
+        }
+    }
+
+    fn collect_hats(&self, transform: Affine, hats: &mut Vec<Vec<(f64, f64)>>) {
+        if self.children.is_empty() {
+            hats.push(self.outline.iter().map(|&p| apply(&transform, p)).collect());
+        }
+
+        for (child_transform, child) in &self.children {
+            child.collect_hats(compose(&transform, child_transform), hats);
+        }
+    }
+}
+
+fn h_metatile() -> Rc<Metatile> {
+    let hat = Metatile::hat();
+    let outline = vec![
+        (0.0, 0.0), (4.0, 0.0), (4.5, HALF_ROOT3),
+        (2.5, 5.0 * HALF_ROOT3), (1.5, 5.0 * HALF_ROOT3), (-0.5, HALF_ROOT3),
+    ];
+
+    let h = &hat.outline;
+    let reflected = compose(&translation(2.5, HALF_ROOT3),
+                            &compose(&[-0.5, -HALF_ROOT3, 0.0, HALF_ROOT3, -0.5, 0.0],
+                                     &[0.5, 0.0, 0.0, 0.0, -0.5, 0.0]));
+
+    let children = vec![
+        (match_two(h[5], h[7], outline[5], outline[0]), hat.clone()),
+        (match_two(h[9], h[11], outline[1], outline[2]), hat.clone()),
+        (match_two(h[5], h[7], outline[3], outline[4]), hat.clone()),
+        (reflected, hat.clone()),
+    ];
+
+    Rc::new(Metatile {outline: outline, children: children})
+}
+
+fn t_metatile() -> Rc<Metatile> {
+    let outline = vec![(0.0, 0.0), (3.0, 0.0), (1.5, 3.0 * HALF_ROOT3)];
+    let children = vec![([0.5, 0.0, 0.5, 0.0, 0.5, HALF_ROOT3], Metatile::hat())];
+
+    Rc::new(Metatile {outline: outline, children: children})
+}
+
+fn p_metatile() -> Rc<Metatile> {
+    let outline = vec![(0.0, 0.0), (4.0, 0.0), (3.0, 2.0 * HALF_ROOT3), (-1.0, 2.0 * HALF_ROOT3)];
+    Rc::new(Metatile {outline: outline, children: hat_pair()})
+}
+
+fn f_metatile() -> Rc<Metatile> {
+    let outline = vec![
+        (0.0, 0.0), (3.0, 0.0), (3.5, HALF_ROOT3), (3.0, 2.0 * HALF_ROOT3), (-1.0, 2.0 * HALF_ROOT3),
+    ];
+
+    Rc::new(Metatile {outline: outline, children: hat_pair()})
+}
+
+// The two hats in a P or F metatile.
+
+fn hat_pair() -> Vec<(Affine, Rc<Metatile>)> {
+    let hat = Metatile::hat();
+    let turned = compose(&translation(0.0, 2.0 * HALF_ROOT3),
+                         &compose(&[0.5, HALF_ROOT3, 0.0, -HALF_ROOT3, 0.5, 0.0],
+                                  &[0.5, 0.0, 0.0, 0.0, 0.5, 0.0]));
+
+    vec![([0.5, 0.0, 1.5, 0.0, 0.5, HALF_ROOT3], hat.clone()), (turned, hat)]
+}
+
+// How to build a patch of 29 metatiles, after starting with an H. Each rule
+// (A, I, Some((B, J)), S, K) adds a metatile of shape S (one of "HTPF") whose
+// side K goes from corner J of metatile B to corner I of metatile A; without
+// B and J, the side runs backwards along side I of metatile A.
+
+type PatchRule = (usize, usize, Option<(usize, usize)>, char, usize);
+
+const PATCH_RULES: [PatchRule; 28] = [
+    (0,  0, None,          'P', 2),
+    (1,  0, None,          'H', 2),
+    (2,  0, None,          'P', 2),
+    (3,  0, None,          'H', 2),
+    (4,  4, None,          'P', 2),
+    (0,  4, None,          'F', 3),
+    (2,  4, None,          'F', 3),
+    (4,  1, Some((3, 2)),  'F', 0),
+    (8,  3, None,          'H', 0),
+    (9,  2, None,          'P', 0),
+    (10, 2, None,          'H', 0),
+    (11, 4, None,          'P', 2),
+    (12, 0, None,          'H', 2),
+    (13, 0, None,          'F', 3),
+    (14, 2, None,          'F', 1),
+    (15, 3, None,          'H', 4),
+    (8,  2, None,          'F', 1),
+    (17, 3, None,          'H', 0),
+    (18, 2, None,          'P', 0),
+    (19, 2, None,          'H', 2),
+    (20, 4, None,          'F', 3),
+    (20, 0, None,          'P', 2),
+    (22, 0, None,          'H', 2),
+    (23, 4, None,          'F', 3),
+    (23, 0, None,          'F', 3),
+    (16, 0, None,          'P', 2),
+    (9,  4, Some((0, 2)),  'T', 2),
+    (4,  0, None,          'F', 3),
+];
+
+fn metatile_patch(metatiles: &[Rc<Metatile>; 4]) -> Metatile {
+    let shape = |name: char| metatiles["HTPF".find(name).unwrap()].clone();
+    let mut patch = Metatile::new(vec![]);
+    patch.children.push((IDENTITY, shape('H')));
+
+    for (a, i, other, name, k) in PATCH_RULES {
+        let (p, q) = match other {
+            Some((b, j)) => (patch.corner(b, j), patch.corner(a, i)),
+            None => {
+                let len = patch.children[a].1.outline.len();
+                (patch.corner(a, (i + 1) % len), patch.corner(a, i))
+            }
+        };
+
+        let tile = shape(name);
+        let side = (tile.outline[k], tile.outline[(k + 1) % tile.outline.len()]);
+        patch.children.push((match_two(side.0, side.1, p, q), tile));
+    }
+
+    patch
+}
+
+// The next size up of H, T, P and F metatiles, made from parts of a patch.
+
+fn bigger_metatiles(patch: &Metatile) -> [Rc<Metatile>; 4] {
+    let bps1 = patch.corner(8, 2);
+    let bps2 = patch.corner(21, 2);
+    let rbps = apply(&rotation_about(bps1, -2.0 * PI / 3.0), bps2);
+
+    let p72 = patch.corner(7, 2);
+    let p252 = patch.corner(25, 2);
+
+    let llc = intersection(bps1, rbps, patch.corner(6, 2), p72);
+    let mut w = sub2(patch.corner(6, 2), llc);
+
+    let mut h_outline = vec![llc, bps1];
+    w = apply(&rotation(-PI / 3.0), w);
+    h_outline.push(add2(h_outline[1], w));
+    h_outline.push(patch.corner(14, 2));
+    w = apply(&rotation(-PI / 3.0), w);
+    h_outline.push(sub2(h_outline[3], w));
+    h_outline.push(patch.corner(6, 2));
+
+    let p_outline = vec![p72, add2(p72, sub2(bps1, llc)), bps1, llc];
+
+    let f_outline = vec![
+        bps2, patch.corner(24, 2), patch.corner(25, 0), p252, add2(p252, sub2(llc, bps1)),
+    ];
+
+    let aaa = h_outline[2];
+    let bbb = add2(h_outline[1], sub2(h_outline[4], h_outline[5]));
+    let ccc = apply(&rotation_about(bbb, -PI / 3.0), aaa);
+    let t_outline = vec![bbb, ccc, aaa];
+
+    let part = |outline: Vec<(f64, f64)>, children: &[usize]| {
+        let mut metatile = Metatile::new(outline);
+        metatile.children = children.iter().map(|&c| patch.children[c].clone()).collect();
+        metatile.recenter();
+        Rc::new(metatile)
+    };
+
+    [
+        part(h_outline, &[0, 9, 16, 27, 26, 6, 1, 8, 10, 15]),
+        part(t_outline, &[11]),
+        part(p_outline, &[7, 2, 3, 4, 28]),
+        part(f_outline, &[21, 20, 22, 23, 24, 25]),
+    ]
+}
+
+// Points on a grid of triangles, with sides of length 1.
+
+fn hex_point(x: f64, y: f64) -> (f64, f64) {
+    (x + 0.5 * y, HALF_ROOT3 * y)
+}
+
+// Affine maps, where [a, b, c, d, e, f] takes (x, y) to
+// (ax + by + c, dx + ey + f).
+
+fn apply(m: &Affine, p: (f64, f64)) -> (f64, f64) {
+    (m[0] * p.0 + m[1] * p.1 + m[2], m[3] * p.0 + m[4] * p.1 + m[5])
+}
+
+fn compose(a: &Affine, b: &Affine) -> Affine {
+    [a[0] * b[0] + a[1] * b[3], a[0] * b[1] + a[1] * b[4], a[0] * b[2] + a[1] * b[5] + a[2],
+     a[3] * b[0] + a[4] * b[3], a[3] * b[1] + a[4] * b[4], a[3] * b[2] + a[4] * b[5] + a[5]]
+}
+
+fn invert(m: &Affine) -> Affine {
+    let det = m[0] * m[4] - m[1] * m[3];
+    [m[4] / det, -m[1] / det, (m[1] * m[5] - m[2] * m[4]) / det,
+     -m[3] / det, m[0] / det, (m[2] * m[3] - m[0] * m[5]) / det]
+}
+
+fn translation(x: f64, y: f64) -> Affine {
+    [1.0, 0.0, x, 0.0, 1.0, y]
+}
+
+fn rotation(angle: f64) -> Affine {
+    [angle.cos(), -angle.sin(), 0.0, angle.sin(), angle.cos(), 0.0]
+}
+
+fn rotation_about(p: (f64, f64), angle: f64) -> Affine {
+    compose(&translation(p.0, p.1), &compose(&rotation(angle), &translation(-p.0, -p.1)))
+}
+
+// The map taking (0, 0) to P and (1, 0) to Q, turning and scaling without
+// reflecting.
+
+fn match_segment(p: (f64, f64), q: (f64, f64)) -> Affine {
+    [q.0 - p.0, p.1 - q.1, p.0, q.1 - p.1, q.0 - p.0, p.1]
+}
+
+// The map taking P1 to P2 and Q1 to Q2.
+
+fn match_two(p1: (f64, f64), q1: (f64, f64), p2: (f64, f64), q2: (f64, f64)) -> Affine {
+    compose(&match_segment(p2, q2), &invert(&match_segment(p1, q1)))
+}
+
+// Where the line through P1 and Q1 meets the line through P2 and Q2.
+
+fn intersection(p1: (f64, f64), q1: (f64, f64), p2: (f64, f64), q2: (f64, f64)) -> (f64, f64) {
+    let d = (q2.1 - p2.1) * (q1.0 - p1.0) - (q2.0 - p2.0) * (q1.1 - p1.1);
+    let u = ((q2.0 - p2.0) * (p1.1 - p2.1) - (q2.1 - p2.1) * (p1.0 - p2.0)) / d;
+    (p1.0 + u * (q1.0 - p1.0), p1.1 + u * (q1.1 - p1.1))
+}
+
+fn add2(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {(a.0 + b.0, a.1 + b.1)}
+fn sub2(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {(a.0 - b.0, a.1 - b.1)}
+fn between_points(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)}
+
+// HYPERBOLIC BOARDS
+//
+// These are patches of the {P,Q} tiling of the hyperbolic plane, where Q
+// regular P-gons meet at every corner. That only works when (P - 2)(Q - 2) is
+// more than 4; otherwise the tiles fit on a sphere or a flat plane instead,
+// and the spec is an error. The patch starts with one tile, and each of its
+// DEPTH layers adds every tile that touches a corner of the patch so far.
+//
+// The layout is the Poincaré disk, where the hyperbolic plane is squeezed into
+// a disk and the tiles shrink towards its rim. Edges are drawn straight rather
+// than as arcs of circles, and the layout is scaled so the sides of the middle
+// tile have length 1. The tiles near the rim are much smaller, so they're best
+// viewed with local stone sizing (see the analyzer's --local-sizing flag).
+// Deep enough layers would be too close to the rim for their corners to be
+// told apart, so a patch that gets closer than MIN_RIM_GAP is an error too.
+
+pub const MIN_RIM_GAP: f64 = 1e-6;
+
+pub fn lae_hyperbolic(p: usize, q: usize, depth: usize) -> Result<Lae, String> {
+    if p < 3 || q < 3 {
+        return Err(format!("Hyperbolic board {{{},{}}} needs polygons with at least 3 sides, at least 3 to a corner.", p, q));
+    }
+
+    if (p - 2) * (q - 2) <= 4 {
+        return Err(format!(
+            "Hyperbolic board {{{},{}}} isn't hyperbolic: {} {}-gons to a corner tile a sphere or the flat plane \
+             ((P - 2)(Q - 2) must be more than 4).", p, q, q, p
+        ));
+    }
+
+    // The corners of the middle tile are a hyperbolic distance R from its
+    // middle, which is tanh(R/2) from the center of the disk.
+
+    let cosh_r = 1.0 / ((PI / p as f64).tan() * (PI / q as f64).tan());
+    let radius = (cosh_r.acosh() / 2.0).tanh();
+
+    let first: Vec<(f64, f64)> = (0..p).map(|k| {
+        let angle = 2.0 * PI * k as f64 / p as f64;
+        (radius * angle.cos(), radius * angle.sin())
+    }).collect();
+
+    let mut tiles = vec![((0.0, 0.0), first.clone())];
+    let mut points = first;
+
+    // Every reflection adds to the rounding errors of the tile it starts from,
+    // so the tiles are visited in the order they were found, and each new tile
+    // is reflected from one as close to the middle as possible.
+
+    for _ in 0..depth {
+        let old_points = points.clone();
+        let mut tile = 0;
+
+        while tile < tiles.len() {
+            let (center, corners) = tiles[tile].clone();
+            tile += 1;
+
+            for k in 0..p {
+                let mirror = |z| geodesic_reflection(corners[k], corners[(k + 1) % p], z);
+                let new_center = mirror(center);
+
+                if tiles.iter().any(|t| same_disk_point(t.0, new_center)) {
+                    continue;
+                }
+
+                let new_corners: Vec<(f64, f64)> = corners.iter().map(|&z| mirror(z)).collect();
+
+                if new_corners.iter().any(|&z| old_points.iter().any(|&o| same_disk_point(o, z))) {
+                    if new_corners.iter().any(|&z| 1.0 - distance2(z, (0.0, 0.0)).sqrt() < MIN_RIM_GAP) {
+                        return Err(format!(
+                            "Hyperbolic board {{{},{}}} at depth {} reaches too close to the rim of the disk to draw.",
+                            p, q, depth
+                        ));
+                    }
+
+                    for &corner in &new_corners {
+                        if !points.iter().any(|&o| same_disk_point(o, corner)) {
+                            points.push(corner);
+                        }
+                    }
+
+                    tiles.push((new_center, new_corners));
+                }
+            }
+        }
+    }
+
+    let index = |z: (f64, f64)| points.iter().position(|&o| same_disk_point(o, z)).unwrap();
+    let mut edges = Edges::new();
+
+    for (_, corners) in &tiles {
+        for k in 0..p {
+            let (a, b) = (index(corners[k]), index(corners[(k + 1) % p]));
+            edges.push((a.min(b), a.max(b)));
+        }
+    }
+
+    edges.sort();
+    edges.dedup();
+
+    let side = distance2(points[0], points[1]).sqrt();
+    let layout = points.iter().map(|&(x, y)| ((x / side) as f32, (y / side) as f32)).collect();
+
+    Ok((layout, edges))
+}
+
+// Reflect a point in the Poincaré disk across the geodesic through A and B.
+// The Möbius map z -> (z - A)/(1 - conj(A) z) moves A to the center of the
+// disk and the geodesic to a line through the center, where the reflection is
+// easy, and then its inverse moves everything back. Working out the circle the
+// geodesic lies on instead loses too much precision near the rim.
+
+fn geodesic_reflection(a: (f64, f64), b: (f64, f64), z: (f64, f64)) -> (f64, f64) {
+    let mul = |u: (f64, f64), v: (f64, f64)| (u.0 * v.0 - u.1 * v.1, u.0 * v.1 + u.1 * v.0);
+    let div = |u: (f64, f64), v: (f64, f64)| {
+        let norm2 = v.0 * v.0 + v.1 * v.1;
+        ((u.0 * v.0 + u.1 * v.1) / norm2, (u.1 * v.0 - u.0 * v.1) / norm2)
+    };
+    let conj = |u: (f64, f64)| (u.0, -u.1);
+
+    let to_center = |u| div(sub2(u, a), sub2((1.0, 0.0), mul(conj(a), u)));
+    let from_center = |u| div(add2(u, a), add2((1.0, 0.0), mul(conj(a), u)));
+
+    let w = to_center(b);
+    let turn = div(mul(w, w), (w.0 * w.0 + w.1 * w.1, 0.0));
+
+    from_center(mul(turn, conj(to_center(z))))
+}
+
+// Whether two points in the Poincaré disk are the same, allowing for rounding
+// errors. Points are compared by their distance in the hyperbolic plane, since
+// the tiles shrink towards the rim until distinct corners are closer than any
+// fixed distance in the disk.
+
+fn same_disk_point(a: (f64, f64), b: (f64, f64)) -> bool {
+    let norm2 = |z: (f64, f64)| z.0 * z.0 + z.1 * z.1;
+    2.0 * distance2(a, b) / ((1.0 - norm2(a)) * (1.0 - norm2(b))) < 1e-4
+}
+
+// 3D BOARDS
+//
+// These boards are stacks of layers, where each point is also joined to the
+// same point in the layers above and below it. Their layouts are the 3D
+// points, with each layer one unit above the last, as seen by a camera: the
+// default one, or one given in the spec by its yaw and pitch in degrees. The
+// layouts keep the 3D points too, for other views, and the analyzer can show
+// the layers one at a time. A camera that would draw two points closer than
+// MIN_CAMERA_SEPARATION apart (looking straight down, say, or straight along
+// the rows) hides some of them behind others, so it's an error.
+
+pub const MIN_CAMERA_SEPARATION: f32 = 0.01;
+
+pub fn lae_cube(width: usize, height: usize, depth: usize, camera: Camera) -> Result<Lae, String> {
+    lae_stacked(lae_grid(width, height), depth, camera)
+}
+
+pub fn lae_hexstack(layers: usize, depth: usize, camera: Camera) -> Result<Lae, String> {
+    lae_stacked(lae_trihex(layers), depth, camera)
+}
+
+fn camera(yaw: usize, pitch: usize) -> Camera {
+    Camera {yaw: yaw as f32 / 360.0, pitch: pitch as f32 / 360.0}
+}
+
+fn lae_stacked((layout, edges): Lae, depth: usize, camera: Camera) -> Result<Lae, String> {
+    let n = layout.len();
+    let mut points = vec![];
+    let mut stacked_edges = Edges::new();
+
+    for z in 0..depth {
+        points.extend(layout.iter().map(|&(x, y)| (x, y, z as f32)));
+        stacked_edges.extend(edges.iter().map(|&(a, b)| (z * n + a, z * n + b)));
+
+        if z > 0 {
+            stacked_edges.extend((0..n).map(|point| ((z - 1) * n + point, z * n + point)));
+        }
+    }
+
+    let projected = camera.project(&points);
+
+    for (a, &(xa, ya)) in projected.iter().enumerate() {
+        for (b, &(xb, yb)) in projected.iter().enumerate().skip(a + 1) {
+            if f32::hypot(xa - xb, ya - yb) < MIN_CAMERA_SEPARATION {
+                return Err(format!(
+                    "The camera draws points {} and {} of the board on top of each other; try another yaw and pitch.",
+                    a, b
+                ));
+            }
+        }
+    }
+
+    Ok((projected, stacked_edges))
+}
+
+// RANDOM BOARDS
+//
+// These boards are generated from a seed, so the same spec always gives the
+// same board. The random numbers come from the SplitMix64 in bench.rs rather
+// than from rand, whose generators can change between versions. The points
+// for voronoi and planar boards are scattered over a square by best-candidate
+// sampling (each new point is the one, out of a few random tries, furthest
+// from the points so far), which spreads them out more evenly than plain
+// random points would, and the layout is scaled so the closest two are 1
+// apart. Every board is connected.
+
+const SAMPLING_TRIES: usize = 10;
+
+// The Delaunay triangulation of N random points, whose edges join the points
+// whose Voronoi cells touch. P percent of the edges are then removed, longest
+// first, skipping any that would leave the board in pieces; the longest edges
+// are the thin triangles around the outside.
+
+pub fn lae_voronoi(n: usize, seed: u64, percent: usize) -> Lae {
+    let mut rng = SplitMix64(seed);
+    let points = random_points(n, &mut rng);
+    let mut edges = delaunay_edges(&points);
+
+    let length = |&(a, b): &(usize, usize)| distance2(points[a], points[b]);
+    edges.sort_by(|a, b| length(b).partial_cmp(&length(a)).unwrap());
+    let edges = remove_edges(n, edges, percent);
+
+    (scaled_layout(&points), edges)
+}
+
+// A W by H grid with P percent of its edges removed at random, skipping any
+// that would leave the board in pieces.
+
+pub fn lae_randomgrid(width: usize, height: usize, percent: usize, seed: u64) -> Lae {
+    let mut rng = SplitMix64(seed);
+    let (layout, edges) = lae_grid(width, height);
+
+    let mut edges: Edges = edges.into_iter().filter(|&(a, b)| a < b).collect();
+    rng.shuffle(&mut edges);
+
+    (layout, remove_edges(width * height, edges, percent))
+}
+
+// A random planar graph. N random points are joined by straight edges between
+// nearby points (up to PLANAR_REACH apart, where the points are one apart on
+// average), taken in a random order and each added if it doesn't cross the
+// edges so far. If that leaves the board in pieces, the shortest edges that
+// join two pieces without crossing anything are added too. Then edges are
+// removed at random, keeping the board in one piece, until there are twice as
+// many edges as points (so that points have four neighbors on average, like
+// on a grid).
+
+const PLANAR_REACH: f64 = 2.0;
+
+pub fn lae_planar(n: usize, seed: u64) -> Lae {
+    let mut rng = SplitMix64(seed);
+    let points = random_points(n, &mut rng);
+
+    let mut pairs: Edges = (0..n).flat_map(|a| (a + 1..n).map(move |b| (a, b))).collect();
+    rng.shuffle(&mut pairs);
+
+    let mut edges = Edges::new();
+    let fits = |(a, b): (usize, usize), edges: &Edges| {
+        !edges.iter().any(|&(c, d)| {
+            a != c && a != d && b != c && b != d &&
+                segments_cross(points[a], points[b], points[c], points[d])
+        })
+    };
+
+    for &pair in &pairs {
+        if distance2(points[pair.0], points[pair.1]) <= PLANAR_REACH * PLANAR_REACH && fits(pair, &edges) {
+            edges.push(pair);
+        }
+    }
+
+    pairs.sort_by(|a, b| {
+        distance2(points[a.0], points[a.1]).partial_cmp(&distance2(points[b.0], points[b.1])).unwrap()
+    });
+
+    let mut pieces = components(n, &edges);
+
+    for pair in pairs {
+        if pieces[pair.0] != pieces[pair.1] && fits(pair, &edges) {
+            edges.push(pair);
+            pieces = components(n, &edges);
+        }
+    }
+
+    rng.shuffle(&mut edges);
+    let excess = edges.len().saturating_sub(2 * n);
+    let percent = (100 * excess).div_ceil(edges.len().max(1));
+    let mut edges = remove_edges(n, edges, percent);
+    edges.sort();
+
+    (scaled_layout(&points), edges)
+}
+
+fn random_points(n: usize, rng: &mut SplitMix64) -> Vec<(f64, f64)> {
+    let side = (n as f64).sqrt();
+    let mut points: Vec<(f64, f64)> = vec![];
+
+    for _ in 0..n {
+        let nearest = |candidate: (f64, f64), points: &[(f64, f64)]| {
+            points.iter().map(|&p| distance2(p, candidate)).fold(f64::INFINITY, f64::min)
+        };
+
+        let candidates: Vec<(f64, f64)> =
+            (0..SAMPLING_TRIES).map(|_| (rng.unit() * side, rng.unit() * side)).collect();
+
+        let best = candidates.into_iter()
+            .max_by(|&a, &b| nearest(a, &points).partial_cmp(&nearest(b, &points)).unwrap())
+            .unwrap();
+
+        points.push(best);
+    }
+
+    points
+}
+
+fn scaled_layout(points: &[(f64, f64)]) -> Layout {
+    let layout: Layout = points.iter().map(|&(x, y)| (x as f32, y as f32)).collect();
+    let separation = layout.min_point_separation();
+
+    if separation.is_finite() {
+        layout.scale(1.0 / separation)
+    } else {
+        layout
+    }
+}
+
+// Remove the first P percent of a list of edges, in order, skipping any edge
+// whose removal would disconnect the board.
+
+fn remove_edges(point_count: usize, mut edges: Edges, percent: usize) -> Edges {
+    let mut to_remove = edges.len() * percent.min(100) / 100;
+    let mut index = 0;
+
+    while to_remove > 0 && index < edges.len() {
+        let edge = edges.remove(index);
+
+        if is_connected(point_count, &edges) {
+            to_remove -= 1;
+        } else {
+            edges.insert(index, edge);
+            index += 1;
+        }
+    }
+
+    edges
+}
+
+fn is_connected(point_count: usize, edges: &Edges) -> bool {
+    components(point_count, edges).iter().all(|&piece| piece == 0)
+}
+
+// Label each point with the lowest-numbered point in its connected piece.
+
+fn components(point_count: usize, edges: &Edges) -> Vec<usize> {
+    let mut neighbors = vec![vec![]; point_count];
+    for &(a, b) in edges {
+        neighbors[a].push(b);
+        neighbors[b].push(a);
+    }
+
+    let mut labels = vec![usize::MAX; point_count];
+
+    for start in 0..point_count {
+        let mut stack = vec![start];
+
+        while let Some(point) = stack.pop() {
+            if labels[point] == usize::MAX {
+                labels[point] = start;
+                stack.extend(neighbors[point].iter().copied());
+            }
+        }
+    }
+
+    labels
+}
+
+// The edges of the Delaunay triangulation of some points, by the
+// Bowyer-Watson algorithm: the points are added one at a time inside a
+// triangle big enough to hold them all, and each one replaces the triangles
+// whose circumcircles it falls inside with a fan of triangles around itself.
+
+fn delaunay_edges(points: &[(f64, f64)]) -> Edges {
+    let n = points.len();
+    let size = points.iter().fold(1.0, |size: f64, p| size.max(p.0.abs()).max(p.1.abs()));
+    let mut all = points.to_vec();
+    all.extend([(-1000.0 * size, -1000.0 * size), (1000.0 * size, -1000.0 * size), (0.0, 1000.0 * size)]);
+
+    let mut triangles: Vec<[usize; 3]> = vec![[n, n + 1, n + 2]];
+
+    for p in 0..n {
+        let (bad, good): (Vec<[usize; 3]>, Vec<[usize; 3]>) =
+            triangles.into_iter().partition(|&t| in_circumcircle(all[p], all[t[0]], all[t[1]], all[t[2]]));
+
+        // The edges around the hole are the ones that belong to only one of
+        // the removed triangles.
+
+        let sides: Vec<(usize, usize)> =
+            bad.iter()
+               .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+               .map(|(a, b)| (a.min(b), a.max(b)))
+               .collect();
+
+        triangles = good;
+        for &(a, b) in &sides {
+            if sides.iter().filter(|&&side| side == (a, b)).count() == 1 {
+                triangles.push([a, b, p]);
+            }
+        }
+    }
+
+    let mut edges: Edges =
+        triangles.iter()
+                 .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+                 .filter(|&(a, b)| a < n && b < n)
+                 .map(|(a, b)| (a.min(b), a.max(b)))
+                 .collect();
+
+    edges.sort();
+    edges.dedup();
+    edges
+}
+
+fn in_circumcircle(p: (f64, f64), a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> bool {
+    let (ax, ay) = (a.0 - p.0, a.1 - p.1);
+    let (bx, by) = (b.0 - p.0, b.1 - p.1);
+    let (cx, cy) = (c.0 - p.0, c.1 - p.1);
+
+    let det = (ax * ax + ay * ay) * (bx * cy - cx * by)
+            - (bx * bx + by * by) * (ax * cy - cx * ay)
+            + (cx * cx + cy * cy) * (ax * by - bx * ay);
+
+    // The determinant's sign depends on which way round the triangle goes.
+
+    let orientation = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+    det * orientation > 0.0
+}
+
+fn segments_cross(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    let side = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0);
+    side(a, b, c) * side(a, b, d) < 0.0 && side(c, d, a) * side(c, d, b) < 0.0
+}
+
+fn distance2(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0) * (a.0 - b.0) + (a.1 - b.1) * (a.1 - b.1)
+}
+
 // HELPER FUNCTIONS
 
 trait LayoutStuff {
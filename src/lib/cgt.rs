@@ -0,0 +1,770 @@
+
+/* cgt.rs
+ *
+ * This module applies combinatorial game theory to endgames that have fallen
+ * apart into independent regions. Immortal stones (as found by
+ * Board::keep_only_immortal) can never be captured, so the points that aren't
+ * covered by them split into connected regions which can't affect each other:
+ * a move in one region can only capture stones in that same region. The game
+ * is then a sum of smaller games, one per region, and each of those can be
+ * searched on its own.
+ *
+ * Each region is searched with every move kept inside the region. A move that
+ * would make an immortal stone mortal (filling in one of its last two eyes,
+ * say) is never allowed, so the regions stay independent. The search gives
+ * every region a value in canonical form: a number, a switch like {3|-1}, an
+ * infinitesimal like * or ^, or something more complicated. Values use the
+ * usual accounting of CGT for Go, where every move costs the mover a point,
+ * so that filling in your own territory is worth nothing and a region's
+ * value counts the points that it will end up contributing.
+ *
+ * The predicted result comes from the sum of the values. The player to move
+ * can hold the sum to its stop (the left stop if Black is to move, the right
+ * stop if White is), and what's left once the stop is taken away decides who
+ * gets the last move. Under stone scoring that move still puts a stone on
+ * the board, and so gains a point that the accounting above doesn't see: the
+ * player to move gets it if they win the leftover game moving first. A stop
+ * that isn't an integer gives no prediction.
+ *
+ * A region whose game graph has cycles (a ko, or stones captured and played
+ * again) has no proper value, since moves back to a position on the line of
+ * play are left out of it, so a sum with such a region gives no prediction
+ * either. The regions' game graphs can still be played out together, with
+ * passes allowed, until both players pass in a row. That copes with cycles
+ * but takes much longer, and it's kept as a check on the prediction.
+ *
+ * Each eye that a group needs to stay alive is left alone. Regions that
+ * aren't needed (spare eyes) are chosen greedily, one region at a time, so a
+ * board where there's a choice of which eyes to keep may be scored a little
+ * pessimistically for the owner.
+ *
+ * Ko is mostly ignored. Each position of a region is searched once, leaving
+ * out any move that would repeat a position on the line of play that first
+ * reached it, but the search doesn't know about the history of the game or
+ * about ko threats elsewhere on the board. A region with too many positions
+ * to search gets no value, and then there's no prediction either.
+ */
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use crate::engine::{Board, Position, Color};
+use crate::engine::Color::*;
+use crate::gametree::GameTree;
+
+// The most positions a single region may have, and the most positions of the
+// whole sum that predicting the result may visit (counting repeat visits).
+
+pub const MAX_REGION_POSITIONS: usize = 5000;
+pub const MAX_PLAY_OUT_STATES:  usize = 1000000;
+
+//============================================================================
+// Dyadic rationals.
+//============================================================================
+
+// A number of the form num / 2^exp, kept in lowest terms.
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Dyadic {
+    num: i64,
+    exp: u32,
+}
+
+impl Dyadic {
+    pub fn integer(n: i64) -> Self {
+        Dyadic {num: n, exp: 0}
+    }
+
+    fn new(mut num: i64, mut exp: u32) -> Self {
+        while exp > 0 && num % 2 == 0 {
+            num /= 2;
+            exp -= 1;
+        }
+        Dyadic {num: num, exp: exp}
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.exp == 0
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.num as f64 / (1u64 << self.exp) as f64
+    }
+
+    pub fn add(&self, other: &Dyadic) -> Dyadic {
+        let exp = self.exp.max(other.exp);
+        Dyadic::new(self.num * (1 << (exp - self.exp)) + other.num * (1 << (exp - other.exp)), exp)
+    }
+
+    fn floor(&self) -> i64 {
+        self.num.div_euclid(1 << self.exp)
+    }
+
+    // The simplest number strictly between two others, either of which may be
+    // missing (meaning there's no bound on that side).
+
+    fn simplest_between(low: Option<Dyadic>, high: Option<Dyadic>) -> Dyadic {
+        let above = |n: Dyadic| low.is_none_or(|l| n > l);
+        let below = |n: Dyadic| high.is_none_or(|h| n < h);
+
+        // The integer closest to zero, if there is one.
+
+        let candidate = match (low, high) {
+            (Some(l), _) if l >= Dyadic::integer(0) => l.floor() + 1,
+            (_, Some(h)) if h <= Dyadic::integer(0) => -(Dyadic {num: -h.num, exp: h.exp}.floor() + 1),
+            _ => 0,
+        };
+
+        if above(Dyadic::integer(candidate)) && below(Dyadic::integer(candidate)) {
+            return Dyadic::integer(candidate);
+        }
+
+        // Otherwise, the fraction with the smallest denominator. Both bounds
+        // must exist here, since an unbounded side always leaves room for an
+        // integer.
+
+        let l = low.unwrap();
+        let mut exp = 1;
+
+        loop {
+            let scaled_floor = if exp >= l.exp {
+                l.num * (1 << (exp - l.exp))
+            } else {
+                l.num.div_euclid(1 << (l.exp - exp))
+            };
+
+            let result = Dyadic::new(scaled_floor + 1, exp);
+            if below(result) {
+                return result;
+            }
+
+            exp += 1;
+        }
+    }
+}
+
+impl Ord for Dyadic {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let exp = self.exp.max(other.exp);
+        (self.num * (1 << (exp - self.exp))).cmp(&(other.num * (1 << (exp - other.exp))))
+    }
+}
+
+impl PartialOrd for Dyadic {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Dyadic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.exp == 0 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, 1u64 << self.exp)
+        }
+    }
+}
+
+//============================================================================
+// Games.
+//============================================================================
+
+// A short game in canonical form. Numbers are kept as numbers, since they
+// come up all the time and their options are easy to work out when needed.
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Game {
+    Number(Dyadic),
+    Compound {left: Vec<Game>, right: Vec<Game>},
+}
+
+use Game::*;
+
+impl Game {
+    pub fn integer(n: i64) -> Self {
+        Number(Dyadic::integer(n))
+    }
+
+    // The canonical form of {left | right}, where every option is already in
+    // canonical form.
+
+    pub fn new(mut left: Vec<Game>, mut right: Vec<Game>) -> Self {
+        loop {
+            // Remove dominated options.
+
+            left = undominated(left, |a, b| a.le(b));
+            right = undominated(right, |a, b| b.le(a));
+
+            // Bypass reversible options.
+
+            let current = Compound {left: left.clone(), right: right.clone()};
+            let mut changed = false;
+
+            let mut new_left = vec![];
+            for option in left {
+                match option.right_options().into_iter().find(|r| r.le(&current)) {
+                    Some(reverse) => {new_left.extend(reverse.left_options()); changed = true;}
+                    None => new_left.push(option),
+                }
+            }
+
+            let mut new_right = vec![];
+            for option in right {
+                match option.left_options().into_iter().find(|l| current.le(l)) {
+                    Some(reverse) => {new_right.extend(reverse.right_options()); changed = true;}
+                    None => new_right.push(option),
+                }
+            }
+
+            left = new_left;
+            right = new_right;
+
+            if !changed {
+                break;
+            }
+        }
+
+        // If what's left is {a | b} with numbers a < b (or a side missing),
+        // the game is the simplest number between them.
+
+        let number = |options: &Vec<Game>| match options.as_slice() {
+            [] => Some(None),
+            [Number(x)] => Some(Some(*x)),
+            _ => None,
+        };
+
+        if let (Some(l), Some(r)) = (number(&left), number(&right)) {
+            if l.is_none() || r.is_none() || l < r {
+                return Number(Dyadic::simplest_between(l, r));
+            }
+        }
+
+        Compound {left: left, right: right}
+    }
+
+    pub fn left_options(&self) -> Vec<Game> {
+        match self {
+            Number(x) if x.is_integer() && x.num > 0 => vec![Game::integer(x.num - 1)],
+            Number(x) if x.is_integer() => vec![],
+            Number(x) => vec![Number(Dyadic::new(x.num - 1, x.exp))],
+            Compound {left, ..} => left.clone(),
+        }
+    }
+
+    pub fn right_options(&self) -> Vec<Game> {
+        match self {
+            Number(x) if x.is_integer() && x.num < 0 => vec![Game::integer(x.num + 1)],
+            Number(x) if x.is_integer() => vec![],
+            Number(x) => vec![Number(Dyadic::new(x.num + 1, x.exp))],
+            Compound {right, ..} => right.clone(),
+        }
+    }
+
+    // G <= H unless some left option of G is >= H or some right option of H
+    // is <= G.
+
+    pub fn le(&self, other: &Game) -> bool {
+        if let (Number(x), Number(y)) = (self, other) {
+            return x <= y;
+        }
+
+        !self.left_options().iter().any(|l| other.le(l)) &&
+        !other.right_options().iter().any(|r| r.le(self))
+    }
+
+    pub fn add(&self, other: &Game) -> Game {
+        match (self, other) {
+            (Number(x), Number(y)) => Number(x.add(y)),
+
+            // Number translation: x + G = {x + GL | x + GR} for a number x and
+            // a game G that isn't one.
+
+            (Number(_), Compound {left, right}) | (Compound {left, right}, Number(_)) => {
+                let x = if let Number(_) = self {self} else {other};
+                Game::new(left.iter().map(|l| l.add(x)).collect(),
+                          right.iter().map(|r| r.add(x)).collect())
+            }
+
+            (Compound {left: gl, right: gr}, Compound {left: hl, right: hr}) => {
+                let left = gl.iter().map(|l| l.add(other))
+                                    .chain(hl.iter().map(|l| self.add(l)))
+                                    .collect();
+                let right = gr.iter().map(|r| r.add(other))
+                                     .chain(hr.iter().map(|r| self.add(r)))
+                                     .collect();
+                Game::new(left, right)
+            }
+        }
+    }
+
+    // The number reached when both players play the game out as well as they
+    // can, with Left (Black) moving first or Right (White) moving first.
+
+    pub fn left_stop(&self) -> Dyadic {
+        match self {
+            Number(x) => *x,
+            Compound {left, ..} => left.iter().map(|l| l.right_stop()).max().unwrap(),
+        }
+    }
+
+    pub fn right_stop(&self) -> Dyadic {
+        match self {
+            Number(x) => *x,
+            Compound {right, ..} => right.iter().map(|r| r.left_stop()).min().unwrap(),
+        }
+    }
+}
+
+// Keep only the options that aren't beaten by some other option (keeping one
+// copy of options that are equal).
+
+fn undominated(options: Vec<Game>, worse: impl Fn(&Game, &Game) -> bool) -> Vec<Game> {
+    let mut kept: Vec<Game> = vec![];
+
+    for option in options {
+        if kept.iter().any(|k| worse(&option, k)) {
+            continue;
+        }
+        kept.retain(|k| !worse(k, &option));
+        kept.push(option);
+    }
+
+    kept
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let zero = Game::integer(0);
+        let star = Compound {left: vec![zero.clone()], right: vec![zero.clone()]};
+
+        match self {
+            Number(x) => write!(f, "{}", x),
+            Compound {left, right} => {
+                match (left.as_slice(), right.as_slice()) {
+                    ([Number(x)], [Number(y)]) if x == y && x.num == 0 => write!(f, "*"),
+                    ([Number(x)], [Number(y)]) if x == y => write!(f, "{}*", x),
+                    ([l], [r]) if *l == zero && *r == star => write!(f, "^"),
+                    ([l], [r]) if *l == star && *r == zero => write!(f, "v"),
+                    _ => {
+                        let list = |options: &Vec<Game>| options.iter()
+                                                                .map(|o| o.to_string())
+                                                                .collect::<Vec<String>>()
+                                                                .join(", ");
+                        write!(f, "{{{} | {}}}", list(left), list(right))
+                    }
+                }
+            }
+        }
+    }
+}
+
+//============================================================================
+// Regions.
+//============================================================================
+
+pub struct Region {
+    pub points: Vec<usize>,
+    pub value:  Option<Game>,
+    graph:      Vec<RegionNode>,    // the first node is the current position
+    cyclic:     bool,
+}
+
+// A position in a region's search, with its stone score (counting only the
+// region's points) and the positions each color can move to.
+
+struct RegionNode {
+    score: i32,
+    black: Vec<usize>,
+    white: Vec<usize>,
+}
+
+pub struct RegionReport {
+    pub immortal_score: i32,
+    pub regions:        Vec<Region>,
+}
+
+impl RegionReport {
+    // The sum of every region's value, if they all have one.
+
+    pub fn sum(&self) -> Option<Game> {
+        self.regions.iter().try_fold(Game::integer(0), |sum, region| {
+            region.value.as_ref().map(|value| sum.add(value))
+        })
+    }
+
+    // The predicted final score (Black's stones minus White's) with a given
+    // color to move, if every region has a value without cycles and the stop
+    // of their sum is an integer.
+
+    pub fn predicted(&self, to_play: Color) -> Option<i32> {
+        if self.regions.iter().any(|region| region.cyclic) {
+            return None;
+        }
+
+        let sum = self.sum()?;
+        let stop = if to_play == Black {sum.left_stop()} else {sum.right_stop()};
+
+        if !stop.is_integer() {
+            return None;
+        }
+
+        let tail = sum.add(&Game::integer(-stop.num));
+        let zero = Game::integer(0);
+
+        let last_move = match to_play {
+            Black if !tail.le(&zero) => 1,
+            White if !zero.le(&tail) => -1,
+            _ => 0,
+        };
+
+        Some(stop.num as i32 + last_move + self.immortal_score)
+    }
+
+    // The final score found the slow way, by playing the regions' game graphs
+    // out together (see PlayOut), if every region has a value and that
+    // doesn't take too long. This is a check on predicted().
+
+    pub fn played_out(&self, to_play: Color) -> Option<i32> {
+        if self.regions.iter().any(|region| region.value.is_none()) {
+            return None;
+        }
+
+        let start = vec![0; self.regions.len()];
+        let mut play_out = PlayOut {
+            graphs: self.regions.iter().map(|region| &region.graph).collect(),
+            memo:   HashMap::new(),
+            seen:   HashSet::from([start.clone()]),
+            states: 0,
+        };
+
+        play_out.play(start, to_play, false, i32::MIN, i32::MAX).map(|(value, _)| value + self.immortal_score)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!("Immortal stones: {:+}", self.immortal_score)];
+
+        for region in &self.regions {
+            let value = match &region.value {
+                Some(value) if region.cyclic => format!("{} (has cycles)", value),
+                Some(value) => value.to_string(),
+                None => "(too big to search)".to_string(),
+            };
+
+            let points: Vec<String> = region.points.iter().map(|p| p.to_string()).collect();
+            lines.push(format!("Region [{}]: {}", points.join(" "), value));
+        }
+
+        if let Some(sum) = self.sum() {
+            lines.push(format!("Sum: {}", sum));
+        }
+
+        lines.join("\n")
+    }
+}
+
+// Find the independent regions of a position and work out their values.
+
+pub fn analyze_regions(board: &Board, position: &Position) -> RegionReport {
+    let mut immortal = position.clone();
+    board.keep_only_immortal(&mut immortal);
+
+    let immortal_points: Vec<usize> =
+        (0..board.point_count()).filter(|&p| immortal[p] != Empty).collect();
+
+    // Split everything else into connected regions.
+
+    let mut region_of = vec![None; board.point_count()];
+    let mut region_points = vec![];
+
+    for start in 0..board.point_count() {
+        if immortal[start] != Empty || region_of[start].is_some() {
+            continue;
+        }
+
+        let mut points = vec![start];
+        region_of[start] = Some(region_points.len());
+        let mut i = 0;
+
+        while i < points.len() {
+            for n in board.get_neighbors(points[i]) {
+                if immortal[n] == Empty && region_of[n].is_none() {
+                    region_of[n] = Some(region_points.len());
+                    points.push(n);
+                }
+            }
+            i += 1;
+        }
+
+        points.sort();
+        region_points.push(points);
+    }
+
+    // A region whose border is all one color's immortal stones is an eye of
+    // those stones. Its owner may fill it in completely only if the stones
+    // stay immortal without it; otherwise the owner has to leave at least one
+    // point of it empty. Filling in one eye can make another one necessary,
+    // so the spare eyes are picked one at a time, each on top of the last.
+
+    let owners: Vec<Color> =
+        region_points.iter()
+            .map(|points| {
+                let mut colors = points.iter()
+                                       .flat_map(|&p| board.get_neighbors(p))
+                                       .map(|n| immortal[n])
+                                       .filter(|&c| c != Empty);
+                let first = colors.next().unwrap_or(Empty);
+                if colors.all(|c| c == first) {first} else {Empty}
+            })
+            .collect();
+
+    let mut filled = position.clone();
+    let mut spare = vec![false; region_points.len()];
+
+    for (i, points) in region_points.iter().enumerate() {
+        if owners[i] == Empty {
+            continue;
+        }
+
+        // Filling in the region can capture stones inside it, so keep going
+        // until it's full or stops changing.
+
+        let mut next = filled.clone();
+        for _ in 0..=points.len() {
+            for &p in points {
+                if next[p] == Empty {
+                    board.play(&mut next, owners[i], p);
+                }
+            }
+        }
+
+        let mut still_immortal = next.clone();
+        board.keep_only_immortal(&mut still_immortal);
+
+        if points.iter().chain(immortal_points.iter()).all(|&p| still_immortal[p] != Empty) {
+            spare[i] = true;
+            filled = next;
+        }
+    }
+
+    let mut regions = vec![];
+
+    for (i, points) in region_points.into_iter().enumerate() {
+        let mut search = RegionSearch {
+            board:           board,
+            points:          &points,
+            owner:           if spare[i] {Empty} else {owners[i]},
+            immortal_points: &immortal_points,
+            memo:            HashMap::new(),
+            history:         HashSet::new(),
+            nodes:           vec![],
+            values:          vec![],
+            cyclic:          false,
+        };
+
+        let value = search.search(position).map(|node| search.values[node].clone());
+        let (graph, cyclic) = (search.nodes, search.cyclic);
+        regions.push(Region {points: points, value: value, graph: graph, cyclic: cyclic});
+    }
+
+    RegionReport {
+        immortal_score: board.score_delta_stone(&immortal),
+        regions:        regions,
+    }
+}
+
+impl GameTree {
+    pub fn analyze_regions(&self) -> RegionReport {
+        analyze_regions(self.board(), self.position())
+    }
+}
+
+struct RegionSearch<'a> {
+    board:           &'a Board,
+    points:          &'a Vec<usize>,
+    owner:           Color,          // Empty unless the owner can't fill the region in
+    immortal_points: &'a Vec<usize>,
+    memo:            HashMap<u64, usize>,
+    history:         HashSet<u64>,
+    nodes:           Vec<RegionNode>,
+    values:          Vec<Game>,
+    cyclic:          bool,           // whether any move was left out of a value
+}
+
+impl<'a> RegionSearch<'a> {
+    // Search a region from a given position, returning the node for that
+    // position, or None if the region has too many positions. There's one
+    // node per position, whoever is to move, since a node holds the moves of
+    // both colors, and the nodes form the region's whole game graph, cycles
+    // and all.
+    //
+    // Each node also gets a value: a move by Black is worth one point less
+    // than the position it leads to, and a move by White one point more.
+    // Values can't go round a cycle, so moves back to a position on the line
+    // being searched are left out of them. A position's value is worked out
+    // the first time the search reaches it and reused every time after that.
+
+    fn search(&mut self, position: &Position) -> Option<usize> {
+        let hash = self.board.hash_position(position);
+
+        if let Some(&node) = self.memo.get(&hash) {
+            return Some(node);
+        }
+
+        if self.nodes.len() >= MAX_REGION_POSITIONS {
+            return None;
+        }
+
+        let score: i32 = self.points.iter().map(|&p| match position[p] {
+            Black => 1,
+            White => -1,
+            Empty => 0,
+        }).sum();
+
+        let node = self.nodes.len();
+        self.nodes.push(RegionNode {score: score, black: vec![], white: vec![]});
+        self.values.push(Game::integer(score as i64));
+        self.memo.insert(hash, node);
+        self.history.insert(hash);
+
+        let mut options = [vec![], vec![]];
+
+        for (i, color) in [Black, White].into_iter().enumerate() {
+            for &point in self.points {
+                if position[point] != Empty {
+                    continue;
+                }
+
+                let mut next = position.clone();
+                self.board.play(&mut next, color, point);
+
+                let next_hash = self.board.hash_position(&next);
+                if next_hash == hash || self.fills_eye(&next, color) {
+                    continue;
+                }
+
+                let mut immortal = next.clone();
+                self.board.keep_only_immortal(&mut immortal);
+                if self.immortal_points.iter().any(|&p| immortal[p] == Empty) {
+                    continue;
+                }
+
+                let child = self.search(&next)?;
+                let moves = if color == Black {&mut self.nodes[node].black} else {&mut self.nodes[node].white};
+
+                if !moves.contains(&child) {
+                    moves.push(child);
+                    if self.history.contains(&next_hash) {
+                        self.cyclic = true;
+                    } else {
+                        options[i].push(child);
+                    }
+                }
+            }
+        }
+
+        self.history.remove(&hash);
+
+        let [black, white] = options;
+        if !black.is_empty() || !white.is_empty() {
+            self.values[node] = Game::new(black.iter().map(|&c| self.values[c].add(&Game::integer(-1))).collect(),
+                                          white.iter().map(|&c| self.values[c].add(&Game::integer(1))).collect());
+        }
+
+        Some(node)
+    }
+
+    // Check whether a move by a given color has filled in an eye that its
+    // owner can't spare.
+
+    fn fills_eye(&self, position: &Position, color: Color) -> bool {
+        color == self.owner && self.points.iter().all(|&p| position[p] != Empty)
+    }
+}
+
+// Play the regions out to the end under stone scoring, with each region at a
+// given node of its graph. The game ends when both players pass in a row,
+// and the result is the total score of the regions at that point. No move
+// may repeat a position (a combination of region nodes) seen earlier in the
+// play out, and results that this rule had a say in depend on how they were
+// reached, so only the others are remembered. The search is alpha-beta, and
+// results outside the window are only bounds, so they aren't remembered
+// either. Returns None if this visits more than MAX_PLAY_OUT_STATES
+// positions.
+
+struct PlayOut<'a> {
+    graphs: Vec<&'a Vec<RegionNode>>,
+    memo:   HashMap<(Vec<usize>, Color, bool), i32>,
+    seen:   HashSet<Vec<usize>>,
+    states: usize,
+}
+
+impl<'a> PlayOut<'a> {
+    // The result, along with a flag saying whether a repeat was ruled out on
+    // the way to it.
+
+    fn play(&mut self, nodes: Vec<usize>, to_play: Color, passed: bool,
+            mut alpha: i32, mut beta: i32) -> Option<(i32, bool)> {
+        let key = (nodes, to_play, passed);
+        if let Some(&value) = self.memo.get(&key) {
+            return Some((value, false));
+        }
+
+        self.states += 1;
+        if self.states > MAX_PLAY_OUT_STATES {
+            return None;
+        }
+
+        let (nodes, _, _) = &key;
+        let window = (alpha, beta);
+        let mut repeats = false;
+
+        // Passing ends the game if the other player just passed.
+
+        let mut best = if passed {
+            nodes.iter().enumerate().map(|(i, &n)| self.graphs[i][n].score).sum()
+        } else {
+            let (value, pass_repeats) = self.play(nodes.clone(), to_play.reverse(), true, alpha, beta)?;
+            repeats |= pass_repeats;
+            value
+        };
+
+        'regions: for (i, &node) in nodes.iter().enumerate() {
+            let graph = self.graphs[i];
+            let options = if to_play == Black {&graph[node].black} else {&graph[node].white};
+
+            for &option in options {
+                if to_play == Black {alpha = alpha.max(best);} else {beta = beta.min(best);}
+                if alpha >= beta {
+                    break 'regions;
+                }
+
+                let mut next = nodes.clone();
+                next[i] = option;
+
+                if self.seen.contains(&next) {
+                    repeats = true;
+                    continue;
+                }
+
+                self.seen.insert(next.clone());
+                let result = self.play(next.clone(), to_play.reverse(), false, alpha, beta);
+                self.seen.remove(&next);
+
+                let (value, option_repeats) = result?;
+                repeats |= option_repeats;
+
+                if (to_play == Black && value > best) || (to_play == White && value < best) {
+                    best = value;
+                }
+            }
+        }
+
+        if !repeats && window.0 < best && best < window.1 {
+            self.memo.insert(key, best);
+        }
+
+        Some((best, repeats))
+    }
+}
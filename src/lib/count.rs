@@ -0,0 +1,437 @@
+/* count.rs
+ *
+ * This module counts legal Go positions on any board: colorings of the
+ * points with empty, black, and white in which every chain of stones has at
+ * least one liberty.
+ *
+ * The count is done the way Tromp counted positions on square grids, by
+ * dynamic programming over a path decomposition of the board. The points are
+ * added one at a time, in an order chosen greedily to keep the frontier (the
+ * points already added that still have neighbors to come) small. A state
+ * records, for every frontier point, its color, which frontier stones belong
+ * to the same chain so far, and whether that chain has found a liberty yet.
+ * Every state carries the number of partial colorings that lead to it. When
+ * a point leaves the frontier and takes the last piece of a chain without a
+ * liberty with it, that chain can never get one, so the state is dropped.
+ *
+ * The work grows exponentially with the width of the frontier, not with the
+ * size of the board, so long thin boards are cheap and big square ones are
+ * not: 7x7 takes a few seconds, and 9x9 needs minutes and gigabytes. The
+ * counts themselves overflow 64 bits quickly (a 19x19 board has a 171-digit
+ * count), so they're kept in a small unsigned big integer type that only
+ * knows how to add and print itself.
+ *
+ * There's also a brute-force count for tiny boards, which goes through every
+ * coloring and plays its stones onto an empty position with Board::play. A
+ * coloring is legal exactly when no stone gets captured along the way, which
+ * makes the brute-force count a check on the engine's capture rules as well
+ * as on the dynamic programming.
+ */
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use crate::engine::Board;
+use crate::engine::Color::*;
+
+//============================================================================
+// Big unsigned integers.
+//============================================================================
+
+// An unsigned integer of any size, as base 2^32 digits from least to most
+// significant, with no leading zero digits.
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct BigCount {
+    digits: Vec<u32>,
+}
+
+impl BigCount {
+    pub fn zero() -> Self {
+        BigCount {digits: vec![]}
+    }
+
+    pub fn from_u64(n: u64) -> Self {
+        let mut digits = vec![n as u32, (n >> 32) as u32];
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        BigCount {digits}
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn add(&mut self, other: &BigCount) {
+        if self.digits.len() < other.digits.len() {
+            self.digits.resize(other.digits.len(), 0);
+        }
+
+        let mut carry = 0u64;
+        for i in 0..self.digits.len() {
+            let sum = self.digits[i] as u64 + other.digits.get(i).copied().unwrap_or(0) as u64 + carry;
+            self.digits[i] = sum as u32;
+            carry = sum >> 32;
+        }
+
+        if carry > 0 {
+            self.digits.push(carry as u32);
+        }
+    }
+
+    // The base-10 logarithm, which is accurate to about 15 significant
+    // digits however big the number is.
+
+    pub fn log10(&self) -> f64 {
+        let n = self.digits.len();
+        if n == 0 {
+            return f64::NEG_INFINITY;
+        }
+
+        let top = self.digits.iter().rev().take(2).fold(0.0, |acc, &d| acc * 4294967296.0 + d as f64);
+        let shift = n.saturating_sub(2);
+        top.log10() + (32 * shift) as f64 * 2f64.log10()
+    }
+
+    // Divide by a small number in place, returning the remainder.
+
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for digit in self.digits.iter_mut().rev() {
+            let value = (remainder << 32) | *digit as u64;
+            *digit = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+
+        remainder as u32
+    }
+}
+
+impl fmt::Display for BigCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // Peel off nine decimal digits at a time.
+
+        let mut n = self.clone();
+        let mut chunks = vec![];
+        while !n.is_zero() {
+            chunks.push(n.div_rem_small(1_000_000_000));
+        }
+
+        let mut text = chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            text += &format!("{:09}", chunk);
+        }
+
+        write!(f, "{}", text)
+    }
+}
+
+//============================================================================
+// Counting by dynamic programming.
+//============================================================================
+
+pub struct LegalCount {
+    pub count:      BigCount,
+    pub width:      usize,    // the largest frontier during the sweep
+    pub max_states: usize,    // the most states alive at once
+}
+
+// Frontier point codes. Zero is an empty point. A stone has its color in the
+// lowest bit, whether its chain has a liberty in the next, and its chain's
+// label above those. Labels start at one, so no stone's code is zero.
+
+const STONE_WHITE: u8 = 1;
+const STONE_LIBERTY: u8 = 2;
+const LABEL_SHIFT: u32 = 2;
+
+// Labels have to fit in a code above the other two bits, which limits how
+// wide the frontier can get.
+
+pub const MAX_FRONTIER_WIDTH: usize = 61;
+
+pub fn count_legal_positions(board: &Board) -> Result<LegalCount, String> {
+    let n = board.point_count();
+    let order = sweep_order(board);
+    let mut step_of = vec![0; n];
+    for (step, &point) in order.iter().enumerate() {
+        step_of[point] = step;
+    }
+
+    // A point leaves the frontier after the step that adds its last neighbor
+    // (or itself, if that comes later).
+
+    let leave_step: Vec<usize> =
+        (0..n).map(|p| board.get_neighbors(p).iter()
+                            .map(|&q| step_of[q])
+                            .fold(step_of[p], usize::max))
+              .collect();
+
+    // The frontier holds each point from the step after it's added until the
+    // step it leaves, so its width can be found before doing any work.
+
+    let mut changes = vec![0i64; n + 1];
+    for p in 0..n {
+        changes[step_of[p] + 1] += 1;
+        changes[leave_step[p] + 1] -= 1;
+    }
+
+    let width = changes.iter()
+                       .scan(0, |size, change| {*size += change; Some(*size as usize + 1)})
+                       .take(n)
+                       .max()
+                       .unwrap_or(0);
+
+    if width > MAX_FRONTIER_WIDTH {
+        return Err(format!("The frontier is {} points wide, and counting only works up to {}.",
+                           width, MAX_FRONTIER_WIDTH));
+    }
+
+    let mut frontier: Vec<usize> = vec![];
+    let mut states: HashMap<Vec<u8>, BigCount> = HashMap::new();
+    states.insert(vec![], BigCount::from_u64(1));
+
+    let mut max_states = 1;
+
+    for (step, &point) in order.iter().enumerate() {
+        let neighbors: Vec<usize> =
+            frontier.iter().enumerate()
+                    .filter(|(_, &q)| board.get_neighbors(point).contains(&q))
+                    .map(|(i, _)| i)
+                    .collect();
+
+        let keep: Vec<bool> =
+            frontier.iter().chain(std::iter::once(&point))
+                    .map(|&q| leave_step[q] > step)
+                    .collect();
+
+        let mut next_states: HashMap<Vec<u8>, BigCount> = HashMap::with_capacity(states.len() * 2);
+
+        for (state, count) in states.iter() {
+            for color in 0..3u8 {
+                if let Some(next) = add_point(state, &neighbors, color, &keep) {
+                    next_states.entry(next).or_default().add(count);
+                }
+            }
+        }
+
+        frontier.push(point);
+        frontier = frontier.iter().zip(keep.iter())
+                           .filter(|(_, &k)| k)
+                           .map(|(&q, _)| q)
+                           .collect();
+
+        states = next_states;
+        max_states = max_states.max(states.len());
+    }
+
+    Ok(LegalCount {
+        count:      states.remove(&vec![]).unwrap_or_default(),
+        width,
+        max_states,
+    })
+}
+
+// Add a point of a given color (0 for empty, 1 for black, 2 for white) to a
+// state, given which frontier positions are its neighbors, then drop the
+// positions that leave the frontier. Returns None if that strands a chain
+// without a liberty.
+
+fn add_point(state: &[u8], neighbors: &[usize], color: u8, keep: &[bool]) -> Option<Vec<u8>> {
+    let mut codes = state.to_vec();
+
+    if color == 0 {
+        for &i in neighbors {
+            if codes[i] != 0 {
+                let chain = label(codes[i]);
+                set_liberty(&mut codes, chain);
+            }
+        }
+        codes.push(0);
+    } else {
+        let white = if color == 2 {STONE_WHITE} else {0};
+        let fresh = codes.iter().filter(|&&c| c != 0).map(|&c| label(c) + 1).max().unwrap_or(1);
+        let mut liberty = false;
+
+        for &i in neighbors {
+            if codes[i] == 0 {
+                liberty = true;
+            } else if codes[i] & STONE_WHITE == white {
+                liberty |= codes[i] & STONE_LIBERTY != 0;
+                let merged = label(codes[i]);
+                for c in codes.iter_mut() {
+                    if *c != 0 && label(*c) == merged {
+                        *c = stone_code(white, fresh);
+                    }
+                }
+            }
+        }
+
+        codes.push(stone_code(white, fresh));
+        if liberty {
+            set_liberty(&mut codes, fresh);
+        }
+    }
+
+    // Every chain without a liberty that loses its last frontier stone is
+    // dead.
+
+    for i in 0..codes.len() {
+        if !keep[i] && codes[i] != 0 && codes[i] & STONE_LIBERTY == 0 {
+            let chain = label(codes[i]);
+            let survives = (0..codes.len()).any(|j| keep[j] && codes[j] != 0 && label(codes[j]) == chain);
+            if !survives {
+                return None;
+            }
+        }
+    }
+
+    // Keep the remaining positions, with their labels renumbered in order of
+    // first appearance so that equivalent states look the same.
+
+    let mut renumber = [0u8; 64];
+    let mut next_label = 1;
+    let mut result = Vec::with_capacity(codes.len());
+
+    for i in 0..codes.len() {
+        if !keep[i] {
+            continue;
+        }
+
+        if codes[i] == 0 {
+            result.push(0);
+            continue;
+        }
+
+        let old = label(codes[i]) as usize;
+        if renumber[old] == 0 {
+            renumber[old] = next_label;
+            next_label += 1;
+        }
+        result.push((codes[i] & (STONE_WHITE | STONE_LIBERTY)) | (renumber[old] << LABEL_SHIFT));
+    }
+
+    Some(result)
+}
+
+fn stone_code(white: u8, label: u8) -> u8 {
+    white | (label << LABEL_SHIFT)
+}
+
+fn label(code: u8) -> u8 {
+    code >> LABEL_SHIFT
+}
+
+fn set_liberty(codes: &mut [u8], chain: u8) {
+    for c in codes.iter_mut() {
+        if *c != 0 && label(*c) == chain {
+            *c |= STONE_LIBERTY;
+        }
+    }
+}
+
+// Choose the order to add points in. We start from a point as far from the
+// rest of the board as we can find, then keep adding whichever point makes
+// the frontier grow the least, preferring points close to the start.
+
+fn sweep_order(board: &Board) -> Vec<usize> {
+    let n = board.point_count();
+    let start = farthest_point(board, farthest_point(board, 0));
+    let distance = distances(board, start);
+
+    let mut added = vec![false; n];
+    let mut remaining: Vec<usize> = (0..n).map(|p| board.get_neighbors(p).len()).collect();
+    let mut order = vec![];
+
+    for _ in 0..n {
+        let growth = |p: usize| {
+            let grows = if remaining[p] > 0 {1} else {0};
+            let shrinks = board.get_neighbors(p).iter().filter(|&&q| added[q] && remaining[q] == 1).count();
+            grows - shrinks as i64
+        };
+
+        let point = (0..n).filter(|&p| !added[p])
+                          .min_by_key(|&p| (growth(p), distance[p], p))
+                          .unwrap();
+
+        added[point] = true;
+        order.push(point);
+        for q in board.get_neighbors(point) {
+            remaining[q] -= 1;
+        }
+    }
+
+    order
+}
+
+fn distances(board: &Board, start: usize) -> Vec<usize> {
+    let mut distance = vec![usize::MAX; board.point_count()];
+    let mut queue = VecDeque::from([start]);
+    distance[start] = 0;
+
+    while let Some(p) = queue.pop_front() {
+        for q in board.get_neighbors(p) {
+            if distance[q] == usize::MAX {
+                distance[q] = distance[p] + 1;
+                queue.push_back(q);
+            }
+        }
+    }
+
+    distance
+}
+
+fn farthest_point(board: &Board, start: usize) -> usize {
+    let distance = distances(board, start);
+    (0..board.point_count()).filter(|&p| distance[p] != usize::MAX)
+                            .max_by_key(|&p| (distance[p], std::cmp::Reverse(p)))
+                            .unwrap()
+}
+
+//============================================================================
+// Counting by brute force.
+//============================================================================
+
+pub const BRUTE_FORCE_MAX_POINTS: usize = 12;
+
+// Playing the stones of a legal coloring one at a time never captures
+// anything, because every partial chain either is its whole chain (which has
+// a liberty) or touches one of its own points that hasn't been played yet. So
+// a coloring is legal exactly when playing it out leaves every stone in place.
+
+pub fn count_legal_positions_brute_force(board: &Board) -> u64 {
+    let n = board.point_count();
+    assert!(n <= BRUTE_FORCE_MAX_POINTS, "Too many points to count by brute force.");
+
+    let mut count = 0;
+
+    for index in 0..3u64.pow(n as u32) {
+        let mut colors = vec![Empty; n];
+        let mut rest = index;
+        for color in colors.iter_mut() {
+            *color = match rest % 3 {0 => Empty, 1 => Black, _ => White};
+            rest /= 3;
+        }
+
+        let mut pos = board.empty_position();
+        for (point, &color) in colors.iter().enumerate() {
+            if color != Empty {
+                board.play(&mut pos, color, point);
+            }
+        }
+
+        if (0..n).all(|p| pos[p] == colors[p]) {
+            count += 1;
+        }
+    }
+
+    count
+}
@@ -20,14 +20,21 @@
  * The only publically accessible method is the [] operator, which is
  * used to access the color of each point in the position. You can pass
  * a Position to the play() method of the Board that generated it to
- * play a move in the position. This modifies the Position object.
+ * play a move in the position. This modifies the Position object. The
+ * Board can also tell you which chain a point belongs to (stones of one
+ * color, or a "bubble" of empty points) and how many liberties it has, and
+ * can hash a position for checking whether it has been seen before.
  */
 
 use serde::{Serialize, Deserialize};
 use std::ops::Index;
 use std::cmp::max;
+use std::hash::{Hash, Hasher};
 use crate::engine::Color::*;
 
+pub(crate) const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+pub(crate) const FNV_PRIME:  u64 = 0x100000001b3;
+
 //============================================================================
 // Edges type.
 //============================================================================
@@ -38,7 +45,7 @@ pub type Edges = Vec::<(usize, usize)>;
 // Color enum.
 //============================================================================
 
-#[derive(Clone, PartialEq, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, Copy, Debug, Serialize, Deserialize)]
 pub enum Color {
     Empty = 0,
     Black,
@@ -77,6 +84,23 @@ impl PartialEq for Position {
 
 impl Eq for Position {}
 
+impl Hash for Position {
+    fn hash<H: Hasher>(&self, state: &mut H) {self.board_state.hash(state);}
+}
+
+//============================================================================
+// Thread safety.
+//============================================================================
+
+// The parallel solver hands copies of boards and positions to worker threads,
+// so make sure neither of them ever stops being Send + Sync.
+
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Board>();
+    assert_send_sync::<Position>();
+};
+
 //============================================================================
 // Board struct.
 //============================================================================
@@ -142,6 +166,54 @@ impl Board {
         self.neighbor_lists[point_a].clone()
     }
 
+    // Get the points in the chain containing a given point. For an empty point
+    // this is the bubble of empty points it belongs to.
+
+    pub fn chain<'a>(&self, pos: &'a Position, point: usize) -> &'a [usize] {
+        &pos.chains[pos.chain_id_backref[point]]
+    }
+
+    // Count the liberties of the chain containing a given stone.
+
+    pub fn liberty_count(&self, pos: &Position, point: usize) -> usize {
+        let mut liberties: Vec<usize> =
+            self.chain(pos, point).iter()
+                .flat_map(|&p| self.neighbor_lists[p].iter())
+                .filter(|&&n| pos[n] == Empty)
+                .copied()
+                .collect();
+
+        liberties.sort();
+        liberties.dedup();
+        liberties.len()
+    }
+
+    // Check whether playing at a given empty point would fill in one of a
+    // color's eyes (every neighbor is that color's stone) and leave the
+    // resulting chain with fewer than two liberties. Random games use this to
+    // keep from killing their own groups.
+
+    pub fn fills_needed_eye(&self, pos: &Position, color: Color, point: usize) -> bool {
+        if self.neighbor_lists[point].iter().any(|&n| pos[n] != color) {
+            return false;
+        }
+
+        // Filling the eye can't capture anything, so either the stone is still
+        // there afterwards or the whole chain was suicide.
+
+        let mut next = pos.clone();
+        self.play(&mut next, color, point);
+        next[point] != color || self.liberty_count(&next, point) < 2
+    }
+
+    // Hash a position with 64-bit FNV-1a over the colors of its points. This is
+    // written out here rather than taken from std so that it gives the same
+    // hashes with every version of Rust.
+
+    pub fn hash_position(&self, pos: &Position) -> u64 {
+        (0..self.point_count).fold(FNV_OFFSET, |hash, p| (hash ^ pos[p] as u64).wrapping_mul(FNV_PRIME))
+    }
+
     // Function to create an empty position.
 
     pub fn empty_position(&self) -> Position {
@@ -219,6 +291,27 @@ impl Board {
         }
         delta
     }
+
+    // Score the game with area-scoring: stones, plus each bubble of empty
+    // points that only touches stones of one color.
+
+    pub fn score_delta_area(&self, pos: &Position) -> i32 {
+        let mut delta = self.score_delta_stone(pos);
+
+        for chain in pos.chains.iter() {
+            if chain.is_empty() || pos[chain[0]] != Empty {continue;}
+
+            let touches = |color| chain.iter().any(|&p| self.neighbor_lists[p].iter().any(|&n| pos[n] == color));
+
+            match (touches(Black), touches(White)) {
+                (true, false) => delta += chain.len() as i32,
+                (false, true) => delta -= chain.len() as i32,
+                _ => {}
+            }
+        }
+
+        delta
+    }
 }
 
 // Private methods.
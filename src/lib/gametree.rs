@@ -6,7 +6,10 @@
  * struct, which it takes ownership of. Initially, the tree has only one node,
  * at the empty position for that board. You can perform operations such as
  * playing a move, undoing the current move without deleting the branch it's
- * on, and resetting the tree to the initial position.
+ * on, and resetting the tree to the initial position. Each node can also
+ * hold an annotation, which is saved along with the tree. The tree doesn't
+ * know what's in it; it's whatever serde value its user wants to keep with
+ * the node (see analysis.rs, which keeps the computer's analysis there).
  *
  * TODO: Move the Symbol enum into the analyzer app, and make it a type
  * parameter of the GameTree class, i.e., make it so that the user of the
@@ -21,7 +24,7 @@ use crate::gametree::Turn::*;
 use crate::gametree::TurnResult::*;
 use crate::gametree::Symbol::*;
 
-#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Turn {
     Pass,
     Play(usize),
@@ -58,14 +61,22 @@ struct GameTreeNode {
 
     position:       Position,
     only_immortal:  Position,
+
+    annotation:     Option<serde_json::Value>,
 }
 
+// Annotations are saved under "analysis", since that's all they used to be.
+
 #[derive(Serialize, Deserialize)]
 struct CompactGTN {
     children:       Vec<(Turn, usize)>,
     symbols:        Vec<(usize, Symbol)>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "analysis")]
+    annotation:     Option<serde_json::Value>,
 }
 
+#[derive(Clone)]
 pub struct GameTree {
     board:  Board,
     tree:   Vec<GameTreeNode>,
@@ -88,6 +99,8 @@ impl GameTree {
 
                     position:       board.empty_position(),
                     only_immortal:  board.empty_position(),
+
+                    annotation:     None,
                 }
             ],
             board: board,
@@ -98,6 +111,13 @@ impl GameTree {
 
     pub fn turn(&mut self, color: Color, turn: Turn) -> TurnResult {
         if self.game_over() {return FailGameAlreadyOver;}
+        self.play_on(color, turn)
+    }
+
+    // Like turn(), but a game that two passes in a row have ended carries on.
+    // GTP works this way, since a controller can play on after two passes.
+
+    pub fn play_on(&mut self, color: Color, turn: Turn) -> TurnResult {
         if self.tree[self.cursor].to_play != color {return FailNotYourTurn;}
 
         if let Some(child) = self.tree[self.cursor].children.iter().filter(|c| c.0 == turn).next() {
@@ -167,6 +187,17 @@ impl GameTree {
         self.tree[self.cursor].only_immortal[point] != Empty
     }
 
+    pub fn annotation(&self) -> Option<&serde_json::Value> {
+        self.tree[self.cursor].annotation.as_ref()
+    }
+
+    // Store an annotation in a given node (see node_id()), which doesn't have
+    // to be the one at the cursor.
+
+    pub fn set_annotation(&mut self, node: usize, annotation: serde_json::Value) {
+        self.tree[node].annotation = Some(annotation);
+    }
+
     pub fn whose_turn(&self) -> Color {
         self.tree[self.cursor].to_play
     }
@@ -175,6 +206,13 @@ impl GameTree {
         self.tree[self.cursor].turn_depth
     }
 
+    // An identifier for the node at the cursor. Nodes are never removed from
+    // the tree, so this stays valid for as long as the tree exists.
+
+    pub fn node_id(&self) -> usize {
+        self.cursor
+    }
+
     pub fn set_root_here(&mut self) {
         self.root = self.cursor;
     }
@@ -183,10 +221,34 @@ impl GameTree {
         &self.board
     }
 
+    pub fn position(&self) -> &Position {
+        &self.tree[self.cursor].position
+    }
+
+    // The positions on the branch leading to the cursor, starting with the
+    // position at the cursor and ending with the empty board. These are the
+    // positions the ko rule forbids repeating.
+
+    pub fn branch_positions(&self) -> Vec<&Position> {
+        let mut positions = vec![];
+        let mut walk = Some(self.cursor);
+
+        while let Some(node) = walk {
+            positions.push(&self.tree[node].position);
+            walk = self.tree[node].parent;
+        }
+
+        positions
+    }
+
     pub fn score_delta_stone(&self) -> i32 {
         self.board.score_delta_stone(&self.tree[self.cursor].position)
     }
 
+    pub fn score_delta_area(&self) -> i32 {
+        self.board.score_delta_area(&self.tree[self.cursor].position)
+    }
+
     // Private methods.
 
     fn add_child(&mut self, turn: Turn, position: Position) {
@@ -202,6 +264,8 @@ impl GameTree {
 
                 position:       position.clone(),
                 only_immortal:  position,
+
+                annotation:     None,
             };
 
         self.board.keep_only_immortal(&mut new_node.only_immortal);
@@ -232,8 +296,9 @@ impl GameTree {
         for node in self.tree.iter() {
             let mut compact_node = 
                 CompactGTN {
-                    children: node.children.clone(),
-                    symbols:  vec![],
+                    children:   node.children.clone(),
+                    symbols:    vec![],
+                    annotation: node.annotation.clone(),
                 };
 
             for (i, &symbol) in node.symbols.iter().enumerate() {
@@ -272,6 +337,8 @@ impl GameTree {
 
                     position:       gametree.board.empty_position(),
                     only_immortal:  gametree.board.empty_position(),
+
+                    annotation:     compact_node.annotation,
                 };
 
             for (i, symbol) in compact_node.symbols {
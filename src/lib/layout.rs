@@ -1,7 +1,159 @@
 
 use std::f32::consts::TAU;
+use std::ops::{Deref, DerefMut};
+use serde::{Serialize, Deserialize};
 
-pub type Layout = Vec::<(f32, f32)>;
+// A layout gives every point of a board a position to be drawn at. A point
+// can also have any number of ghosts: extra positions where a copy of it is
+// drawn, so that a board whose edges wrap around (like a torus) can show the
+// points across the wrap next to their neighbors. Ghosts are for display
+// only; clicking one is the same as clicking the point itself.
+//
+// A layout for a 3D board also keeps every point's position in space, and
+// its positions are those points as seen by a camera (see Camera below).
+// Another camera can give another view of the board (see view()). The z
+// coordinates in space aren't used for drawing the board as a whole, but they
+// say which layer each point is in, so that the layers can be drawn one at a
+// time.
+//
+// A Layout dereferences to the list of primary positions, so code that only
+// cares about those can treat it as a Vec. In a .san file, a plain layout is
+// written as a list of positions, as it always has been, and one with ghosts
+// or positions in space as {"points": [...], "ghosts": [[point, [x, y]], ...],
+// "space": [[x, y, z], ...]}, leaving out whichever of the last two is empty.
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "LayoutFile", into = "LayoutFile")]
+pub struct Layout {
+    points:     Vec<(f32, f32)>,
+    pub ghosts: Vec<(usize, (f32, f32))>,
+    pub space:  Vec<(f32, f32, f32)>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum LayoutFile {
+    Plain(Vec<(f32, f32)>),
+    Full {
+        points: Vec<(f32, f32)>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        ghosts: Vec<(usize, (f32, f32))>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        space:  Vec<(f32, f32, f32)>,
+    },
+}
+
+impl From<LayoutFile> for Layout {
+    fn from(file: LayoutFile) -> Self {
+        match file {
+            LayoutFile::Plain(points) => Layout::from(points),
+            LayoutFile::Full {points, ghosts, space} => Layout {points: points, ghosts: ghosts, space: space},
+        }
+    }
+}
+
+impl From<Layout> for LayoutFile {
+    fn from(layout: Layout) -> Self {
+        if layout.ghosts.is_empty() && !layout.is_3d() {
+            LayoutFile::Plain(layout.points)
+        } else {
+            LayoutFile::Full {points: layout.points, ghosts: layout.ghosts, space: layout.space}
+        }
+    }
+}
+
+impl Layout {
+    pub fn new() -> Self {
+        Layout {points: vec![], ghosts: vec![], space: vec![]}
+    }
+
+    pub fn is_3d(&self) -> bool {
+        !self.space.is_empty()
+    }
+
+    // The distinct z coordinates of a 3D layout, from lowest to highest, or
+    // nothing for a 2D one.
+
+    pub fn layers(&self) -> Vec<f32> {
+        let mut layers: Vec<f32> = self.space.iter().map(|p| p.2).collect();
+        layers.sort_by(f32::total_cmp);
+        layers.dedup();
+        layers
+    }
+
+    // A 3D board as seen by a given camera. The positions are in the same
+    // units as the points in space, so they're not scaled or moved the way
+    // this layout may have been.
+
+    pub fn view(&self, camera: Camera) -> Layout {
+        camera.project(&self.space)
+    }
+
+    pub fn add_ghost(&mut self, point: usize, position: (f32, f32)) {
+        self.ghosts.push((point, position));
+    }
+
+    // Every position a given point is drawn at, starting with its primary
+    // one.
+
+    pub fn positions(&self, point: usize) -> Vec<(f32, f32)> {
+        std::iter::once(self.points[point])
+            .chain(self.ghosts.iter().filter(|g| g.0 == point).map(|g| g.1))
+            .collect()
+    }
+
+    // Every position any point is drawn at, along with the point it belongs
+    // to and whether it's a ghost.
+
+    pub fn all_positions(&self) -> Vec<(usize, (f32, f32), bool)> {
+        self.points.iter().enumerate().map(|(i, &p)| (i, p, false))
+            .chain(self.ghosts.iter().map(|&(i, p)| (i, p, true)))
+            .collect()
+    }
+}
+
+impl Deref for Layout {
+    type Target = Vec<(f32, f32)>;
+    fn deref(&self) -> &Self::Target {&self.points}
+}
+
+impl DerefMut for Layout {
+    fn deref_mut(&mut self) -> &mut Self::Target {&mut self.points}
+}
+
+impl From<Vec<(f32, f32)>> for Layout {
+    fn from(points: Vec<(f32, f32)>) -> Self {
+        Layout {points: points, ghosts: vec![], space: vec![]}
+    }
+}
+
+impl FromIterator<(f32, f32)> for Layout {
+    fn from_iter<I: IntoIterator<Item = (f32, f32)>>(iter: I) -> Self {
+        Layout::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl IntoIterator for Layout {
+    type Item = (f32, f32);
+    type IntoIter = std::vec::IntoIter<(f32, f32)>;
+    fn into_iter(self) -> Self::IntoIter {self.points.into_iter()}
+}
+
+impl<'a> IntoIterator for &'a Layout {
+    type Item = &'a (f32, f32);
+    type IntoIter = std::slice::Iter<'a, (f32, f32)>;
+    fn into_iter(self) -> Self::IntoIter {self.points.iter()}
+}
+
+// Wrap-around edges (like the ones joining opposite sides of a torus) are
+// drawn as stubs leaving the board rather than as lines across it, each stub
+// STUB_RATIO times the spacing between points long. An edge counts as
+// wrapping around if the board wraps around (see wrap_extent()), and the
+// edge is more than WRAP_EDGE_RATIO times the spacing and at least half as
+// long as the board is across.
+
+pub const WRAP_EDGE_RATIO: f32 = 1.5;
+pub const STUB_RATIO:      f32 = 0.6;
 
 pub trait LayoutTrait {
     fn transform(self, x0: f32, x1: f32, y0: f32, y1: f32, s0: f32, s1: f32) -> Layout;
@@ -11,6 +163,7 @@ pub trait LayoutTrait {
     fn mirror(self) -> Layout;
     fn bounds(&self) -> (f32, f32, f32, f32);
     fn min_point_separation(&self) -> f32;
+    fn point_separations(&self) -> Vec<f32>;
 }
 
 impl LayoutTrait for Layout {
@@ -19,9 +172,13 @@ impl LayoutTrait for Layout {
     //     [x1 y1 | s1]
 
     fn transform(self, x0: f32, x1: f32, y0: f32, y1: f32, s0: f32, s1: f32) -> Layout {
-        self.into_iter()
-            .map(|p| (p.0 * x0 + p.1 * y0 + s0, p.0 * x1 + p.1 * y1 + s1))
-            .collect()
+        let apply = |p: (f32, f32)| (p.0 * x0 + p.1 * y0 + s0, p.0 * x1 + p.1 * y1 + s1);
+
+        Layout {
+            points: self.points.into_iter().map(apply).collect(),
+            ghosts: self.ghosts.into_iter().map(|(i, p)| (i, apply(p))).collect(),
+            space:  self.space,
+        }
     }
 
     fn scale(self, factor: f32) -> Layout {
@@ -41,19 +198,22 @@ impl LayoutTrait for Layout {
         self.transform(angle.cos(), -angle.sin(), angle.sin(), angle.cos(), 0.0, 0.0)
     }
 
+    // The bounds include the ghosts, since they're drawn too.
+
     fn bounds(&self) -> (f32, f32, f32, f32) {
-        let left   = self.iter().map(|&n| n.0).reduce(f32::min).unwrap();
-        let right  = self.iter().map(|&n| n.0).reduce(f32::max).unwrap();
-        let top    = self.iter().map(|&n| n.1).reduce(f32::min).unwrap();
-        let bottom = self.iter().map(|&n| n.1).reduce(f32::max).unwrap();
+        let all = || self.points.iter().chain(self.ghosts.iter().map(|g| &g.1));
+        let left   = all().map(|&n| n.0).reduce(f32::min).unwrap();
+        let right  = all().map(|&n| n.0).reduce(f32::max).unwrap();
+        let top    = all().map(|&n| n.1).reduce(f32::min).unwrap();
+        let bottom = all().map(|&n| n.1).reduce(f32::max).unwrap();
         (left, right, top, bottom)
     }
 
     fn min_point_separation(&self) -> f32 {
         let mut min_dist: f32 = f32::INFINITY;
 
-        for a in self {
-            for b in self {
+        for a in &self.points {
+            for b in &self.points {
                 let dist = f32::hypot(a.0 - b.0, a.1 - b.1);
                 if dist > 0.0 && dist < min_dist {
                     min_dist = dist;
@@ -63,5 +223,87 @@ impl LayoutTrait for Layout {
 
         min_dist
     }
+
+    // The distance from each point to the nearest other point, which is a
+    // measure of the local scale on boards whose layouts aren't uniform, like
+    // hyperbolic boards in the Poincaré disk.
+
+    fn point_separations(&self) -> Vec<f32> {
+        self.points.iter().map(|a| {
+            self.points.iter()
+                .map(|b| f32::hypot(a.0 - b.0, a.1 - b.1))
+                .filter(|&dist| dist > 0.0)
+                .fold(f32::INFINITY, f32::min)
+        }).collect()
+    }
+}
+
+// A camera looks at 3D points from far away, so without any perspective, and
+// gives their layout. Its yaw turns the points around the z axis, and its
+// pitch is how far down it looks from the horizontal, both as fractions of a
+// full turn. Points with higher z coordinates come out higher up the layout.
+//
+// Without perspective, the layers of a stack are drawn across each other, so
+// the default camera was picked to keep the points apart: it looks steeply
+// down and a little to one side, which puts every two points of a cube up to
+// 9 on a side at least a third of an edge apart.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub yaw:   f32,
+    pub pitch: f32,
 }
 
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {yaw: 0.052, pitch: 0.197}
+    }
+}
+
+impl Camera {
+    pub fn project(&self, points: &[(f32, f32, f32)]) -> Layout {
+        let (yaw, pitch) = (self.yaw * TAU, self.pitch * TAU);
+
+        let view = |&(x, y, z): &(f32, f32, f32)| {
+            let (x, y) = (x * yaw.cos() - y * yaw.sin(), x * yaw.sin() + y * yaw.cos());
+            (x, y * pitch.sin() - z * pitch.cos())
+        };
+
+        Layout {
+            points: points.iter().map(view).collect(),
+            ghosts: vec![],
+            space:  points.to_vec(),
+        }
+    }
+}
+
+// The board size to pass to edge_segments(). Only boards with ghosts wrap
+// around, so for any other board this is infinite and every edge is drawn in
+// full, however long it is.
+
+pub fn wrap_extent(layout: &Layout) -> f32 {
+    if layout.ghosts.is_empty() {
+        return f32::INFINITY;
+    }
+
+    let (left, right, top, bottom) = layout.bounds();
+    f32::max(right - left, bottom - top)
+}
+
+// The line segments to draw for the edge between two points, given the
+// spacing between points and the size of the board (its larger dimension).
+// Ordinary edges are a single segment; wrap-around edges are two stubs, each
+// pointing away from the other end of the edge.
+
+pub fn edge_segments(a: (f32, f32), b: (f32, f32), spacing: f32, extent: f32)
+                    -> Vec<((f32, f32), (f32, f32))> {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = f32::hypot(dx, dy);
+
+    if length <= spacing * WRAP_EDGE_RATIO || length < extent / 2.0 {
+        return vec![(a, b)];
+    }
+
+    let (ux, uy) = (dx / length * spacing * STUB_RATIO, dy / length * spacing * STUB_RATIO);
+    vec![(a, (a.0 - ux, a.1 - uy)), (b, (b.0 + ux, b.1 + uy))]
+}
@@ -3,5 +3,16 @@ pub mod engine;
 pub mod layout;
 pub mod gametree;
 pub mod boards;
+pub mod tiling;
+pub mod spec;
+pub mod transform;
 pub mod san;
+pub mod solver;
+pub mod pns;
+pub mod mcts;
+pub mod analysis;
+pub mod player;
+pub mod cgt;
+pub mod count;
+pub mod bench;
 
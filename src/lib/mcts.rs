@@ -0,0 +1,309 @@
+
+/* mcts.rs
+ *
+ * This module provides a Monte Carlo tree search engine, for getting an
+ * opinion about positions on boards that are too big to solve exactly. It
+ * works on any Board, since the only thing it needs to know about the board
+ * is which points are adjacent to which.
+ *
+ * The search is plain UCT. Each iteration walks down the tree choosing the
+ * child with the best upper confidence bound, adds one new node, and then
+ * plays a random game to the end from there. The random games never fill in
+ * a point all of whose neighbors are the player's own stones if doing so
+ * would leave the resulting chain with fewer than two liberties. Under stone
+ * scoring, filling your own eyes is good right up until you're down to two
+ * of them, so this lets the random games end in sensible positions without
+ * throwing away points.
+ *
+ * The ko rule is positional superko, as in GameTree: no move may recreate a
+ * position seen earlier on the same branch (including the branch of the
+ * GameTree leading up to the starting position). Suicide is allowed, as it
+ * is in Board::play() and GameTree, as long as it doesn't repeat a position.
+ *
+ * The result lists every move that was tried at the root along with its
+ * visit count and win rate, from the point of view of the player to move.
+ */
+
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::time::{Instant, Duration};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::engine::{Board, Position, Color};
+use crate::engine::Color::*;
+use crate::gametree::{GameTree, Turn};
+use crate::gametree::Turn::*;
+
+//============================================================================
+// Public types.
+//============================================================================
+
+// Configuration for the search. It stops after a given number of playouts or
+// a given amount of time, whichever comes first (at least one of them must be
+// set). Komi is added to White's score; a game that ends with the score
+// exactly equal to komi counts as half a win for each side.
+
+#[derive(Clone, Debug)]
+pub struct MctsConfig {
+    pub playouts:    Option<u64>,
+    pub max_time:    Option<Duration>,
+    pub exploration: f64,
+    pub komi:        f32,
+    pub seed:        Option<u64>,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            playouts:    Some(10000),
+            max_time:    None,
+            exploration: 1.4,
+            komi:        0.0,
+            seed:        None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MoveStats {
+    pub turn:     Turn,
+    pub visits:   u64,
+    pub win_rate: f64,
+}
+
+// Moves are sorted by visit count, most visited first.
+
+#[derive(Clone, Debug)]
+pub struct MctsResult {
+    pub moves:    Vec<MoveStats>,
+    pub playouts: u64,
+    pub elapsed:  Duration,
+}
+
+impl MctsResult {
+    pub fn best_move(&self) -> Option<Turn> {
+        self.moves.first().map(|m| m.turn)
+    }
+}
+
+//============================================================================
+// Search.
+//============================================================================
+
+struct Node {
+    parent:   Option<usize>,
+    turn:     Option<Turn>,
+    position: Position,
+    hash:     u64,
+    to_play:  Color,
+    passes:   usize,          // consecutive passes leading to this node
+    children: Vec<usize>,
+    untried:  Vec<Turn>,
+    visits:   u64,
+    wins:     f64,            // from the point of view of the player who moved here
+}
+
+pub struct Mcts {
+    config: MctsConfig,
+}
+
+impl Mcts {
+    pub fn new(config: MctsConfig) -> Self {
+        assert!(config.playouts.is_some() || config.max_time.is_some(),
+                "MCTS needs a playout limit or a time limit.");
+        Mcts {config: config}
+    }
+
+    // Search the position at the cursor of a given tree.
+
+    pub fn search(&self, tree: &GameTree) -> MctsResult {
+        let history: HashSet<u64> = tree.branch_positions().iter().map(|p| tree.board().hash_position(p)).collect();
+        let passes = match tree.last_turn() {
+            Some(Pass) => 1,
+            _ => 0,
+        };
+
+        self.search_position(tree.board(), tree.position(), tree.whose_turn(), passes, history)
+    }
+
+    // Search a given position directly. The history is the set of hashes of
+    // the positions that can't be repeated, and passes is the number of
+    // consecutive passes that led to this position.
+
+    pub fn search_position(&self, board: &Board, position: &Position, to_play: Color,
+                           passes: usize, history: HashSet<u64>) -> MctsResult {
+        let start = Instant::now();
+        let mut rng = match self.config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let mut nodes = vec![new_node(board, position.clone(), to_play, passes, None, None)];
+        let mut playouts = 0;
+
+        loop {
+            if self.config.playouts.is_some_and(|limit| playouts >= limit) ||
+               self.config.max_time.is_some_and(|limit| start.elapsed() > limit) {
+                break;
+            }
+
+            // Selection.
+
+            let mut current = 0;
+            let mut path_hashes = vec![];
+
+            while nodes[current].untried.is_empty() && !nodes[current].children.is_empty() {
+                current = self.select_child(&nodes, current);
+                path_hashes.push(nodes[current].hash);
+            }
+
+            let mut seen = history.clone();
+            seen.extend(path_hashes);
+
+            // Expansion. Moves in the untried list might turn out to be illegal,
+            // in which case we just throw them away.
+
+            while nodes[current].passes < 2 && !nodes[current].untried.is_empty() {
+                let index = rng.gen_range(0..nodes[current].untried.len());
+                let turn = nodes[current].untried.swap_remove(index);
+                let color = nodes[current].to_play;
+                let mut position = nodes[current].position.clone();
+                let mut passes = 0;
+
+                match turn {
+                    Pass => {passes = nodes[current].passes + 1;}
+                    Play(point) => {
+                        if !try_play(board, &mut position, color, point, &seen) {continue;}
+                    }
+                }
+
+                let child = new_node(board, position, color.reverse(), passes, Some(current), Some(turn));
+                seen.insert(child.hash);
+                nodes.push(child);
+                let id = nodes.len() - 1;
+                nodes[current].children.push(id);
+                current = id;
+                break;
+            }
+
+            // Simulation, and backpropagation of the result up to the root.
+
+            let score = playout(board, &nodes[current], &mut seen, &mut rng);
+            let black_value = if score > self.config.komi {1.0} else if score < self.config.komi {0.0} else {0.5};
+
+            let mut walk = Some(current);
+            while let Some(node) = walk {
+                nodes[node].visits += 1;
+                nodes[node].wins += match nodes[node].to_play {
+                    White => black_value,
+                    _ => 1.0 - black_value,
+                };
+                walk = nodes[node].parent;
+            }
+
+            playouts += 1;
+        }
+
+        let mut moves: Vec<MoveStats> =
+            nodes[0].children.iter()
+                .map(|&c| MoveStats {
+                    turn:     nodes[c].turn.unwrap(),
+                    visits:   nodes[c].visits,
+                    win_rate: nodes[c].wins / nodes[c].visits as f64,
+                })
+                .collect();
+
+        moves.sort_by_key(|m| Reverse(m.visits));
+
+        MctsResult {
+            moves:    moves,
+            playouts: playouts,
+            elapsed:  start.elapsed(),
+        }
+    }
+}
+
+// Private methods.
+
+impl Mcts {
+    fn select_child(&self, nodes: &[Node], node: usize) -> usize {
+        let log_visits = (nodes[node].visits as f64).ln();
+        let ucb = |c: usize| {
+            let visits = nodes[c].visits as f64;
+            nodes[c].wins / visits + self.config.exploration * (log_visits / visits).sqrt()
+        };
+
+        *nodes[node].children.iter()
+            .max_by(|&&a, &&b| ucb(a).total_cmp(&ucb(b)))
+            .unwrap()
+    }
+}
+
+fn new_node(board: &Board, position: Position, to_play: Color, passes: usize,
+            parent: Option<usize>, turn: Option<Turn>) -> Node {
+    let untried = if passes >= 2 {
+        vec![]
+    } else {
+        [Pass].into_iter()
+              .chain((0..board.point_count()).filter(|&p| position[p] == Empty).map(Play))
+              .collect()
+    };
+
+    Node {
+        parent:   parent,
+        turn:     turn,
+        hash:     board.hash_position(&position),
+        position: position,
+        to_play:  to_play,
+        passes:   passes,
+        children: vec![],
+        untried:  untried,
+        visits:   0,
+        wins:     0.0,
+    }
+}
+
+// Play a random game to the end from a given node and return the stone score.
+
+fn playout(board: &Board, node: &Node, seen: &mut HashSet<u64>, rng: &mut StdRng) -> f32 {
+    let mut position = node.position.clone();
+    let mut color = node.to_play;
+    let mut passes = node.passes;
+    let mut moves_left = board.point_count() * 3;
+
+    while passes < 2 && moves_left > 0 {
+        let mut candidates: Vec<usize> =
+            (0..board.point_count())
+                .filter(|&p| position[p] == Empty)
+                .filter(|&p| !board.fills_needed_eye(&position, color, p))
+                .collect();
+
+        let mut played = false;
+
+        while !candidates.is_empty() {
+            let point = candidates.swap_remove(rng.gen_range(0..candidates.len()));
+            let mut next = position.clone();
+
+            if try_play(board, &mut next, color, point, seen) {
+                seen.insert(board.hash_position(&next));
+                position = next;
+                played = true;
+                break;
+            }
+        }
+
+        passes = if played {0} else {passes + 1};
+        color = color.reverse();
+        moves_left -= 1;
+    }
+
+    board.score_delta_stone(&position) as f32
+}
+
+// Play a move and report whether it was legal, which is to say that it didn't
+// repeat a position we've seen.
+
+fn try_play(board: &Board, position: &mut Position, color: Color, point: usize, seen: &HashSet<u64>) -> bool {
+    board.play(position, color, point);
+    !seen.contains(&board.hash_position(position))
+}
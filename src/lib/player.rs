@@ -0,0 +1,139 @@
+
+/* player.rs
+ *
+ * This module provides move generation for the programs that let the
+ * computer play: choose_move() picks a move for the player to move at the
+ * cursor of a GameTree. Tiny boards get the exact solver, as long as it
+ * finishes in time; everything else gets MCTS. Both share one budget of
+ * thinking time.
+ *
+ * It also provides Player, a configurable move generator for comparing
+ * different engines against each other. Players are written as specs:
+ *
+ *     - "auto:SECONDS" is choose_move() with a given amount of thinking time.
+ *     - "mcts:PLAYOUTS" is MCTS with a fixed number of playouts.
+ *     - "solver" is the exact solver with no limits (tiny boards only).
+ *     - "random" plays a random legal move, never filling in one of its own
+ *       eyes, and passes when there's nothing else to do.
+ */
+
+use std::time::{Instant, Duration};
+use rand::Rng;
+use rand::rngs::StdRng;
+use crate::engine::Color::*;
+use crate::gametree::{GameTree, Turn};
+use crate::gametree::Turn::*;
+use crate::gametree::TurnResult::*;
+use crate::solver::{Solver, SolverConfig};
+use crate::mcts::{Mcts, MctsConfig};
+use crate::analysis::EXACT_MAX_POINTS;
+
+// The solver gets half of the thinking time, and MCTS gets whatever the
+// solver didn't use. Komi only matters to MCTS, since the solver's best move
+// is the one with the best score whatever the komi.
+
+pub fn choose_move(tree: &GameTree, think_time: Duration, komi: f32) -> Turn {
+    let deadline = Instant::now() + think_time;
+
+    if tree.board().point_count() <= EXACT_MAX_POINTS {
+        let config = SolverConfig {max_time: Some(think_time / 2), ..Default::default()};
+        if let Ok(result) = Solver::new(config).solve(tree) {
+            if let Some(turn) = result.best_move {
+                return turn;
+            }
+        }
+    }
+
+    let time_left = deadline.saturating_duration_since(Instant::now());
+    let config = MctsConfig {playouts: None, max_time: Some(time_left), komi: komi, ..Default::default()};
+    Mcts::new(config).search(tree).best_move().unwrap_or(Pass)
+}
+
+#[derive(Clone, Debug)]
+pub enum Player {
+    Auto(Duration),
+    Mcts(u64),
+    Solver,
+    Random,
+}
+
+impl Player {
+    pub fn from_spec(spec: &str) -> Result<Player, String> {
+        let parts: Vec<&str> = spec.split(":").collect();
+
+        match parts.as_slice() {
+            ["auto", seconds] => {
+                match seconds.parse::<f64>() {
+                    Ok(seconds) if seconds > 0.0 => Ok(Player::Auto(Duration::from_secs_f64(seconds))),
+                    _ => Err(format!("Could not parse '{}' as a number of seconds.", seconds)),
+                }
+            }
+            ["mcts", playouts] => {
+                match playouts.parse::<u64>() {
+                    Ok(playouts) if playouts > 0 => Ok(Player::Mcts(playouts)),
+                    _ => Err(format!("Could not parse '{}' as a number of playouts.", playouts)),
+                }
+            }
+            ["solver"] => Ok(Player::Solver),
+            ["random"] => Ok(Player::Random),
+            _ => Err(format!(
+                "Player '{}' does not exist. Valid players are auto:SECONDS, mcts:PLAYOUTS, solver and random.",
+                spec
+            )),
+        }
+    }
+
+    // Choose a move for the player to move at the cursor of a given tree, in a
+    // game where komi is added to White's score. Any randomness comes from the
+    // given generator, so a player with a seeded generator is repeatable
+    // (except for the time-limited ones).
+
+    pub fn choose(&self, tree: &GameTree, komi: f32, rng: &mut StdRng) -> Turn {
+        match self {
+            Player::Auto(think_time) => choose_move(tree, *think_time, komi),
+
+            Player::Mcts(playouts) => {
+                let config = MctsConfig {
+                    playouts: Some(*playouts),
+                    komi:     komi,
+                    seed:     Some(rng.gen()),
+                    ..Default::default()
+                };
+                Mcts::new(config).search(tree).best_move().unwrap_or(Pass)
+            }
+
+            Player::Solver => {
+                Solver::new(SolverConfig::default())
+                    .solve(tree)
+                    .ok()
+                    .and_then(|result| result.best_move)
+                    .unwrap_or(Pass)
+            }
+
+            Player::Random => choose_random(tree, rng),
+        }
+    }
+}
+
+fn choose_random(tree: &GameTree, rng: &mut StdRng) -> Turn {
+    let color = tree.whose_turn();
+    let board = tree.board();
+    let mut tree = tree.clone();
+
+    let mut candidates: Vec<usize> =
+        (0..board.point_count())
+            .filter(|&p| tree.color_at(p) == Empty)
+            .filter(|&p| board.get_neighbors(p).iter().any(|&n| tree.color_at(n) != color))
+            .collect();
+
+    while !candidates.is_empty() {
+        let point = candidates.swap_remove(rng.gen_range(0..candidates.len()));
+        let result = tree.turn(color, Play(point));
+
+        if result == Success || result == SuccessGameOver {
+            return Play(point);
+        }
+    }
+
+    Pass
+}
@@ -0,0 +1,308 @@
+
+/* pns.rs
+ *
+ * This module provides proof-number search, for answering yes-or-no questions
+ * about a position without working out its exact value. A Question names an
+ * attacking color and a goal, which is checked at every node of the search:
+ * it can say that the goal has been reached, that it can no longer be
+ * reached, or that it isn't known yet. At the end of the game, not knowing
+ * counts as a no. Two common kinds of goal come built in:
+ *
+ *     - Question::score_at_least(color, k) asks whether a color can finish
+ *       the game at least k points ahead under stone scoring.
+ *
+ *     - Question::keeps_stone(point, color) asks whether a color can make
+ *       sure it has a stone on a given point at the end of the game. This is
+ *       proven early as soon as the stone is immortal.
+ *
+ * The attacker plays the OR nodes of the search and the other color plays
+ * the AND nodes. Since every node of the search is a node of a GameTree, the
+ * search is over move sequences rather than positions, and the ko history of
+ * every line is exactly what GameTree::turn() enforces.
+ *
+ * When a question is answered, the result includes the proof tree (if the
+ * answer is yes) or the disproof tree (if it's no): every defense the losing
+ * side has, together with one winning reply from the winning side to each.
+ */
+
+use std::time::{Instant, Duration};
+use crate::engine::Color;
+use crate::engine::Color::*;
+use crate::gametree::{GameTree, Turn};
+use crate::gametree::Turn::*;
+use crate::gametree::TurnResult::*;
+
+//============================================================================
+// Questions.
+//============================================================================
+
+type Goal = Box<dyn Fn(&GameTree) -> Option<bool> + Send + Sync>;
+
+pub struct Question {
+    attacker: Color,
+    goal:     Goal,
+}
+
+impl Question {
+    // A question with a custom goal. If the goal returns None for a position
+    // where the game is over, the goal counts as not reached there.
+
+    pub fn new(attacker: Color, goal: impl Fn(&GameTree) -> Option<bool> + Send + Sync + 'static) -> Self {
+        assert!(attacker != Empty);
+        Question {attacker: attacker, goal: Box::new(goal)}
+    }
+
+    pub fn score_at_least(color: Color, margin: i32) -> Self {
+        let sign = match color {Black => 1, White => -1, Empty => panic!()};
+        Question::new(color, move |tree| {
+            if tree.game_over() {
+                Some(sign * tree.score_delta_stone() >= margin)
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn keeps_stone(point: usize, color: Color) -> Self {
+        Question::new(color, move |tree| {
+            if tree.color_at(point) == color && tree.is_immortal(point) {
+                Some(true)
+            } else if tree.game_over() {
+                Some(tree.color_at(point) == color)
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn attacker(&self) -> Color {
+        self.attacker
+    }
+}
+
+//============================================================================
+// Results.
+//============================================================================
+
+#[derive(Clone, Debug, Default)]
+pub struct PnsConfig {
+    pub max_nodes: Option<u64>,
+    pub max_time:  Option<Duration>,
+}
+
+// A proof or disproof tree. The root has no turn; every other node has the
+// turn that leads to it from its parent.
+
+#[derive(Clone, Debug)]
+pub struct ProofTree {
+    pub turn:     Option<Turn>,
+    pub children: Vec<ProofTree>,
+}
+
+impl ProofTree {
+    pub fn size(&self) -> usize {
+        1 + self.children.iter().map(|c| c.size()).sum::<usize>()
+    }
+
+    // Render the tree with one turn per line, indented by depth.
+
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![];
+        self.to_text_rec(0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn to_text_rec(&self, depth: usize, lines: &mut Vec<String>) {
+        if let Some(turn) = self.turn {
+            let turn_str = match turn {
+                Pass => "pass".to_string(),
+                Play(point) => point.to_string(),
+            };
+            lines.push(format!("{}{}", "|   ".repeat(depth - 1), turn_str));
+        }
+
+        for child in &self.children {
+            child.to_text_rec(depth + 1, lines);
+        }
+    }
+}
+
+// The answer is None if the search ran out of nodes or time first.
+
+pub struct PnsResult {
+    pub answer:  Option<bool>,
+    pub tree:    Option<ProofTree>,
+    pub nodes:   u64,
+    pub elapsed: Duration,
+}
+
+//============================================================================
+// Search.
+//============================================================================
+
+const INF: u64 = u64::MAX;
+
+struct PnNode {
+    turn:     Option<Turn>,
+    parent:   Option<usize>,
+    children: Vec<usize>,
+    is_or:    bool,
+    proof:    u64,
+    disproof: u64,
+}
+
+pub struct ProofNumberSearch {
+    config: PnsConfig,
+}
+
+impl ProofNumberSearch {
+    pub fn new(config: PnsConfig) -> Self {
+        ProofNumberSearch {config: config}
+    }
+
+    // Answer a question about the position at the cursor of a given tree.
+    // The search happens on a copy of the tree.
+
+    pub fn answer(&self, tree: &GameTree, question: &Question) -> PnsResult {
+        let start = Instant::now();
+        let mut tree = tree.clone();
+        let mut nodes = vec![self.new_node(&tree, question, None, None)];
+
+        while nodes[0].proof != 0 && nodes[0].disproof != 0 {
+            if self.config.max_nodes.is_some_and(|limit| nodes.len() as u64 >= limit) ||
+               self.config.max_time.is_some_and(|limit| start.elapsed() > limit) {
+                return PnsResult {
+                    answer:  None,
+                    tree:    None,
+                    nodes:   nodes.len() as u64,
+                    elapsed: start.elapsed(),
+                };
+            }
+
+            // Descend to the most-proving node, expand it, and then update the
+            // proof and disproof numbers of everything above it.
+
+            let mut current = 0;
+            let mut depth = 0;
+
+            while !nodes[current].children.is_empty() {
+                let children = &nodes[current].children;
+                current = if nodes[current].is_or {
+                    *children.iter().min_by_key(|&&c| nodes[c].proof).unwrap()
+                } else {
+                    *children.iter().min_by_key(|&&c| nodes[c].disproof).unwrap()
+                };
+
+                tree.turn(tree.whose_turn(), nodes[current].turn.unwrap());
+                depth += 1;
+            }
+
+            self.expand(&mut tree, question, &mut nodes, current);
+
+            for _ in 0..depth {
+                tree.undo();
+            }
+
+            let mut walk = Some(current);
+            while let Some(node) = walk {
+                update(&mut nodes, node);
+                walk = nodes[node].parent;
+            }
+        }
+
+        let answer = nodes[0].proof == 0;
+
+        PnsResult {
+            answer:  Some(answer),
+            tree:    Some(extract(&nodes, 0, answer)),
+            nodes:   nodes.len() as u64,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+// Private methods.
+
+impl ProofNumberSearch {
+    fn new_node(&self, tree: &GameTree, question: &Question, turn: Option<Turn>,
+                parent: Option<usize>) -> PnNode {
+        // A node where the game is over has no children to expand, so an
+        // unknown goal there would be picked as the most-proving node forever.
+
+        let (proof, disproof) = match (question.goal)(tree) {
+            Some(true)               => (0, INF),
+            Some(false)              => (INF, 0),
+            None if tree.game_over() => (INF, 0),
+            None                     => (1, 1),
+        };
+
+        PnNode {
+            turn:     turn,
+            parent:   parent,
+            children: vec![],
+            is_or:    tree.whose_turn() == question.attacker,
+            proof:    proof,
+            disproof: disproof,
+        }
+    }
+
+    // Add a child for every legal turn from a given node. The tree's cursor
+    // must be at that node.
+
+    fn expand(&self, tree: &mut GameTree, question: &Question, nodes: &mut Vec<PnNode>, node: usize) {
+        let color = tree.whose_turn();
+        let turns = [Pass].into_iter().chain((0..tree.board().point_count()).map(Play));
+
+        for turn in turns {
+            let result = tree.turn(color, turn);
+            if result == Success || result == SuccessGameOver {
+                let child = self.new_node(tree, question, Some(turn), Some(node));
+                nodes.push(child);
+                let id = nodes.len() - 1;
+                nodes[node].children.push(id);
+                tree.undo();
+            }
+        }
+    }
+}
+
+// Recompute the proof and disproof numbers of an interior node from its
+// children.
+
+fn update(nodes: &mut [PnNode], node: usize) {
+    if nodes[node].children.is_empty() {
+        return;
+    }
+
+    let proofs    = nodes[node].children.iter().map(|&c| nodes[c].proof);
+    let disproofs = nodes[node].children.iter().map(|&c| nodes[c].disproof);
+
+    let (proof, disproof) = if nodes[node].is_or {
+        (proofs.min().unwrap(), disproofs.fold(0, u64::saturating_add))
+    } else {
+        (proofs.fold(0, u64::saturating_add), disproofs.min().unwrap())
+    };
+
+    nodes[node].proof = proof;
+    nodes[node].disproof = disproof;
+}
+
+// Pull the proof tree (or disproof tree) out of a solved search. At nodes
+// where the winning side is to move we keep one winning child; at the others
+// we keep every child.
+
+fn extract(nodes: &[PnNode], node: usize, proven: bool) -> ProofTree {
+    let winner_to_move = nodes[node].is_or == proven;
+    let solved = |&&c: &&usize| if proven {nodes[c].proof == 0} else {nodes[c].disproof == 0};
+
+    let children: Vec<usize> = if winner_to_move {
+        nodes[node].children.iter().filter(solved).take(1).copied().collect()
+    } else {
+        nodes[node].children.clone()
+    };
+
+    ProofTree {
+        turn:     nodes[node].turn,
+        children: children.into_iter().map(|c| extract(nodes, c, proven)).collect(),
+    }
+}
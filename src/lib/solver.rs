@@ -0,0 +1,640 @@
+
+/* solver.rs
+ *
+ * This module provides the Solver struct, which computes the exact value of
+ * the position at the cursor of a GameTree under stone scoring, using
+ * alpha-beta search. Moves are made through GameTree::turn(), so the ko rule
+ * is the same one the analyzer enforces, including the history of the branch
+ * leading up to the starting position.
+ *
+ * A Solver is constructed from a SolverConfig, which sets the number of
+ * threads, the search window and any node, time or depth limits. Callbacks
+ * can be attached with on_progress() and on_checkpoint() to get periodic
+ * progress reports and snapshots of the search state; with config.trace set,
+ * on_progress() also hears about every step of the search. solve() returns
+ * either a SolveResult (the value, best move, principal variation and
+ * statistics) or, if a limit was hit, a Stopped value which carries a
+ * Checkpoint that can be passed to resume() later to carry on from where the
+ * search stopped. A checkpoint remembers the position and settings it was
+ * made with, and resume() won't take it for any others.
+ *
+ * There's no transposition table, so a checkpoint is just the search stack.
+ * The value of a position depends on the history of the branch leading to
+ * it, since the ko rule forbids repeating any position on the branch, so a
+ * value stored for a position alone could be wrong when the same position
+ * comes up again by a different route.
+ *
+ * Scores are always from Black's point of view (positive is good for Black).
+ */
+
+use std::cmp::{min, max};
+use std::thread;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicUsize, Ordering::SeqCst};
+use std::time::{Instant, Duration};
+use serde::{Serialize, Deserialize};
+use crate::engine::{Color, FNV_OFFSET, FNV_PRIME};
+use crate::engine::Color::*;
+use crate::gametree::{GameTree, Turn};
+use crate::gametree::Turn::*;
+use crate::gametree::TurnResult::*;
+
+//============================================================================
+// Public types.
+//============================================================================
+
+// Configuration for a Solver. The window defaults to the full range of
+// possible scores; on an empty board it can be narrowed to (0, N - 2) thanks
+// to the No Disadvantage Theorem. Values outside the window are only bounds.
+// Setting trace calls on_progress() at every step of the search as well as
+// every progress_interval, which is only worth doing on tiny boards, and only
+// makes sense with one thread.
+
+#[derive(Clone, Debug)]
+pub struct SolverConfig {
+    pub threads:             usize,
+    pub window:              Option<(i32, i32)>,
+    pub max_nodes:           Option<u64>,
+    pub max_time:            Option<Duration>,
+    pub max_depth:           Option<usize>,
+    pub progress_interval:   Option<Duration>,
+    pub checkpoint_interval: Option<Duration>,
+    pub trace:               bool,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            threads:             1,
+            window:              None,
+            max_nodes:           None,
+            max_time:            None,
+            max_depth:           None,
+            progress_interval:   None,
+            checkpoint_interval: None,
+            trace:               false,
+        }
+    }
+}
+
+// A progress report. Reports made every progress_interval have no trace
+// step; the others (see SolverConfig::trace) have one, and their depth is the
+// depth of the node the step is at, with the root at depth 0.
+
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub elapsed:   Duration,
+    pub nodes:     u64,
+    pub depth:     usize,
+    pub root_best: Option<i32>,
+    pub trace:     Option<Trace>,
+}
+
+// The steps of a search that a trace reports: a node scored without
+// searching (at the end of a game or the depth limit), a move about to be
+// searched, a cutoff (the color to play, the best value found and the bound
+// it reached), a node whose moves were all searched without a cutoff, and a
+// node's value.
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Trace {
+    Score(i32),
+    Move(Color, Turn),
+    Cutoff(Color, i32, i32),
+    CheckedAll,
+    Return(i32),
+}
+
+// Node counts include the nodes searched before the checkpoint a search was
+// resumed from. A depth-limited result used the stone score at the depth
+// limit in place of the real value of those nodes, so it isn't exact.
+
+#[derive(Clone, Debug)]
+pub struct SolverStats {
+    pub nodes:         u64,
+    pub elapsed:       Duration,
+    pub depth_limited: bool,
+}
+
+#[derive(Clone)]
+pub struct SolveResult {
+    pub value:               i32,
+    pub best_move:           Option<Turn>,
+    pub principal_variation: Vec<Turn>,
+    pub stats:               SolverStats,
+}
+
+// Returned when a search hits a limit. Parallel searches can't be resumed, so
+// they have no checkpoint.
+
+pub struct Stopped {
+    pub checkpoint: Option<Checkpoint>,
+    pub stats:      SolverStats,
+}
+
+// A snapshot of a single-threaded search: one frame for each node on the path
+// from the root to the node being searched when the snapshot was taken, and
+// whether the search had already cut anything off at the depth limit. It also
+// has a fingerprint of the position searched (see fingerprint()) and the
+// window and depth limit used, since the frames mean nothing with any others.
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    nodes:       u64,
+    frames:      Vec<Frame>,
+    depth_cut:   bool,
+    fingerprint: u64,
+    window:      (i32, i32),
+    max_depth:   Option<usize>,
+}
+
+impl Checkpoint {
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    pub fn depth_limited(&self) -> bool {
+        self.depth_cut
+    }
+}
+
+//============================================================================
+// Solver struct.
+//============================================================================
+
+type Callback<T> = Box<dyn Fn(&T) + Send + Sync>;
+
+pub struct Solver {
+    config:        SolverConfig,
+    on_progress:   Option<Callback<Progress>>,
+    on_checkpoint: Option<Callback<Checkpoint>>,
+}
+
+impl Solver {
+    pub fn new(config: SolverConfig) -> Self {
+        Solver {
+            config:        config,
+            on_progress:   None,
+            on_checkpoint: None,
+        }
+    }
+
+    pub fn config(&self) -> &SolverConfig {
+        &self.config
+    }
+
+    // Set a function to call every config.progress_interval.
+
+    pub fn on_progress(mut self, callback: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    // Set a function to call with a snapshot of the search every
+    // config.checkpoint_interval. Only single-threaded searches call this.
+
+    pub fn on_checkpoint(mut self, callback: impl Fn(&Checkpoint) + Send + Sync + 'static) -> Self {
+        self.on_checkpoint = Some(Box::new(callback));
+        self
+    }
+
+    // Solve the position at the cursor of a given tree. The tree itself is
+    // left alone; the search happens on a copy of it.
+
+    pub fn solve(&self, tree: &GameTree) -> Result<SolveResult, Stopped> {
+        self.run(tree, vec![], 0, false)
+    }
+
+    // Carry on with a search that stopped. The tree must be at the same
+    // position (reached by the same branch) it was at when the search was
+    // started, and the solver must be single-threaded and have the same
+    // window and depth limit; if not, this returns an error saying which one
+    // is different.
+
+    pub fn resume(&self, tree: &GameTree, checkpoint: Checkpoint) -> Result<Result<SolveResult, Stopped>, String> {
+        if checkpoint.fingerprint != fingerprint(tree) {
+            return Err("Checkpoint is for a different board or position.".to_string());
+        }
+
+        if checkpoint.window != self.window(tree) || checkpoint.max_depth != self.config.max_depth {
+            return Err("Checkpoint is for a different window or depth limit.".to_string());
+        }
+
+        if self.config.threads > 1 {
+            return Err("Checkpoints can only be resumed with one thread.".to_string());
+        }
+
+        Ok(self.run(tree, checkpoint.frames, checkpoint.nodes, checkpoint.depth_cut))
+    }
+}
+
+// Private methods.
+
+impl Solver {
+    fn run(&self, tree: &GameTree, frames: Vec<Frame>, nodes: u64, depth_cut: bool) -> Result<SolveResult, Stopped> {
+        let (alpha, beta) = self.window(tree);
+
+        // A checkpoint's first frame is the root's, so it knows the best value
+        // the root had found before the search stopped.
+
+        let root_best = frames.first().and_then(|frame| frame.best).unwrap_or(UNKNOWN);
+
+        let shared = Shared {
+            solver:          self,
+            start:           Instant::now(),
+            nodes:           AtomicU64::new(nodes),
+            root_best:       AtomicI32::new(root_best),
+            aborted:         AtomicBool::new(false),
+            depth_cut:       AtomicBool::new(depth_cut),
+            last_progress:   AtomicU64::new(0),
+            last_checkpoint: AtomicU64::new(0),
+            fingerprint:     fingerprint(tree),
+            window:          (alpha, beta),
+        };
+
+        let mut tree = tree.clone();
+
+        let (outcome, stack) = if self.config.threads <= 1 {
+            let mut search = Search::new(&shared, &tree, true, frames);
+            let outcome = search.solve(&mut tree, alpha, beta);
+            (outcome, Some(search.stack))
+        } else {
+            (solve_parallel(&shared, &tree, alpha, beta), None)
+        };
+
+        let stats = SolverStats {
+            nodes:         shared.nodes.load(SeqCst),
+            elapsed:       shared.start.elapsed(),
+            depth_limited: shared.depth_cut.load(SeqCst),
+        };
+
+        match outcome {
+            Ok((value, pv)) => Ok(SolveResult {
+                value:               value,
+                best_move:           pv.first().copied(),
+                principal_variation: pv,
+                stats:               stats,
+            }),
+            Err(Aborted) => Err(Stopped {
+                checkpoint: stack.map(|frames| shared.checkpoint(stats.nodes, frames)),
+                stats:      stats,
+            }),
+        }
+    }
+
+    fn window(&self, tree: &GameTree) -> (i32, i32) {
+        let point_count = tree.board().point_count() as i32;
+        self.config.window.unwrap_or((-point_count, point_count))
+    }
+}
+
+// A fingerprint of the position at the cursor of a tree, which covers the
+// board and every position on the branch leading to it, since the ko rule
+// makes those part of the position too.
+
+fn fingerprint(tree: &GameTree) -> u64 {
+    let board = tree.board();
+    let fnv = |hash: u64, value: u64| (hash ^ value).wrapping_mul(FNV_PRIME);
+    let hash = board.to_string().bytes().fold(FNV_OFFSET, |hash, byte| fnv(hash, byte as u64));
+    tree.branch_positions().into_iter().fold(hash, |hash, position| fnv(hash, board.hash_position(position)))
+}
+
+//============================================================================
+// Search internals.
+//============================================================================
+
+// Sentinel for "no root value known yet" in Shared::root_best.
+
+const UNKNOWN: i32 = i32::MIN;
+
+// State shared between every thread working on a search. Times are stored as
+// milliseconds since the start of the search so that they fit in an atomic.
+
+struct Shared<'a> {
+    solver:          &'a Solver,
+    start:           Instant,
+    nodes:           AtomicU64,
+    root_best:       AtomicI32,
+    aborted:         AtomicBool,
+    depth_cut:       AtomicBool,
+    last_progress:   AtomicU64,
+    last_checkpoint: AtomicU64,
+    fingerprint:     u64,
+    window:          (i32, i32),
+}
+
+impl Shared<'_> {
+    fn checkpoint(&self, nodes: u64, frames: Vec<Frame>) -> Checkpoint {
+        Checkpoint {
+            nodes:       nodes,
+            frames:      frames,
+            depth_cut:   self.depth_cut.load(SeqCst),
+            fingerprint: self.fingerprint,
+            window:      self.window,
+            max_depth:   self.solver.config.max_depth,
+        }
+    }
+
+    fn root_best(&self) -> Option<i32> {
+        match self.root_best.load(SeqCst) {
+            UNKNOWN => None,
+            value => Some(value),
+        }
+    }
+}
+
+// One frame per node on the path from the root to the node currently being
+// searched. Move 0 is the pass and move i + 1 is a play at point i; best and
+// pv are the best value found at this node so far and the line that gets it.
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Frame {
+    move_index: usize,
+    best:       Option<i32>,
+    pv:         Vec<Turn>,
+}
+
+struct Aborted;
+
+struct Search<'a, 'b> {
+    shared:     &'b Shared<'a>,
+    is_root:    bool,
+    root_depth: usize,
+    stack:      Vec<Frame>,
+    resume:     Vec<Frame>,
+}
+
+impl<'a, 'b> Search<'a, 'b> {
+    fn new(shared: &'b Shared<'a>, tree: &GameTree, is_root: bool, resume: Vec<Frame>) -> Self {
+        Search {
+            shared:     shared,
+            is_root:    is_root,
+            root_depth: tree.turn_depth(),
+            stack:      vec![],
+            resume:     resume,
+        }
+    }
+
+    // A search for one of the parallel workers. These search children of the
+    // root, so they never update the root value and never take checkpoints.
+
+    fn worker(shared: &'b Shared<'a>, tree: &GameTree) -> Self {
+        let mut search = Search::new(shared, tree, false, vec![]);
+        search.root_depth -= 1;
+        search
+    }
+
+    // Solve a board using alpha-beta pruning. The basic insight is that, when you are
+    // examining one of a player's possible moves, if you find a refutation by the
+    // opponent that makes the move worse than another one you've already examined, you
+    // don't need to keep analyzing that move to compute exactly *how much* worse,
+    // because you already know you aren't choosing this move.
+    //
+    // It also doesn't matter how far back the current player's alternative is. Black
+    // is guaranteed a score of 0 or better by the No Disadvantage Theorem, and on some
+    // boards, the only way for them to achieve it is to pass on move 1. However, no
+    // matter how deep you are in the game tree, if you're analyzing White's moves and
+    // have found that they can achieve -3 by some move, you don't need to keep
+    // analyzing their options on this turn.
+    //
+    // I think this is true: when a given analysis node of the maximizing player is
+    // already known to have a value of at least alpha, values up to alpha are all
+    // interchangeable with each other in all nodes below it in the game tree.
+    //
+    // When resuming from a checkpoint, each frame in self.resume tells the node
+    // at that depth which move it was in the middle of and what it had found
+    // before that move, so it can skip straight to it.
+
+    fn solve(&mut self, tree: &mut GameTree, alpha: i32, beta: i32) -> Result<(i32, Vec<Turn>), Aborted> {
+        self.count_node()?;
+
+        let color = tree.whose_turn();
+        let depth = tree.turn_depth() - self.root_depth;
+
+        if tree.game_over() {
+            self.trace(depth, Trace::Score(tree.score_delta_stone()));
+            return Ok((tree.score_delta_stone(), vec![]));
+        }
+
+        if let Some(max_depth) = self.shared.solver.config.max_depth {
+            if depth >= max_depth {
+                self.shared.depth_cut.store(true, SeqCst);
+                self.trace(depth, Trace::Score(tree.score_delta_stone()));
+                return Ok((tree.score_delta_stone(), vec![]));
+            }
+        }
+
+        let frame = if self.resume.is_empty() {
+            Frame {move_index: 0, best: None, pv: vec![]}
+        } else {
+            self.resume.remove(0)
+        };
+
+        let first_move = frame.move_index;
+        let mut cut = false;
+        self.stack.push(frame);
+
+        for move_index in first_move ..= tree.board().point_count() {
+            let best = self.stack.last().unwrap().best;
+
+            if let Some(best) = best {
+                let bound = match color {
+                    Black => (best >= beta).then_some(beta),
+                    White => (best <= alpha).then_some(alpha),
+                    Empty => panic!(),
+                };
+
+                if let Some(bound) = bound {
+                    self.trace(depth, Trace::Cutoff(color, best, bound));
+                    cut = true;
+                    break;
+                }
+            }
+
+            let turn = if move_index == 0 {Pass} else {Play(move_index - 1)};
+            let result = tree.turn(color, turn);
+
+            if result == Success || result == SuccessGameOver {
+                self.stack.last_mut().unwrap().move_index = move_index;
+                self.trace(depth, Trace::Move(color, turn));
+
+                let (value, child_pv) = match (color, best) {
+                    (_,     None)    => self.solve(tree, alpha, beta)?,
+                    (Black, Some(b)) => self.solve(tree, max(alpha, b), beta)?,
+                    (White, Some(b)) => self.solve(tree, alpha, min(beta, b))?,
+                    (Empty, _)       => panic!(),
+                };
+
+                tree.undo();
+
+                if improves(color, best, value) {
+                    let frame = self.stack.last_mut().unwrap();
+                    frame.best = Some(value);
+                    frame.pv = [vec![turn], child_pv].concat();
+
+                    if self.is_root && self.stack.len() == 1 {
+                        self.shared.root_best.store(value, SeqCst);
+                    }
+                }
+            }
+        }
+
+        if !cut {
+            self.trace(depth, Trace::CheckedAll);
+        }
+
+        let frame = self.stack.pop().unwrap();
+        let best = frame.best.expect("passing is always legal");
+        self.trace(depth, Trace::Return(best));
+        Ok((best, frame.pv))
+    }
+
+    // Report a step of the search, if the config asks for a trace.
+
+    fn trace(&self, depth: usize, step: Trace) {
+        let shared = self.shared;
+
+        if let (true, Some(callback)) = (shared.solver.config.trace, &shared.solver.on_progress) {
+            callback(&Progress {
+                elapsed:   shared.start.elapsed(),
+                nodes:     shared.nodes.load(SeqCst),
+                depth:     depth,
+                root_best: shared.root_best(),
+                trace:     Some(step),
+            });
+        }
+    }
+
+    // Count a node, and check the limits, report progress and take a
+    // checkpoint if it's time to.
+
+    fn count_node(&mut self) -> Result<(), Aborted> {
+        let shared = self.shared;
+        let solver = shared.solver;
+        let nodes = shared.nodes.fetch_add(1, SeqCst) + 1;
+        let elapsed = shared.start.elapsed();
+
+        if shared.aborted.load(SeqCst) ||
+           solver.config.max_nodes.is_some_and(|limit| nodes > limit) ||
+           solver.config.max_time.is_some_and(|limit| elapsed > limit) {
+            shared.aborted.store(true, SeqCst);
+            return Err(Aborted);
+        }
+
+        if let (Some(interval), Some(callback)) = (solver.config.progress_interval, &solver.on_progress) {
+            if claim_interval(&shared.last_progress, elapsed, interval) {
+                callback(&Progress {
+                    elapsed:   elapsed,
+                    nodes:     nodes,
+                    depth:     self.stack.len() + if self.is_root {0} else {1},
+                    root_best: shared.root_best(),
+                    trace:     None,
+                });
+            }
+        }
+
+        if let (Some(interval), Some(callback)) = (solver.config.checkpoint_interval, &solver.on_checkpoint) {
+            if self.is_root && claim_interval(&shared.last_checkpoint, elapsed, interval) {
+                callback(&shared.checkpoint(nodes, self.stack.clone()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Check whether a value found for a move is better for a given color than the
+// best found so far.
+
+fn improves(color: Color, best: Option<i32>, value: i32) -> bool {
+    match (color, best) {
+        (_,     None)    => true,
+        (Black, Some(b)) => value > b,
+        (White, Some(b)) => value < b,
+        (Empty, _)       => panic!(),
+    }
+}
+
+// Check whether a given interval has passed since the time stored in a given
+// atomic, and if so, update the atomic. Only one thread gets to claim each
+// interval.
+
+fn claim_interval(last: &AtomicU64, elapsed: Duration, interval: Duration) -> bool {
+    let now = elapsed.as_millis() as u64;
+    let prev = last.load(SeqCst);
+    now >= prev + interval.as_millis() as u64 &&
+        last.compare_exchange(prev, now, SeqCst, SeqCst).is_ok()
+}
+
+// Solve a board using several threads. This is the "young brothers wait" idea
+// applied at the root: the eldest brother (the pass) is searched first on its
+// own to get a bound, and then the remaining root moves are handed out to the
+// worker threads one at a time. Each worker searches with its own copy of the
+// tree, and the best value found so far is shared between them through an
+// atomic so that later moves get searched with the tightest window available.
+//
+// Because every value that ever goes into the shared bound is one that some
+// root move actually achieves, the window a worker uses is never tighter than
+// the one the single-threaded search would eventually have used, and the final
+// value is the same as the single-threaded search's. Only the order of the
+// work changes (so when two moves are equally good, the best move reported
+// may be a different one).
+
+fn solve_parallel(shared: &Shared, tree: &GameTree, alpha: i32, beta: i32) -> Result<(i32, Vec<Turn>), Aborted> {
+    if tree.game_over() {
+        return Ok((tree.score_delta_stone(), vec![]));
+    }
+
+    let color = tree.whose_turn();
+    let mut eldest = tree.clone();
+    eldest.turn(color, Pass);
+
+    let (value, pv) = Search::worker(shared, &eldest).solve(&mut eldest, alpha, beta)?;
+    let root = Mutex::new((value, [vec![Pass], pv].concat()));
+    shared.root_best.store(value, SeqCst);
+
+    let next_play = AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..shared.solver.config.threads {
+            scope.spawn(|| {
+                let mut tree = tree.clone();
+
+                loop {
+                    let current = shared.root_best.load(SeqCst);
+                    if color == Black && current >= beta  {break;}
+                    if color == White && current <= alpha {break;}
+
+                    let play = next_play.fetch_add(1, SeqCst);
+                    if play >= tree.board().point_count() {break;}
+
+                    let result = tree.turn(color, Play(play));
+                    if result == Success || result == SuccessGameOver {
+                        let mut search = Search::worker(shared, &tree);
+                        let outcome = match color {
+                            Black => search.solve(&mut tree, max(alpha, current), beta),
+                            White => search.solve(&mut tree, alpha, min(beta, current)),
+                            Empty => panic!(),
+                        };
+
+                        tree.undo();
+
+                        let Ok((value, pv)) = outcome else {break};
+                        let mut root = root.lock().unwrap();
+
+                        if improves(color, Some(root.0), value) {
+                            *root = (value, [vec![Play(play)], pv].concat());
+                            shared.root_best.store(value, SeqCst);
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    if shared.aborted.load(SeqCst) {
+        return Err(Aborted);
+    }
+
+    Ok(root.into_inner().unwrap())
+}
@@ -0,0 +1,402 @@
+/* spec.rs
+ *
+ * This module parses board specs. The simplest spec is a single board, like
+ * grid:9:13 or tiling:4.8^2:6 (see boards.rs for the board types), or a plain
+ * number N, which is short for square:N. Specs can also be put together:
+ *
+ *     A + B        both boards, not joined, drawn where they are (see below)
+ *     A * B        the Cartesian product of the boards
+ *     A ** B       the strong product of the boards
+ *     A @ (X, Y)   the board moved over by X and down by Y
+ *     A | OP(...)  the board with an operation done to it
+ *     (A)          A, for grouping
+ *
+ * From loosest to tightest, | binds the whole spec to its left, then + and *
+ * (or **) in the usual way, then @. So the spec
+ *
+ *     hex:3 + loop:6 @ (10, 0) | join(5, 42)
+ *
+ * moves the loop, puts the two boards together and then joins them with an
+ * edge. The hex has points 0 to 36 and the loop 37 to 42, so this joins point
+ * 5 of the hex to the last point of the loop. (The example was first written
+ * with join(5, 72), but there's no point 72, so that spec is an error.)
+ *
+ * Numbers in @ and operations can have decimal points, and can be negative.
+ *
+ * Points are numbered as the spec builds the board: on A + B, B's points come
+ * after A's, and operations refer to the points of the board they're given.
+ * So two boards joined by edges are written A + B | join(...).
+ *
+ * A + B doesn't move either board, so B usually wants moving out of A's way
+ * with @, as the loop is above. Boards whose points would land on top of each
+ * other are an error.
+ */
+
+use crate::boards::{Lae, lae_from_board_type};
+use crate::layout::{Layout, LayoutTrait};
+use crate::transform::*;
+
+// A parsed spec. The position of an operation is where its name starts in
+// the spec, and the position of a union is where its + is, for error messages.
+
+enum Spec {
+    Board(String, Vec<String>),
+    Union(Box<Spec>, usize, Box<Spec>),
+    Product(Box<Spec>, Box<Spec>),
+    StrongProduct(Box<Spec>, Box<Spec>),
+    Shift(Box<Spec>, (f64, f64)),
+    Operation(Box<Spec>, usize, String, Vec<f64>),
+}
+
+type OperationFn = fn(Lae, &[f64]) -> Result<Lae, String>;
+
+// The operations that can follow a |, written as templates. "P..." means one
+// or more points, and "A,B..." one or more pairs of points.
+
+fn operations() -> Vec<(&'static str, OperationFn)> {
+    vec![
+        ("remove(P...)",  |lae, args| {let points = some_point_args(&lae, args)?; Ok(lae_without(lae, &points))}),
+        ("keep(P...)",    |lae, args| {let points = sorted_point_args(&lae, args)?; Ok(lae_induced(lae, &points))}),
+        ("join(A,B...)",  |lae, args| {let pairs = pair_args(&lae, args)?; Ok(lae_joined(lae, &pairs))}),
+        ("scale(F)",      |lae, args| {let f = one_arg(args)?; Ok((lae.0.scale(f as f32), lae.1))}),
+        ("rotate(F)",     |lae, args| {let f = one_arg(args)?; Ok((lae.0.rotate(f as f32), lae.1))}),
+        ("subdivide",     |lae, args| {no_args(args)?; Ok(lae_subdivided(lae))}),
+        ("line",          |lae, args| {no_args(args)?; Ok(lae_line_graph(lae))}),
+        ("dual",          |lae, args| {no_args(args)?; lae_dual(lae)}),
+        ("closed-dual",   |lae, args| {no_args(args)?; lae_closed_dual(lae)}),
+    ]
+}
+
+// Build the board for a spec. Use boards::lae_from_spec() instead, which also
+// checks that the board is one Board::new() can take.
+
+pub fn build_spec(text: &str) -> Result<Lae, String> {
+    let mut parser = Parser {text: text, chars: text.chars().collect(), position: 0};
+    let spec = parser.spec()?;
+
+    parser.skip_spaces();
+    if parser.position < parser.chars.len() {
+        return Err(parser.error(parser.position, &format!("unexpected '{}'", parser.chars[parser.position])));
+    }
+
+    build(&spec, &parser)
+}
+
+fn build(spec: &Spec, parser: &Parser) -> Result<Lae, String> {
+    match spec {
+        Spec::Board(name, args) => {
+            let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+            lae_from_board_type(name, &args)
+        }
+
+        Spec::Union(a, position, b) => {
+            let (lae_a, lae_b) = (build(a, parser)?, build(b, parser)?);
+
+            if let Some((p, q)) = overlapping_points(&lae_a.0, &lae_b.0) {
+                return Err(parser.error(*position, &format!(
+                    "point {} of the board before '+' is on top of point {} of the board after it \
+                     (move one of them with @)", p, q
+                )));
+            }
+
+            Ok(lae_union(lae_a, lae_b))
+        }
+
+        Spec::Product(a, b)       => Ok(lae_product(build(a, parser)?, build(b, parser)?)),
+        Spec::StrongProduct(a, b) => Ok(lae_strong_product(build(a, parser)?, build(b, parser)?)),
+
+        Spec::Shift(a, (dx, dy)) => {
+            let (layout, edges) = build(a, parser)?;
+            Ok((layout.shift(*dx as f32, *dy as f32), edges))
+        }
+
+        Spec::Operation(a, position, name, args) => {
+            let lae = build(a, parser)?;
+            let operation = operations().into_iter().find(|op| op.0.split('(').next() == Some(name.as_str()));
+
+            match operation {
+                Some((_, function)) => function(lae, args)
+                    .map_err(|err| parser.error(*position, &format!("{}() {}", name, err))),
+                None => Err(parser.error(*position, &format!(
+                    "unknown operation '{}'\nValid operations are:\n  - {}",
+                    name, operations().iter().map(|op| op.0).collect::<Vec<_>>().join("\n  - ")
+                ))),
+            }
+        }
+    }
+}
+
+// The first point of one layout that's on top of a point of another, if any.
+
+fn overlapping_points(layout_a: &Layout, layout_b: &Layout) -> Option<(usize, usize)> {
+    layout_a.iter().enumerate().find_map(|(p, &(xa, ya))| {
+        layout_b.iter().position(|&(xb, yb)| f32::hypot(xa - xb, ya - yb) < 1e-3).map(|q| (p, q))
+    })
+}
+
+// Operation arguments.
+
+fn no_args(args: &[f64]) -> Result<(), String> {
+    match args {
+        [] => Ok(()),
+        _  => Err(format!("takes no arguments ({} given)", args.len())),
+    }
+}
+
+fn one_arg(args: &[f64]) -> Result<f64, String> {
+    match args {
+        [arg] => Ok(*arg),
+        _     => Err(format!("takes 1 argument ({} given)", args.len())),
+    }
+}
+
+fn point_args((layout, _): &Lae, args: &[f64]) -> Result<Vec<usize>, String> {
+    args.iter().map(|&arg| {
+        if arg < 0.0 || arg.fract() != 0.0 || arg as usize >= layout.len() {
+            Err(format!("was given {}, which isn't a point on the board (it has points 0 to {})",
+                        arg, layout.len() as i64 - 1))
+        } else {
+            Ok(arg as usize)
+        }
+    }).collect()
+}
+
+fn some_point_args(lae: &Lae, args: &[f64]) -> Result<Vec<usize>, String> {
+    if args.is_empty() {
+        return Err("takes at least 1 point (0 given)".to_string());
+    }
+
+    point_args(lae, args)
+}
+
+fn sorted_point_args(lae: &Lae, args: &[f64]) -> Result<Vec<usize>, String> {
+    let mut points = some_point_args(lae, args)?;
+    points.sort();
+    points.dedup();
+    Ok(points)
+}
+
+fn pair_args(lae: &Lae, args: &[f64]) -> Result<Vec<(usize, usize)>, String> {
+    let points = point_args(lae, args)?;
+
+    if points.is_empty() || points.len() % 2 != 0 {
+        return Err(format!("takes pairs of points ({} given)", points.len()));
+    }
+
+    if let Some(pair) = points.chunks(2).find(|pair| pair[0] == pair[1]) {
+        return Err(format!("can't join point {} to itself", pair[0]));
+    }
+
+    Ok(points.chunks(2).map(|pair| (pair[0], pair[1])).collect())
+}
+
+// A recursive-descent parser, with one method for each level of the grammar:
+//
+//     spec    := sum ("|" NAME ["(" [number ("," number)*] ")"])*
+//     sum     := product ("+" product)*
+//     product := placed (("*" | "**") placed)*
+//     placed  := primary ["@" "(" number "," number ")"]
+//     primary := NUMBER | NAME (":" ARG)* | "(" spec ")"
+
+struct Parser<'a> {
+    text:     &'a str,
+    chars:    Vec<char>,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn spec(&mut self) -> Result<Spec, String> {
+        let mut spec = self.sum()?;
+
+        while self.eat('|') {
+            self.skip_spaces();
+            let position = self.position;
+            let name = self.name();
+
+            if name.is_empty() {
+                return Err(self.error(position, "expected an operation after '|'"));
+            }
+
+            let mut args = vec![];
+
+            if self.eat('(') && !self.eat(')') {
+                args.push(self.number()?);
+                while self.eat(',') {
+                    args.push(self.number()?);
+                }
+                self.expect(')')?;
+            }
+
+            spec = Spec::Operation(Box::new(spec), position, name, args);
+        }
+
+        Ok(spec)
+    }
+
+    fn sum(&mut self) -> Result<Spec, String> {
+        let mut spec = self.product()?;
+
+        while self.eat('+') {
+            let position = self.position - 1;
+            spec = Spec::Union(Box::new(spec), position, Box::new(self.product()?));
+        }
+
+        Ok(spec)
+    }
+
+    fn product(&mut self) -> Result<Spec, String> {
+        let mut spec = self.placed()?;
+
+        while self.eat('*') {
+            if self.peek() == Some('*') {
+                self.position += 1;
+                spec = Spec::StrongProduct(Box::new(spec), Box::new(self.placed()?));
+            } else {
+                spec = Spec::Product(Box::new(spec), Box::new(self.placed()?));
+            }
+        }
+
+        Ok(spec)
+    }
+
+    fn placed(&mut self) -> Result<Spec, String> {
+        let spec = self.primary()?;
+
+        if !self.eat('@') {
+            return Ok(spec);
+        }
+
+        self.expect('(')?;
+        let x = self.number()?;
+        self.expect(',')?;
+        let y = self.number()?;
+        self.expect(')')?;
+
+        Ok(Spec::Shift(Box::new(spec), (x, y)))
+    }
+
+    fn primary(&mut self) -> Result<Spec, String> {
+        self.skip_spaces();
+        let position = self.position;
+
+        match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                let spec = self.spec()?;
+                self.expect(')')?;
+                Ok(spec)
+            }
+
+            Some(c) if c.is_ascii_digit() => {
+                let size = self.take_while(|c| c.is_ascii_digit());
+                match self.peek() {
+                    Some(c) if !is_separator(c) => Err(self.error(self.position, &format!(
+                        "unexpected '{}' (a plain number N is short for square:N)", c
+                    ))),
+                    _ => Ok(Spec::Board("square".to_string(), vec![size])),
+                }
+            }
+
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = self.name();
+                let mut args = vec![];
+
+                while self.peek() == Some(':') {
+                    self.position += 1;
+                    let arg_position = self.position;
+                    let arg = self.take_while(|c| !is_separator(c) && c != ':');
+
+                    if arg.is_empty() {
+                        return Err(self.error(arg_position, "expected an argument after ':'"));
+                    }
+
+                    args.push(arg);
+                }
+
+                Ok(Spec::Board(name, args))
+            }
+
+            Some(c) => Err(self.error(position, &format!("expected a board (like grid:9:9 or 19), not '{}'", c))),
+            None    => Err(self.error(position, "expected a board (like grid:9:9 or 19)")),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        self.skip_spaces();
+        let position = self.position;
+        let sign = if self.peek() == Some('-') {self.position += 1; "-"} else {""};
+        let digits = self.take_while(|c| c.is_ascii_digit() || c == '.');
+
+        match format!("{}{}", sign, digits).parse::<f64>() {
+            Ok(number) if !digits.is_empty() => Ok(number),
+            _ => Err(self.error(position, "expected a number")),
+        }
+    }
+
+    fn name(&mut self) -> String {
+        self.take_while(|c| c.is_ascii_alphanumeric() || c == '-')
+    }
+
+    // Skip spaces, then take the next character if it's the one given.
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_spaces();
+
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            match self.peek() {
+                Some(found) => Err(self.error(self.position, &format!("expected '{}', not '{}'", c, found))),
+                None        => Err(self.error(self.position, &format!("expected '{}'", c))),
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn take_while(&mut self, keep: impl Fn(char) -> bool) -> String {
+        let start = self.position;
+        while self.peek().is_some_and(&keep) {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn skip_spaces(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    // An error message pointing at a position in the spec.
+
+    fn error(&self, position: usize, message: &str) -> String {
+        let (first_line, rest) = message.split_once('\n').unwrap_or((message, ""));
+        let mut err = format!(
+            "Bad board spec: {}.\n  {}\n  {}^",
+            first_line, self.text, " ".repeat(position)
+        );
+
+        if !rest.is_empty() {
+            err = format!("{}\n{}", err, rest);
+        }
+
+        err
+    }
+}
+
+// The characters that end a board's name or arguments. Commas don't, since
+// tiling configurations have them.
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || "|+*@()".contains(c)
+}
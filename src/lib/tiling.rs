@@ -0,0 +1,667 @@
+/* tiling.rs
+ *
+ * This module makes boards from edge-to-edge tilings of the plane by regular
+ * polygons. A tiling is described by its vertex configurations: the polygons
+ * around a vertex in order, like 4.8.8 (a square and two octagons) or
+ * 3.3.3.4.4, which can also be written 3^3.4^2. A single configuration gives
+ * one of the eleven Archimedean tilings:
+ *
+ *     3^6  4^4  6^3  3^4.6  3^3.4^2  3^2.4.3.4  3.4.6.4  3.6.3.6  3.12^2
+ *     4.6.12  4.8^2
+ *
+ * Several configurations separated by commas, like 3^6,3^2.4.3.4, allow any
+ * of them at each vertex, which is how 2-uniform tilings are described.
+ *
+ * The tiling is grown outwards from a vertex at the origin. At every step the
+ * incomplete vertex inside the region with the most of its angle already
+ * covered is completed, by placing the polygons that one of the
+ * configurations calls for around it. A placement has to fit: no polygon may
+ * overlap another, and every vertex it touches must still be completable by
+ * some configuration. When there's more than one way to complete a vertex,
+ * the ways are tried in order, configurations that have been used least so
+ * far first, and if the tiling later runs into a vertex that can't be
+ * completed it backs up to the last choice and tries the next way.
+ *
+ * One configuration always pins its tiling down. Several of them may not: the
+ * vertex configurations of a 2-uniform tiling can usually be fitted together
+ * in other ways too, most of them not periodic at all. So vertices with the
+ * same configuration also have to have the same surroundings (the
+ * configurations of their neighbors, in order), which are set by the first
+ * vertex of each configuration to be surrounded. That's enough to get a
+ * 2-uniform tiling from each pair of configurations that has one, though
+ * where a pair has two of them, like 3^6,3^3.4^2, there's no saying which
+ * one comes out.
+ *
+ * Some older board types are patches of tilings too: hex:L is 3^6,
+ * honeycomb:L is 6^3 and sixfourthree:L is 3.4.6.4, each cut to a hexagon,
+ * and turtle and wheels stamp a tile onto a square grid. They keep their own
+ * generators in boards.rs. Saved games record moves by point number, and
+ * those boards number their points in the order their tiles are stamped
+ * out, over shapes that a disc or rectangle can't cut, so building them here
+ * would change which point every move in an existing game refers to.
+ *
+ * The region a tiling covers is limited to MAX_REGION_AREA, and growing it
+ * to MAX_STEPS, which is reported separately from a tiling that can't be
+ * grown at all.
+ */
+
+use std::collections::HashMap;
+use std::f64::consts::{PI, TAU};
+use crate::boards::Lae;
+use crate::layout::Layout;
+use crate::engine::Edges;
+
+const EPSILON:   f64   = 1e-6;
+const MAX_STEPS: usize = 200_000;
+
+// The biggest region a tiling may cover, in square edge lengths. Every step
+// looks through all the points so far, so the time grows with the square of
+// this.
+
+pub const MAX_REGION_AREA: f64 = 2500.0;
+
+// The part of the plane that the board covers, measured in edge lengths and
+// centered on the origin.
+
+#[derive(Clone, Copy, Debug)]
+pub enum Region {
+    Disc(f64),             // radius
+    Rectangle(f64, f64),   // width, height
+}
+
+impl Region {
+    fn area(&self) -> f64 {
+        match *self {
+            Region::Disc(radius) => PI * radius * radius,
+            Region::Rectangle(width, height) => width * height,
+        }
+    }
+
+    fn contains(&self, point: (f64, f64)) -> bool {
+        match *self {
+            Region::Disc(radius) => f64::hypot(point.0, point.1) <= radius + EPSILON,
+            Region::Rectangle(width, height) =>
+                point.0.abs() <= width / 2.0 + EPSILON && point.1.abs() <= height / 2.0 + EPSILON,
+        }
+    }
+}
+
+// Parse a list of vertex configurations like "3^2.4.3.4,4^4" into the number
+// of sides of each polygon around a vertex.
+
+pub fn parse_configurations(text: &str) -> Result<Vec<Vec<usize>>, String> {
+    let mut configs = vec![];
+
+    for config_text in text.split(',') {
+        let mut config = vec![];
+
+        for part in config_text.split('.') {
+            let (sides, count) = match part.split_once('^') {
+                Some((sides, count)) => (sides, count),
+                None => (part, "1"),
+            };
+
+            match (sides.parse::<usize>(), count.parse::<usize>()) {
+                (Ok(sides), Ok(count)) if sides >= 3 && count >= 1 => {
+                    config.extend(std::iter::repeat_n(sides, count));
+                }
+                _ => return Err(format!(
+                    "Could not parse '{}' in vertex configuration '{}'; expected something like 3.4.6.4 or 3^2.4.3.4.",
+                    part, config_text
+                )),
+            }
+        }
+
+        let total: f64 = config.iter().map(|&sides| interior_angle(sides)).sum();
+        if f64::abs(total - TAU) > EPSILON {
+            return Err(format!(
+                "The angles in vertex configuration '{}' add up to {:.1} degrees, not 360.",
+                config_text, total.to_degrees()
+            ));
+        }
+
+        configs.push(config);
+    }
+
+    Ok(configs)
+}
+
+// Generate the board for the tiling with the given vertex configurations,
+// covering the given region.
+
+pub fn lae_tiling(configs_text: &str, region: Region) -> Result<Lae, String> {
+    let configs = parse_configurations(configs_text)?;
+
+    if !region.area().is_finite() || region.area() > MAX_REGION_AREA {
+        return Err(format!(
+            "The region for a tiling can cover at most {} square edge lengths, not {:.0}.",
+            MAX_REGION_AREA, region.area()
+        ));
+    }
+
+    let patch = match grow(Patch::new(&configs), region) {
+        Ok(patch) => patch,
+        Err(Stuck::NoWay) => return Err(format!(
+            "Vertex configuration '{}' doesn't tile the plane (or not in any way this could find).", configs_text
+        )),
+        Err(Stuck::TooLong) => return Err(format!(
+            "Gave up on vertex configuration '{}' after {} steps without covering the region.", configs_text, MAX_STEPS
+        )),
+    };
+
+    // Keep the points inside the region, and the edges between them.
+
+    let inside: Vec<bool> = patch.points.iter().map(|&p| region.contains(p)).collect();
+    let mut edges: Edges =
+        patch.polygons.iter()
+             .flat_map(|polygon| (0..polygon.len()).map(move |i| (polygon[i], polygon[(i + 1) % polygon.len()])))
+             .filter(|&(a, b)| inside[a] && inside[b])
+             .map(|(a, b)| (usize::min(a, b), usize::max(a, b)))
+             .collect();
+
+    edges.sort();
+    edges.dedup();
+
+    // Trim the points left hanging by a single edge where the region's
+    // boundary cuts through polygons.
+
+    loop {
+        let mut degree = vec![0; patch.points.len()];
+        for &(a, b) in &edges {
+            degree[a] += 1;
+            degree[b] += 1;
+        }
+
+        let before = edges.len();
+        edges.retain(|&(a, b)| degree[a] > 1 && degree[b] > 1);
+        if edges.len() == before {
+            break;
+        }
+    }
+
+    // Number the points that are left in rows, from the top left.
+
+    let mut kept: Vec<usize> = edges.iter().flat_map(|&(a, b)| [a, b]).collect();
+    kept.sort();
+    kept.dedup();
+
+    let row = |p: usize| (patch.points[p].1 / EPSILON.sqrt()).round() as i64;
+    kept.sort_by(|&a, &b| {
+        row(a).cmp(&row(b)).then(patch.points[a].0.partial_cmp(&patch.points[b].0).unwrap())
+    });
+
+    let mut index = vec![0; patch.points.len()];
+    for (i, &p) in kept.iter().enumerate() {
+        index[p] = i;
+    }
+
+    let layout: Layout = kept.iter().map(|&p| (patch.points[p].0 as f32, patch.points[p].1 as f32)).collect();
+    let edges: Edges = edges.into_iter().map(|(a, b)| (index[a], index[b])).collect();
+    Ok((layout, edges))
+}
+
+// Why a patch couldn't be grown: either every way of completing its points
+// ran into a dead end, or it took more than MAX_STEPS steps.
+
+enum Stuck {
+    NoWay,
+    TooLong,
+}
+
+// Grow a patch until every point in the region is complete. Each choice with
+// ways left to try is remembered along with a mark to undo the patch back
+// to, rather than a copy of the whole patch.
+
+fn grow(mut patch: Patch, region: Region) -> Result<Patch, Stuck> {
+    let mut choices: Vec<(Mark, Vec<Placement>)> = vec![];
+
+    for _ in 0..MAX_STEPS {
+        let point = match patch.most_constrained(region) {
+            Some(point) => point,
+            None => return Ok(patch),
+        };
+
+        let mut placements = patch.placements(point);
+        placements.reverse();
+
+        loop {
+            // Back up to the last choice that still has a way left to try.
+
+            while placements.is_empty() {
+                let (mark, rest) = choices.pop().ok_or(Stuck::NoWay)?;
+                patch.undo(&mark);
+                placements = rest;
+            }
+
+            let placement = placements.pop().unwrap();
+            let mark = patch.mark();
+
+            if patch.place(&placement) {
+                if !placements.is_empty() {
+                    choices.push((mark, placements));
+                }
+                break;
+            }
+
+            patch.undo(&mark);
+        }
+    }
+
+    Err(Stuck::TooLong)
+}
+
+// A polygon's corner at a point: the direction of the edge the corner starts
+// from, going counterclockwise, and the number of sides.
+
+#[derive(Clone, Copy, Debug)]
+struct Corner {
+    angle: f64,
+    sides: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Placement {
+    point:   usize,
+    config:  usize,
+    corners: Vec<Corner>,   // the corners to add
+}
+
+struct Patch {
+    sequences: Vec<(usize, Vec<usize>)>,     // every rotation and reflection of every configuration
+    used:      Vec<usize>,                   // how many times each configuration has been placed
+    rules:     Vec<Option<Vec<(usize, usize)>>>,   // each configuration's surroundings, once known
+    points:    Vec<(f64, f64)>,
+    corners:   Vec<Vec<Corner>>,             // each point's corners
+    polygons:  Vec<Vec<usize>>,
+    centers:   Vec<((f64, f64), usize)>,     // each polygon's center and number of sides
+    cells:     HashMap<(i64, i64), Vec<usize>>,   // points by unit cell
+    tiles:     HashMap<(i64, i64), Vec<usize>>,   // polygons by unit cell
+}
+
+// What a patch had before some placements, so that they can be undone. Points
+// and polygons are only ever added, so they're undone by counting.
+
+struct Mark {
+    points:   usize,
+    polygons: usize,
+    used:     Vec<usize>,
+    rules:    Vec<Option<Vec<(usize, usize)>>>,
+}
+
+impl Patch {
+    fn new(configs: &[Vec<usize>]) -> Self {
+        let mut sequences: Vec<(usize, Vec<usize>)> = vec![];
+
+        for (index, config) in configs.iter().enumerate() {
+            let reversed: Vec<usize> = config.iter().rev().copied().collect();
+            for base in [config, &reversed] {
+                for shift in 0..base.len() {
+                    let sequence = [&base[shift..], &base[..shift]].concat();
+                    if !sequences.iter().any(|s| s.1 == sequence) {
+                        sequences.push((index, sequence));
+                    }
+                }
+            }
+        }
+
+        let mut patch = Patch {
+            sequences: sequences,
+            used:      vec![0; configs.len()],
+            rules:     vec![None; configs.len()],
+            points:    vec![],
+            corners:   vec![],
+            polygons:  vec![],
+            centers:   vec![],
+            cells:     HashMap::new(),
+            tiles:     HashMap::new(),
+        };
+
+        patch.point_at((0.0, 0.0));
+        patch
+    }
+
+    // The incomplete point in the region with the most of its angle covered,
+    // the closest to the origin of those.
+
+    fn most_constrained(&self, region: Region) -> Option<usize> {
+        let covered = |p: usize| self.corners[p].iter().map(|c| interior_angle(c.sides)).sum::<f64>();
+        let distance = |p: usize| f64::hypot(self.points[p].0, self.points[p].1);
+
+        (0..self.points.len())
+            .filter(|&p| region.contains(self.points[p]) && covered(p) < TAU - EPSILON)
+            .min_by(|&a, &b| {
+                (covered(b) - covered(a)).partial_cmp(&0.0).unwrap()
+                    .then(distance(a).partial_cmp(&distance(b)).unwrap())
+                    .then(a.cmp(&b))
+            })
+    }
+
+    // The ways to complete a point, least used configurations first.
+
+    fn placements(&self, point: usize) -> Vec<Placement> {
+        let mut placements: Vec<Placement> =
+            self.completions(&self.corners[point]).into_iter()
+                .filter(|(_config, corners)| self.fits(point, corners))
+                .map(|(config, corners)| Placement {point: point, config: config, corners: corners})
+                .collect();
+
+        placements.sort_by_key(|placement| self.used[placement.config]);
+        placements
+    }
+
+    // The ways to complete a list of corners at a point into one of the
+    // configurations, as the corners each would add. A complete point has one
+    // completion, which adds nothing, and a point that can't be completed has
+    // none.
+
+    fn completions(&self, corners: &[Corner]) -> Vec<(usize, Vec<Corner>)> {
+        let start = corners.first().map_or(0.0, |corner| corner.angle);
+        let mut completions: Vec<(usize, Vec<Corner>)> = vec![];
+
+        for (config, sequence) in &self.sequences {
+            let mut angle = start;
+            let mut all = vec![];
+
+            for &sides in sequence {
+                all.push(Corner {angle: angle, sides: sides});
+                angle = normalize(angle + interior_angle(sides));
+            }
+
+            let matches = |corner: &Corner| all.iter().any(|c| c.sides == corner.sides && same_angle(c.angle, corner.angle));
+            if !corners.iter().all(matches) {
+                continue;
+            }
+
+            let added: Vec<Corner> =
+                all.into_iter()
+                   .filter(|c| !corners.iter().any(|corner| same_angle(c.angle, corner.angle)))
+                   .collect();
+
+            let duplicate = completions.iter().any(|(_, other)| {
+                other.len() == added.len() &&
+                    other.iter().all(|a| added.iter().any(|b| a.sides == b.sides && same_angle(a.angle, b.angle)))
+            });
+
+            if !duplicate {
+                completions.push((*config, added));
+            }
+        }
+
+        completions
+    }
+
+    // Check whether the polygons for some new corners at a point fit: they
+    // mustn't overlap any polygon or cover any point, and every point they
+    // touch must still be completable.
+
+    fn fits(&self, point: usize, corners: &[Corner]) -> bool {
+        let mut added: Vec<((f64, f64), Corner)> = vec![];
+        let mut centers: Vec<((f64, f64), usize)> = vec![];
+
+        for &corner in corners {
+            let polygon = polygon_corners(self.points[point], corner);
+            let center = polygon_center(&polygon);
+            let radius = inradius(corner.sides);
+
+            let overlaps =
+                self.nearby(&self.tiles, center, 4).map(|t| self.centers[t]).chain(centers.iter().copied())
+                    .any(|(other, sides)| distance(center, other) < radius + inradius(sides) - EPSILON);
+
+            let covers =
+                self.nearby(&self.cells, center, 2)
+                    .any(|p| distance(center, self.points[p]) < radius - EPSILON);
+
+            if overlaps || covers {
+                return false;
+            }
+
+            centers.push((center, corner.sides));
+            added.extend(polygon);
+        }
+
+        for &(position, _) in &added {
+            let mut at_point: Vec<Corner> =
+                added.iter().filter(|a| distance(a.0, position) < EPSILON).map(|a| a.1).collect();
+
+            if let Some(existing) = self.find_point(position) {
+                at_point.extend(self.corners[existing].iter().copied());
+            }
+
+            if self.completions(&at_point).is_empty() {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Place the polygons for a placement, and then check that the points it
+    // touched (and their neighbors) still agree with the surroundings rules.
+    // If they don't, the patch is left half-changed, and the placement has to
+    // be undone.
+
+    fn place(&mut self, placement: &Placement) -> bool {
+        self.used[placement.config] += 1;
+        let mut touched = vec![];
+
+        for &corner in &placement.corners {
+            let polygon = polygon_corners(self.points[placement.point], corner);
+            let center = polygon_center(&polygon);
+            let ids: Vec<usize> = polygon.iter().map(|&(position, _)| self.point_at(position)).collect();
+
+            for (&id, &(_, corner)) in ids.iter().zip(&polygon) {
+                self.corners[id].push(corner);
+            }
+
+            self.tiles.entry(cell(center)).or_default().push(self.polygons.len());
+            self.centers.push((center, corner.sides));
+            touched.extend(ids.iter().copied());
+            self.polygons.push(ids);
+        }
+
+        let neighbors: Vec<usize> = touched.iter().flat_map(|&p| self.neighbors(p)).flatten().collect();
+        touched.extend(neighbors);
+        touched.sort();
+        touched.dedup();
+        touched.into_iter().all(|p| self.follows_rule(p))
+    }
+
+    fn mark(&self) -> Mark {
+        Mark {
+            points:   self.points.len(),
+            polygons: self.polygons.len(),
+            used:     self.used.clone(),
+            rules:    self.rules.clone(),
+        }
+    }
+
+    // Undo everything placed since a mark, newest first, so that each point
+    // loses its corners in the opposite order to the one they came in.
+
+    fn undo(&mut self, mark: &Mark) {
+        for polygon in (mark.polygons..self.polygons.len()).rev() {
+            for &id in self.polygons[polygon].iter().rev() {
+                self.corners[id].pop();
+            }
+            self.tiles.get_mut(&cell(self.centers[polygon].0)).unwrap().pop();
+        }
+
+        for point in (mark.points..self.points.len()).rev() {
+            self.cells.get_mut(&cell(self.points[point])).unwrap().pop();
+        }
+
+        self.polygons.truncate(mark.polygons);
+        self.centers.truncate(mark.polygons);
+        self.points.truncate(mark.points);
+        self.corners.truncate(mark.points);
+        self.used.clone_from(&mark.used);
+        self.rules.clone_from(&mark.rules);
+    }
+
+    // The configuration a point has, if it's complete.
+
+    fn config_at(&self, point: usize) -> Option<usize> {
+        let covered: f64 = self.corners[point].iter().map(|c| interior_angle(c.sides)).sum();
+        if covered < TAU - EPSILON {
+            return None;
+        }
+
+        self.completions(&self.corners[point]).first().map(|completion| completion.0)
+    }
+
+    // A point's neighbors, along the edge each of its corners starts from.
+
+    fn neighbors(&self, point: usize) -> Vec<Option<usize>> {
+        let (x, y) = self.points[point];
+        self.corners[point].iter().map(|c| self.find_point((x + c.angle.cos(), y + c.angle.sin()))).collect()
+    }
+
+    // A complete point's surroundings: going counterclockwise around it, each
+    // edge's configuration at the far end (if that point is complete too) and
+    // then the number of sides of the polygon after the edge.
+
+    fn surroundings(&self, point: usize) -> Vec<(Option<usize>, usize)> {
+        let mut corners: Vec<(Corner, Option<usize>)> =
+            self.corners[point].iter().copied().zip(self.neighbors(point)).collect();
+
+        corners.sort_by(|a, b| a.0.angle.partial_cmp(&b.0.angle).unwrap());
+        corners.into_iter().map(|(c, n)| (n.and_then(|n| self.config_at(n)), c.sides)).collect()
+    }
+
+    // Check that a point (if it's complete) has the same surroundings as every
+    // other complete point with its configuration, up to rotation and
+    // reflection. The first point of each configuration whose surroundings
+    // are all known sets the rule for the rest. This is what keeps several
+    // configurations from being mixed in a different way all over the
+    // tiling.
+
+    fn follows_rule(&mut self, point: usize) -> bool {
+        let config = match self.config_at(point) {
+            Some(config) => config,
+            None => return true,
+        };
+
+        let seen = self.surroundings(point);
+
+        match &self.rules[config] {
+            Some(rule) => same_surroundings(&seen, rule),
+            None => {
+                if seen.iter().any(|s| s.0.is_none()) {
+                    return true;
+                }
+
+                // A configuration surrounded only by itself would spread over
+                // the whole plane and leave no room for the others.
+
+                if self.rules.len() > 1 && seen.iter().all(|s| s.0 == Some(config)) {
+                    return false;
+                }
+
+                self.rules[config] = Some(seen.into_iter().map(|(n, sides)| (n.unwrap(), sides)).collect());
+                true
+            }
+        }
+    }
+
+    fn find_point(&self, position: (f64, f64)) -> Option<usize> {
+        self.nearby(&self.cells, position, 1).find(|&p| distance(self.points[p], position) < EPSILON.sqrt())
+    }
+
+    // The point at a position, adding one if there isn't one there yet.
+
+    fn point_at(&mut self, position: (f64, f64)) -> usize {
+        if let Some(point) = self.find_point(position) {
+            return point;
+        }
+
+        self.cells.entry(cell(position)).or_default().push(self.points.len());
+        self.points.push(position);
+        self.corners.push(vec![]);
+        self.points.len() - 1
+    }
+
+    // The things in the cells within a given number of cells of a position.
+
+    fn nearby<'a>(&'a self, cells: &'a HashMap<(i64, i64), Vec<usize>>, position: (f64, f64), reach: i64)
+        -> impl Iterator<Item = usize> + 'a
+    {
+        let (x, y) = cell(position);
+        (x - reach ..= x + reach)
+            .flat_map(move |cx| (y - reach ..= y + reach).map(move |cy| (cx, cy)))
+            .filter_map(move |key| cells.get(&key))
+            .flatten()
+            .copied()
+    }
+}
+
+// A regular polygon with unit sides with a given corner at a given position,
+// as the position and corner of each of its vertices. The polygon is walked
+// counterclockwise, turning by its exterior angle at each vertex.
+
+fn polygon_corners(origin: (f64, f64), corner: Corner) -> Vec<((f64, f64), Corner)> {
+    let turn = TAU / corner.sides as f64;
+    let mut position = origin;
+    let mut result = vec![];
+
+    for i in 0..corner.sides {
+        let angle = normalize(corner.angle + i as f64 * turn);
+        result.push((position, Corner {angle: angle, sides: corner.sides}));
+        position = (position.0 + angle.cos(), position.1 + angle.sin());
+    }
+
+    result
+}
+
+fn polygon_center(polygon: &[((f64, f64), Corner)]) -> (f64, f64) {
+    let n = polygon.len() as f64;
+    (polygon.iter().map(|p| p.0.0).sum::<f64>() / n,
+     polygon.iter().map(|p| p.0.1).sum::<f64>() / n)
+}
+
+// Check whether some surroundings (with unknown configurations allowed) can
+// be turned into a rule by rotating or reflecting them. Reflecting reverses
+// the order of the polygons, and moves each one to the other side of the edge
+// it was paired with.
+
+fn same_surroundings(seen: &[(Option<usize>, usize)], rule: &[(usize, usize)]) -> bool {
+    let n = seen.len();
+    if n != rule.len() {
+        return false;
+    }
+
+    let agrees = |(config, sides): (Option<usize>, usize), wanted: (usize, usize)| {
+        sides == wanted.1 && config.is_none_or(|config| config == wanted.0)
+    };
+
+    (0..n).any(|shift| {
+        (0..n).all(|i| agrees(seen[(i + shift) % n], rule[i])) ||
+        (0..n).all(|i| agrees((seen[(n + shift - i) % n].0, seen[(2 * n + shift - i - 1) % n].1), rule[i]))
+    })
+}
+
+// Helper functions.
+
+fn interior_angle(sides: usize) -> f64 {
+    PI - TAU / sides as f64
+}
+
+fn inradius(sides: usize) -> f64 {
+    0.5 / (PI / sides as f64).tan()
+}
+
+fn normalize(angle: f64) -> f64 {
+    angle.rem_euclid(TAU)
+}
+
+fn same_angle(a: f64, b: f64) -> bool {
+    let diff = normalize(a - b);
+    !(EPSILON..=TAU - EPSILON).contains(&diff)
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    f64::hypot(a.0 - b.0, a.1 - b.1)
+}
+
+fn cell(position: (f64, f64)) -> (i64, i64) {
+    (position.0.floor() as i64, position.1.floor() as i64)
+}
@@ -0,0 +1,493 @@
+/* transform.rs
+ *
+ * This module makes new boards out of old ones: putting two boards together,
+ * taking products of two boards, keeping only some of a board's points,
+ * joining points with new edges, and deriving new graphs from a board's edges
+ * and faces (subdivision, the line graph and the dual). These are the
+ * operations behind the +, *, ** and | parts of board specs (see spec.rs).
+ *
+ * Every operation works on a layout and edges together, and gives back a
+ * layout with a position for every point it makes. Ghosts and positions in
+ * space are kept wherever they still make sense.
+ */
+
+use std::collections::{HashMap, HashSet};
+use crate::boards::Lae;
+use crate::layout::{Layout, LayoutTrait};
+use crate::engine::Edges;
+
+// Two boards as one board, with no edges between them. The points of the
+// second board are numbered after those of the first, and the layouts are
+// used as they are, so the second board usually wants shifting out of the
+// way first.
+
+pub fn lae_union((layout_a, edges_a): Lae, (layout_b, edges_b): Lae) -> Lae {
+    let offset = layout_a.len();
+    let both_3d = layout_a.is_3d() && layout_b.is_3d();
+
+    let mut layout: Layout = layout_a.iter().chain(layout_b.iter()).copied().collect();
+    layout.ghosts = layout_a.ghosts.iter().copied()
+        .chain(layout_b.ghosts.iter().map(|&(point, position)| (point + offset, position)))
+        .collect();
+
+    if both_3d {
+        layout.space = layout_a.space.iter().chain(layout_b.space.iter()).copied().collect();
+    }
+
+    let edges = edges_a.into_iter()
+        .chain(edges_b.into_iter().map(|(a, b)| (a + offset, b + offset)))
+        .collect();
+
+    (layout, edges)
+}
+
+// Two boards as one, joined by edges between the given pairs of points.
+// The first point of each pair is on the first board and the second is on the
+// second board, numbered as it is there.
+
+pub fn lae_union_joined(lae_a: Lae, lae_b: Lae, pairs: &[(usize, usize)]) -> Lae {
+    let offset = lae_a.0.len();
+    let pairs: Vec<(usize, usize)> = pairs.iter().map(|&(a, b)| (a, b + offset)).collect();
+    lae_joined(lae_union(lae_a, lae_b), &pairs)
+}
+
+// The Cartesian product of two boards. Its points are pairs of points, one
+// from each board, and point (a, b) is numbered a * (size of B) + b. Two
+// pairs are neighbors if they're the same in one place and neighbors in the
+// other.
+
+pub fn lae_product((layout_a, edges_a): Lae, (layout_b, edges_b): Lae) -> Lae {
+    let size_b = layout_b.len();
+    let mut edges = Edges::new();
+
+    for a in 0..layout_a.len() {
+        edges.extend(edges_b.iter().map(|&(b1, b2)| (a * size_b + b1, a * size_b + b2)));
+    }
+
+    for &(a1, a2) in &edges_a {
+        edges.extend((0..size_b).map(|b| (a1 * size_b + b, a2 * size_b + b)));
+    }
+
+    (product_layout(&layout_a, &layout_b), edges)
+}
+
+// The strong product of two boards: the Cartesian product, plus an edge
+// between two pairs whenever they're neighbors in both places, so that each
+// square of the Cartesian product gets both its diagonals. It has the same
+// points and layout as the Cartesian product.
+
+pub fn lae_strong_product(lae_a: Lae, lae_b: Lae) -> Lae {
+    let size_b = lae_b.0.len();
+    let mut diagonals = Edges::new();
+
+    for &(a1, a2) in &undirected(&lae_a.1) {
+        for &(b1, b2) in &undirected(&lae_b.1) {
+            diagonals.push((a1 * size_b + b1, a2 * size_b + b2));
+            diagonals.push((a1 * size_b + b2, a2 * size_b + b1));
+        }
+    }
+
+    let (layout, mut edges) = lae_product(lae_a, lae_b);
+    edges.extend(diagonals);
+    (layout, edges)
+}
+
+// The layout of a product puts a copy of the second board's layout at each
+// point of the first board, spread out far enough that the copies don't
+// overlap. The copies are turned by PRODUCT_TWIST of a turn, so that the edges
+// between copies don't line up with the edges inside them and run through
+// their points.
+
+const PRODUCT_TWIST: f32 = 0.07;
+
+fn product_layout(layout_a: &Layout, layout_b: &Layout) -> Layout {
+    let layout_b = Layout::from(layout_b.to_vec()).rotate(PRODUCT_TWIST);
+    let (left, right, top, bottom) = if layout_b.is_empty() {(0.0, 0.0, 0.0, 0.0)} else {layout_b.bounds()};
+    let spacing_a = layout_a.min_point_separation();
+    let spacing_b = layout_b.min_point_separation();
+
+    let size_b = f32::max(right - left, bottom - top);
+    let gap = if spacing_b.is_finite() {spacing_b} else {1.0};
+    let spread = if spacing_a.is_finite() {(size_b + 2.0 * gap) / spacing_a} else {1.0};
+    let (cx, cy) = ((left + right) / 2.0, (top + bottom) / 2.0);
+
+    let mut layout = Layout::new();
+
+    for &(xa, ya) in layout_a.iter() {
+        for &(xb, yb) in layout_b.iter() {
+            layout.push((xa * spread + xb - cx, ya * spread + yb - cy));
+        }
+    }
+
+    layout
+}
+
+// The board made of only the given points of a board and the edges between
+// them. The points keep their order, and are numbered from 0 again.
+
+pub fn lae_induced((layout, edges): Lae, points: &[usize]) -> Lae {
+    let mut new_index = vec![None; layout.len()];
+
+    for (index, &point) in points.iter().enumerate() {
+        new_index[point] = Some(index);
+    }
+
+    let kept = |point: usize| new_index[point];
+
+    let mut new_layout: Layout = points.iter().map(|&point| layout[point]).collect();
+    new_layout.ghosts = layout.ghosts.iter()
+        .filter_map(|&(point, position)| kept(point).map(|index| (index, position)))
+        .collect();
+
+    if layout.is_3d() {
+        new_layout.space = points.iter().map(|&point| layout.space[point]).collect();
+    }
+
+    let new_edges = edges.into_iter()
+        .filter_map(|(a, b)| Some((kept(a)?, kept(b)?)))
+        .collect();
+
+    (new_layout, new_edges)
+}
+
+// A board without the given points (and the edges that touched them).
+
+pub fn lae_without(lae: Lae, points: &[usize]) -> Lae {
+    let keep: Vec<usize> = (0..lae.0.len()).filter(|point| !points.contains(point)).collect();
+    lae_induced(lae, &keep)
+}
+
+// A board with extra edges joining the given pairs of points.
+
+pub fn lae_joined((layout, mut edges): Lae, pairs: &[(usize, usize)]) -> Lae {
+    for &(a, b) in pairs {
+        if !edges.contains(&(a, b)) && !edges.contains(&(b, a)) {
+            edges.push((a, b));
+        }
+    }
+
+    (layout, edges)
+}
+
+// A board with a new point in the middle of every edge, so that each edge
+// becomes two. The old points keep their numbers (and their ghosts), and the
+// new ones come after them, in the order of the edges (counting an edge
+// listed both ways once).
+
+pub fn lae_subdivided((layout, edges): Lae) -> Lae {
+    let edges = undirected(&edges);
+    let size = layout.len();
+    let mut new_layout = layout.clone();
+    let mut new_edges = Edges::new();
+
+    for (index, &(a, b)) in edges.iter().enumerate() {
+        new_layout.push(midpoint(&layout, a, b));
+        new_edges.push((a, size + index));
+        new_edges.push((size + index, b));
+    }
+
+    if layout.is_3d() {
+        new_layout.space.extend(edges.iter().map(|&(a, b)| midpoint_in_space(&layout, a, b)));
+    }
+
+    (new_layout, new_edges)
+}
+
+// The line graph of a board: a point in the middle of each edge, numbered in
+// the order of the edges, with two points joined if their edges share an end.
+
+pub fn lae_line_graph((layout, edges): Lae) -> Lae {
+    let edges = undirected(&edges);
+    let mut new_layout: Layout = edges.iter().map(|&(a, b)| midpoint(&layout, a, b)).collect();
+    let mut edges_at = vec![vec![]; layout.len()];
+
+    for (index, &(a, b)) in edges.iter().enumerate() {
+        edges_at[a].push(index);
+        edges_at[b].push(index);
+    }
+
+    let mut new_edges = Edges::new();
+
+    for around in &edges_at {
+        for (i, &first) in around.iter().enumerate() {
+            new_edges.extend(around[i + 1..].iter().map(|&second| (first, second)));
+        }
+    }
+
+    if layout.is_3d() {
+        new_layout.space = edges.iter().map(|&(a, b)| midpoint_in_space(&layout, a, b)).collect();
+    }
+
+    (new_layout, new_edges)
+}
+
+// The planar dual of a board, found from its layout: a point in the middle of
+// each face (each region the edges split the board into), with two points
+// joined if their faces share an edge. The face around the outside of the
+// board is left out, so the dual of a 5x5 grid is a 4x4 grid. This only makes
+// sense for boards whose edges don't cross in the layout, counting edges that
+// wrap around as going through the nearest ghost, so it's an error on boards
+// whose edges do cross, and on boards that wrap around with a twist (see
+// check_untwisted()). The dual of a board that wraps around wraps around too,
+// with ghosts for the faces across the wrap.
+
+pub fn lae_dual(lae: Lae) -> Result<Lae, String> {
+    dual(lae, false)
+}
+
+// The dual of a board drawn on a sphere, like the Schlegel diagram of a
+// solid, where the outside of the board is a face like any other. It's the
+// planar dual with a point for the face around the outside as well, so the
+// dual of a hexahedron is an octahedron. That point goes just below the
+// board, since the middle of the outside face is usually inside it.
+
+pub fn lae_closed_dual(lae: Lae) -> Result<Lae, String> {
+    dual(lae, true)
+}
+
+fn dual((layout, edges): Lae, closed: bool) -> Result<Lae, String> {
+    check_untwisted(&layout, &edges)?;
+    check_uncrossed(&layout, &edges)?;
+
+    let (faces, face_of) = faces(&layout, &edges);
+    let mut new_index = vec![None; faces.len()];
+    let mut new_layout = Layout::new();
+    let mut outside = vec![];
+
+    for (face, corners) in faces.iter().enumerate() {
+        if signed_area(corners) > 0.0 {
+            new_index[face] = Some(new_layout.len());
+            new_layout.push(centroid(corners));
+        } else if closed {
+            outside.push(face);
+        }
+    }
+
+    // A board made of several pieces has an outside face for each of them,
+    // so these go one under another.
+
+    if !outside.is_empty() {
+        let (_, _, _, bottom) = layout.bounds();
+        let spacing = layout.min_point_separation();
+        let spacing = if spacing.is_finite() {spacing} else {1.0};
+
+        for (i, &face) in outside.iter().enumerate() {
+            new_index[face] = Some(new_layout.len());
+            new_layout.push((centroid(&faces[face]).0, bottom + (i + 1) as f32 * spacing));
+        }
+    }
+
+    // Each face is traced from where one of its points is, so two faces on
+    // either side of an edge that wraps around are traced a wrap apart. The
+    // edge between them then goes through a ghost of each, moved over by the
+    // difference between where the two faces have the edge's first point.
+
+    let mut seen = HashSet::new();
+    let mut new_edges = Edges::new();
+    let mut ghosts = vec![];
+
+    for &(a, b) in &edges {
+        let ((face_1, at_1), (face_2, at_2)) = (face_of[&(a, b)], face_of[&(b, a)]);
+        let (Some(f1), Some(f2)) = (new_index[face_1], new_index[face_2]) else {
+            continue;
+        };
+
+        if f1 != f2 && seen.insert((f1.min(f2), f1.max(f2))) {
+            new_edges.push((f1, f2));
+        }
+
+        let (dx, dy) = step(&layout, b, a);
+        let shift = (at_1.0 - at_2.0 - dx, at_1.1 - at_2.1 - dy);
+
+        if f32::hypot(shift.0, shift.1) > 1e-3 {
+            let (p1, p2) = (new_layout[f1], new_layout[f2]);
+
+            for ghost in [(f2, (p2.0 + shift.0, p2.1 + shift.1)), (f1, (p1.0 - shift.0, p1.1 - shift.1))] {
+                if !ghosts.iter().any(|&(point, (x, y))| point == ghost.0 && f32::hypot(x - ghost.1.0, y - ghost.1.1) < 1e-3) {
+                    ghosts.push(ghost);
+                }
+            }
+        }
+    }
+
+    new_layout.ghosts = ghosts;
+    Ok((new_layout, new_edges))
+}
+
+// Faces are only the regions the edges split the board into if no two edges
+// cross in the layout. Edges that wrap around go through the nearest ghost,
+// as they do when faces are traced.
+
+fn check_uncrossed(layout: &Layout, edges: &Edges) -> Result<(), String> {
+    let edges = undirected(edges);
+    let segments: Vec<((f32, f32), (f32, f32))> = edges.iter().map(|&(a, b)| {
+        let (dx, dy) = step(layout, a, b);
+        (layout[a], (layout[a].0 + dx, layout[a].1 + dy))
+    }).collect();
+
+    for (i, &(a, b)) in edges.iter().enumerate() {
+        for (j, &(c, d)) in edges.iter().enumerate().skip(i + 1) {
+            if a != c && a != d && b != c && b != d && crosses(segments[i], segments[j]) {
+                return Err(format!("doesn't work on boards whose edges cross, like {}-{} and {}-{}", a, b, c, d));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Whether two segments cross at a point inside both of them.
+
+fn crosses(s: ((f32, f32), (f32, f32)), t: ((f32, f32), (f32, f32))) -> bool {
+    let side = |p: (f32, f32), q: (f32, f32), r: (f32, f32)| {
+        (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+    };
+    let length = |(p, q): ((f32, f32), (f32, f32))| f32::hypot(q.0 - p.0, q.1 - p.1);
+    let tolerance = 1e-5 * length(s) * length(t);
+
+    side(s.0, s.1, t.0) * side(s.0, s.1, t.1) < -tolerance * tolerance &&
+    side(t.0, t.1, s.0) * side(t.0, t.1, s.1) < -tolerance * tolerance
+}
+
+// Faces are traced in the layout, going through the nearest ghost across a
+// wrap, which only works if every ghost is the point it belongs to moved
+// over. On a board that wraps around with a twist (like a Mobius strip or a
+// Klein bottle), the ghosts across the twist are upside down, so the faces
+// there would come out merged or missing. That shows up as two neighbors
+// whose ghosts (the ones their edges go through) aren't the same way round
+// as the points themselves.
+
+fn check_untwisted(layout: &Layout, edges: &Edges) -> Result<(), String> {
+    let mut ghosts = vec![vec![]; layout.len()];
+
+    for &(a, b) in edges {
+        let (copy_a, copy_b) = nearest_copies(layout, a, b);
+        if copy_a != layout[a] {ghosts[a].push(copy_a);}
+        if copy_b != layout[b] {ghosts[b].push(copy_b);}
+    }
+
+    for &(a, b) in edges {
+        let (dx, dy) = step(layout, a, b);
+        let length = f32::hypot(dx, dy);
+
+        for &(ax, ay) in &ghosts[a] {
+            for &(bx, by) in &ghosts[b] {
+                let (gx, gy) = (bx - ax, by - ay);
+
+                if (f32::hypot(gx, gy) - length).abs() < 1e-3 * length && f32::hypot(gx - dx, gy - dy) > 1e-3 * length {
+                    return Err("doesn't work on boards that wrap around with a twist, like Mobius strips and Klein bottles".to_string());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// The faces of a layout, each as the positions of the points around it, and
+// the face to the left of each edge going from one point to another, with
+// where that face has the edge's first point. A face is traced by following
+// edges and turning as far left as possible at each point. Positions are
+// unwrapped as a face is traced, so that a face across a wrap-around edge
+// comes out whole, next to where it starts.
+
+type Face = Vec<(f32, f32)>;
+
+fn faces(layout: &Layout, edges: &Edges) -> (Vec<Face>, HashMap<(usize, usize), (usize, (f32, f32))>) {
+    let mut around = vec![vec![]; layout.len()];
+
+    for &(a, b) in edges {
+        around[a].push(b);
+        around[b].push(a);
+    }
+
+    // Sort each point's neighbors by the direction they're in.
+
+    for (point, neighbors) in around.iter_mut().enumerate() {
+        let angle = |&neighbor: &usize| {
+            let (x, y) = step(layout, point, neighbor);
+            y.atan2(x)
+        };
+
+        neighbors.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+        neighbors.dedup();
+    }
+
+    let mut faces = vec![];
+    let mut face_of = HashMap::new();
+
+    for &(a, b) in edges {
+        for start in [(a, b), (b, a)] {
+            if face_of.contains_key(&start) {
+                continue;
+            }
+
+            let (mut from, mut to) = start;
+            let mut position = layout[from];
+            let mut corners = vec![];
+
+            while face_of.insert((from, to), (faces.len(), position)).is_none() {
+                corners.push(position);
+                let (dx, dy) = step(layout, from, to);
+                position = (position.0 + dx, position.1 + dy);
+
+                let neighbors = &around[to];
+                let back = neighbors.iter().position(|&n| n == from).unwrap();
+                (from, to) = (to, neighbors[(back + neighbors.len() - 1) % neighbors.len()]);
+            }
+
+            faces.push(corners);
+        }
+    }
+
+    (faces, face_of)
+}
+
+// Each edge once, whichever way round it's listed, since many boards list
+// every edge both ways.
+
+fn undirected(edges: &Edges) -> Edges {
+    let mut seen = HashSet::new();
+    edges.iter().copied().filter(|&(a, b)| seen.insert((a.min(b), a.max(b)))).collect()
+}
+
+// The way from one point to another, between the copies of them that are
+// closest together.
+
+fn step(layout: &Layout, from: usize, to: usize) -> (f32, f32) {
+    let (a, b) = nearest_copies(layout, from, to);
+    (b.0 - a.0, b.1 - a.1)
+}
+
+// The middle of an edge. On a board that wraps around, that's between the
+// copies of its ends that are closest together, so it may be next to a ghost.
+
+fn midpoint(layout: &Layout, a: usize, b: usize) -> (f32, f32) {
+    let (a, b) = nearest_copies(layout, a, b);
+    ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)
+}
+
+fn midpoint_in_space(layout: &Layout, a: usize, b: usize) -> (f32, f32, f32) {
+    let (a, b) = (layout.space[a], layout.space[b]);
+    ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0, (a.2 + b.2) / 2.0)
+}
+
+fn nearest_copies(layout: &Layout, a: usize, b: usize) -> ((f32, f32), (f32, f32)) {
+    let copies_b = layout.positions(b);
+    let distance = |(p, q): &((f32, f32), (f32, f32))| f32::hypot(q.0 - p.0, q.1 - p.1);
+
+    layout.positions(a).into_iter()
+        .flat_map(|p| copies_b.iter().map(move |&q| (p, q)))
+        .min_by(|x, y| distance(x).total_cmp(&distance(y)))
+        .unwrap()
+}
+
+fn signed_area(corners: &[(f32, f32)]) -> f32 {
+    let next = corners.iter().cycle().skip(1);
+    corners.iter().zip(next).map(|(p, q)| p.0 * q.1 - q.0 * p.1).sum::<f32>() / 2.0
+}
+
+fn centroid(corners: &[(f32, f32)]) -> (f32, f32) {
+    let n = corners.len() as f32;
+    (corners.iter().map(|p| p.0).sum::<f32>() / n, corners.iter().map(|p| p.1).sum::<f32>() / n)
+}
@@ -0,0 +1,179 @@
+
+#![deny(warnings)]
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use clap::Parser;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use stones::boards::lae_from_spec;
+use stones::engine::Board;
+use stones::engine::Color::*;
+use stones::gametree::GameTree;
+use stones::gametree::Turn::*;
+use stones::gametree::TurnResult::*;
+use stones::player::Player;
+use stones::san::{read_san_file, write_san_file};
+
+// Command-line arguments. The board argument is either a board spec or the
+// name of a .san file (whose board and layout are used; its moves aren't).
+// The two players are player specs (see player.rs), such as "mcts:2000" or
+// "random".
+//
+// The players alternate colors, with the first player taking Black in the
+// first game. Games are played under positional superko, and scored with the
+// --scoring rules: "stone" (the default) counts stones on the board, and
+// "area" also counts empty points surrounded by one color. Komi is added to
+// White's score, and the players know about it, but they always play for the
+// stone score. That's no worse under area scoring, where filling in your own
+// territory costs nothing. Each game is written to the output directory as
+// game-NNN.san.
+//
+// The first player's score rate counts a draw as half a win, and comes with a
+// 95% Wilson score interval.
+
+#[derive(Parser)]
+struct CLI {
+    #[arg()]                                        board:   String,
+    #[arg()]                                        first:   String,
+    #[arg()]                                        second:  String,
+    #[arg(short = 'n', long, default_value_t = 10)] games:   usize,
+    #[arg(short, long, default_value_t = 0.0)]      komi:    f32,
+    #[arg(short, long, default_value = "games")]    output:  String,
+    #[arg(short, long)]                             seed:    Option<u64>,
+    #[arg(long, default_value = "stone")]           scoring: String,
+}
+
+fn main() {
+    env::set_var("RUST_BACKTRACE", "1");
+    let args = CLI::parse();
+
+    let (board, layout) = if args.board.ends_with(".san") {
+        match read_san_file(&args.board) {
+            Ok((tree, layout)) => (tree.board().clone(), layout),
+            Err(err) => {
+                eprintln!("Error: could not read '{}': {}", args.board, err);
+                return;
+            }
+        }
+    } else {
+        match lae_from_spec(&args.board) {
+            Ok((layout, edges)) => (Board::new(edges), layout),
+            Err(err_string) => {
+                eprintln!("{}", err_string);
+                return;
+            }
+        }
+    };
+
+    let score_delta: fn(&GameTree) -> i32 = match args.scoring.as_str() {
+        "stone" => GameTree::score_delta_stone,
+        "area"  => GameTree::score_delta_area,
+        _ => {
+            eprintln!("Error: scoring '{}' does not exist. Valid scorings are stone and area.", args.scoring);
+            return;
+        }
+    };
+
+    let players = match (Player::from_spec(&args.first), Player::from_spec(&args.second)) {
+        (Ok(first), Ok(second)) => [first, second],
+        (Err(err_string), _) | (_, Err(err_string)) => {
+            eprintln!("{}", err_string);
+            return;
+        }
+    };
+
+    if let Err(err) = fs::create_dir_all(&args.output) {
+        eprintln!("Error: could not create '{}': {}", args.output, err);
+        return;
+    }
+
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let names = [&args.first, &args.second];
+    let mut wins = [0, 0];
+    let mut wins_as_black = [0, 0];
+    let mut draws = 0;
+
+    for game in 0..args.games {
+        let black = game % 2;
+        let white = 1 - black;
+        let mut game_rng = StdRng::seed_from_u64(rng.gen());
+        let mut tree = GameTree::new(board.clone());
+
+        // Play the game. An illegal move should never happen, since every
+        // player only chooses legal moves, but if one does we treat it as a
+        // pass rather than getting stuck.
+
+        while !tree.game_over() {
+            let color = tree.whose_turn();
+            let mover = if color == Black {black} else {white};
+            let turn = players[mover].choose(&tree, args.komi, &mut game_rng);
+
+            if !matches!(tree.turn(color, turn), Success | SuccessGameOver) {
+                eprintln!("Warning: {} chose an illegal move; passing instead.", names[mover]);
+                tree.turn(color, Pass);
+            }
+        }
+
+        let score = score_delta(&tree) as f32 - args.komi;
+        let result = if score > 0.0 {
+            wins[black] += 1;
+            wins_as_black[black] += 1;
+            format!("B+{}", score)
+        } else if score < 0.0 {
+            wins[white] += 1;
+            format!("W+{}", -score)
+        } else {
+            draws += 1;
+            "0".to_string()
+        };
+
+        println!("Game {:>3}: {} (B) vs {} (W): {}", game + 1, names[black], names[white], result);
+
+        let filename = Path::new(&args.output).join(format!("game-{:03}.san", game + 1));
+        if let Err(err) = write_san_file(filename.to_str().unwrap(), tree, layout.clone()) {
+            eprintln!("Error: could not write '{}': {}", filename.display(), err);
+            return;
+        }
+    }
+
+    // Report the results.
+
+    let games = args.games;
+    let games_as_black = [games.div_ceil(2), games / 2];
+
+    println!("\n{} vs {}, {} games on {} ({} scoring, komi {})",
+             names[0], names[1], games, args.board, args.scoring, args.komi);
+
+    for i in 0..2 {
+        println!("  {}: {} wins ({} of {} as Black, {} of {} as White)",
+                 names[i], wins[i],
+                 wins_as_black[i], games_as_black[i],
+                 wins[i] - wins_as_black[i], games - games_as_black[i]);
+    }
+
+    println!("  Draws: {}", draws);
+
+    if games > 0 {
+        let rate = (wins[0] as f64 + 0.5 * draws as f64) / games as f64;
+        let (low, high) = wilson_interval(rate, games as f64);
+        println!("  {} score rate: {:.1}% (95% CI {:.1}% to {:.1}%)",
+                 names[0], 100.0 * rate, 100.0 * low, 100.0 * high);
+    }
+}
+
+// The 95% Wilson score interval for a proportion observed over n trials.
+
+fn wilson_interval(p: f64, n: f64) -> (f64, f64) {
+    let z = 1.96;
+    let denominator = 1.0 + z * z / n;
+    let center = (p + z * z / (2.0 * n)) / denominator;
+    let spread = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt() / denominator;
+    ((center - spread).max(0.0), (center + spread).min(1.0))
+}
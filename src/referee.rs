@@ -0,0 +1,250 @@
+
+#![deny(warnings)]
+
+use std::env;
+use std::io;
+use std::io::prelude::*;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use clap::Parser;
+
+use stones::boards::lae_from_spec;
+use stones::engine::{Board, Color};
+use stones::engine::Color::*;
+use stones::gametree::{GameTree, Turn};
+use stones::gametree::Turn::*;
+use stones::gametree::TurnResult::*;
+use stones::san::{read_san_file, write_san_file};
+
+// Command-line arguments. The board argument is either a board spec or the
+// name of a .san file (whose board and layout are used; its moves aren't).
+// The engines are shell-style command lines, split on whitespace, for
+// programs that speak GTP and understand the stones- extension commands (see
+// gtp.rs), such as "stones_gtp --think 1".
+//
+// The referee keeps its own copy of the game. Each engine is asked for a move
+// in turn with stones-genmove, and the move is checked and then passed on to
+// the other engine with stones-play. An engine that resigns or answers with
+// an illegal move loses. The game ends after two passes in a row, and is
+// scored with stone scoring plus komi.
+
+#[derive(Parser)]
+struct CLI {
+    #[arg()]                                     board:  String,
+    #[arg(short, long)]                          black:  String,
+    #[arg(short, long)]                          white:  String,
+    #[arg(short, long, default_value_t = 0.0)]   komi:   f32,
+    #[arg(short, long)]                          output: Option<String>,
+    #[arg(short, long, default_value_t = false)] quiet:  bool,
+}
+
+fn main() {
+    env::set_var("RUST_BACKTRACE", "1");
+    let args = CLI::parse();
+
+    let (board, layout) = if args.board.ends_with(".san") {
+        match read_san_file(&args.board) {
+            Ok((tree, layout)) => (tree.board().clone(), layout),
+            Err(err) => {
+                eprintln!("Error: could not read '{}': {}", args.board, err);
+                return;
+            }
+        }
+    } else {
+        match lae_from_spec(&args.board) {
+            Ok((layout, edges)) => (Board::new(edges), layout),
+            Err(err_string) => {
+                eprintln!("{}", err_string);
+                return;
+            }
+        }
+    };
+
+    let mut black = match GtpEngine::start(&args.black) {
+        Ok(engine) => engine,
+        Err(err_string) => {eprintln!("{}", err_string); return;}
+    };
+
+    let mut white = match GtpEngine::start(&args.white) {
+        Ok(engine) => engine,
+        Err(err_string) => {eprintln!("{}", err_string); return;}
+    };
+
+    for engine in [&mut black, &mut white] {
+        let setup = engine.send(&format!("stones-load_board {}", board.to_string()))
+                          .and_then(|_| engine.send(&format!("komi {}", args.komi)));
+
+        if let Err(err_string) = setup {
+            eprintln!("{}", err_string);
+            return;
+        }
+    }
+
+    // Play the game.
+
+    let mut tree = GameTree::new(board);
+    let mut move_number = 1;
+
+    let result = loop {
+        let color = tree.whose_turn();
+        let (mover, other) = match color {
+            Black => (&mut black, &mut white),
+            _     => (&mut white, &mut black),
+        };
+
+        let response = match mover.send(&format!("stones-genmove {}", color_name(color))) {
+            Ok(response) => response,
+            Err(err_string) => {
+                eprintln!("{}", err_string);
+                return;
+            }
+        };
+
+        let turn = match parse_index(&response, tree.board().point_count()) {
+            Some(turn) => turn,
+            None if response.to_lowercase() == "resign" => {
+                break format!("{}+Resign", color_letter(color.reverse()));
+            }
+            None => {
+                eprintln!("{} answered genmove with '{}'.", mover.command, response);
+                break format!("{}+Forfeit", color_letter(color.reverse()));
+            }
+        };
+
+        let game_over = match tree.turn(color, turn) {
+            Success => false,
+            SuccessGameOver => true,
+            _ => {
+                eprintln!("{} played an illegal move ({}).", mover.command, response);
+                break format!("{}+Forfeit", color_letter(color.reverse()));
+            }
+        };
+
+        if !args.quiet {
+            println!("{:>4}. {} {}", move_number, color_letter(color), response);
+        }
+
+        move_number += 1;
+
+        if game_over {
+            let score = tree.score_delta_stone() as f32 - args.komi;
+            break if score > 0.0 {
+                format!("B+{}", score)
+            } else if score < 0.0 {
+                format!("W+{}", -score)
+            } else {
+                "0".to_string()
+            };
+        }
+
+        if let Err(err_string) = other.send(&format!("stones-play {} {}", color_name(color), response)) {
+            eprintln!("{}", err_string);
+            return;
+        }
+    };
+
+    println!("Result: {}", result);
+
+    for engine in [&mut black, &mut white] {
+        engine.quit();
+    }
+
+    if let Some(filename) = &args.output {
+        if let Err(err) = write_san_file(filename, tree, layout) {
+            eprintln!("Error: could not write '{}': {}", filename, err);
+        }
+    }
+}
+
+// A GTP engine running as a child process.
+
+struct GtpEngine {
+    command: String,
+    child:   Child,
+    stdin:   ChildStdin,
+    stdout:  io::BufReader<ChildStdout>,
+}
+
+impl GtpEngine {
+    fn start(command: &str) -> Result<Self, String> {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        if words.is_empty() {
+            return Err("Error: empty engine command.".to_string());
+        }
+
+        let mut child = Command::new(words[0])
+            .args(&words[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("Error: could not start '{}': {}", command, err))?;
+
+        Ok(GtpEngine {
+            command: command.to_string(),
+            stdin:   child.stdin.take().unwrap(),
+            stdout:  io::BufReader::new(child.stdout.take().unwrap()),
+            child:   child,
+        })
+    }
+
+    // Send a command and return the engine's response, without the leading
+    // "=" or "?" and with surrounding whitespace trimmed. A "?" response, or
+    // one that starts with neither, becomes an error.
+
+    fn send(&mut self, command: &str) -> Result<String, String> {
+        let error = |what: String| format!("Error: {} ({}): {}", self.command, command, what);
+
+        writeln!(self.stdin, "{}", command).and_then(|_| self.stdin.flush())
+                                             .map_err(|err| error(err.to_string()))?;
+
+        let mut lines = vec![];
+
+        loop {
+            let mut line = String::new();
+            match self.stdout.read_line(&mut line) {
+                Ok(0) => return Err(error("engine exited".to_string())),
+                Ok(_) => {}
+                Err(err) => return Err(error(err.to_string())),
+            }
+
+            let line = line.trim_end();
+
+            if line.is_empty() {
+                if lines.is_empty() {continue;}
+                break;
+            }
+
+            lines.push(line.to_string());
+        }
+
+        let response = lines.join("\n");
+
+        if let Some(body) = response.strip_prefix('=') {
+            Ok(body.trim().to_string())
+        } else if let Some(body) = response.strip_prefix('?') {
+            Err(error(body.trim().to_string()))
+        } else {
+            Err(error(format!("malformed response '{}'", response)))
+        }
+    }
+
+    fn quit(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.wait();
+    }
+}
+
+fn parse_index(response: &str, point_count: usize) -> Option<Turn> {
+    if response.to_lowercase() == "pass" {
+        return Some(Pass);
+    }
+
+    response.parse::<usize>().ok().filter(|&point| point < point_count).map(Play)
+}
+
+fn color_name(color: Color) -> &'static str {
+    if color == Black {"black"} else {"white"}
+}
+
+fn color_letter(color: Color) -> &'static str {
+    if color == Black {"B"} else {"W"}
+}
@@ -2,119 +2,415 @@
 #![deny(warnings)]
 
 use std::env;
+use std::fs;
 use clap::Parser;
-use std::cmp::{min, max};
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
 
 use stones::boards::lae_from_spec;
 use stones::gametree::GameTree;
 use stones::engine::Board;
+use stones::san::read_san_file;
+use stones::solver::{Solver, SolverConfig, Checkpoint, Progress, Trace};
+use stones::pns::{ProofNumberSearch, PnsConfig, Question};
+use stones::engine::Color::*;
+use stones::gametree::Turn;
 use stones::gametree::Turn::*;
 use stones::gametree::TurnResult::*;
-use stones::engine::Color::*;
 
-// Command-line arguments.
+// Command-line arguments. The board argument is either a board spec or the
+// name of a .san file. For .san files, --node gives a comma-separated list of
+// turns (point indices or "pass") to play from the file's root before solving.
+//
+// The search itself lives in stones::solver; this binary just sets it up,
+// prints its progress and result, and keeps the checkpoint file. With --trace,
+// it prints every step of the search as well, indented by depth.
+//
+// With --goal, instead of solving the position exactly, it answers a yes-or-no
+// question about it using proof-number search. Goals are "score:COLOR:K" (can
+// COLOR finish at least K points ahead?) or "stone:POINT:COLOR" (can COLOR
+// make sure it has a stone on POINT at the end?).
+//
+// With --regions, it first prints the position's independent regions and
+// their combinatorial game values (see stones::cgt), along with the result
+// they predict and the result of playing them out, before going on to solve
+// it.
 
 #[derive(Parser)]
 struct CLI {
-    #[arg()] board_spec: String,
+    #[arg()]                                     board:            String,
+    #[arg(short, long)]                          node:             Option<String>,
+    #[arg(short, long, default_value_t = 1)]     threads:          usize,
+    #[arg(long)]                                 max_nodes:        Option<u64>,
+    #[arg(long)]                                 max_time:         Option<f64>,
+    #[arg(long)]                                 max_depth:        Option<usize>,
+    #[arg(short, long)]                          progress:         Option<f64>,
+    #[arg(short, long)]                          checkpoint:       Option<String>,
+    #[arg(long, default_value_t = 60.0)]         checkpoint_every: f64,
+    #[arg(short, long)]                          goal:             Option<String>,
+    #[arg(long, default_value_t = false)]        show_proof:       bool,
+    #[arg(short, long, default_value_t = false)] regions:          bool,
+    #[arg(long, default_value_t = false)]        trace:            bool,
 }
 
 fn main() {
     env::set_var("RUST_BACKTRACE", "1");
     let args = CLI::parse();
 
-    // Create the game tree from the board spec passed on the command line.
+    if args.checkpoint.is_some() && args.threads > 1 {
+        eprintln!("Error: cannot use --checkpoint with more than one thread.");
+        return;
+    }
+
+    if args.trace && args.threads > 1 {
+        eprintln!("Error: cannot use --trace with more than one thread.");
+        return;
+    }
 
-    let lae = lae_from_spec(&args.board_spec);
-    let (layout, edges) = match lae {
-        Err(err_string) => {
-            eprintln!("{}", err_string);
+    // Create the game tree we're solving from, along with the window to solve
+    // it in (if we know anything better than the full range of scores).
+
+    let (tree, window) = if args.board.ends_with(".san") {
+        let mut tree = match read_san_file(&args.board) {
+            Ok((tree, _layout)) => tree,
+            Err(err) => {
+                eprintln!("Error: could not read '{}': {}", args.board, err);
+                return;
+            }
+        };
+
+        if let Some(path) = &args.node {
+            if let Err(err_string) = walk_to_node(&mut tree, path) {
+                eprintln!("{}", err_string);
+                return;
+            }
+        }
+
+        // We don't know anything about the position we're starting from, so
+        // the only safe window is the full range of possible scores.
+
+        (tree, None)
+    } else {
+        if args.node.is_some() {
+            eprintln!("Error: --node can only be used with a .san file.");
             return;
-        },
-        Ok(result) => result
+        }
+
+        let (layout, edges) = match lae_from_spec(&args.board) {
+            Err(err_string) => {
+                eprintln!("{}", err_string);
+                return;
+            },
+            Ok(result) => result
+        };
+
+        //println!("Edges: {:?}", edges);
+        let point_count = layout.len();
+        (GameTree::new(Board::new(edges)), Some((0, point_count as i32 - 2)))
     };
 
-    //println!("Edges: {:?}", edges);
-    let point_count = layout.len();
-    let mut tree = GameTree::new(Board::new(edges));
+    // Report the regions if we were asked to.
 
-    //tree.turn(Black, Play(0));
-    //println!("Result after A1: {}", solve(&mut tree, 0, point_count as i32 - 2));
-    //tree.undo();
-    //tree.turn(Black, Play(1));
-    //println!("Result after B2: {}", solve(&mut tree, 0, point_count as i32 - 2));
+    if args.regions {
+        let report = tree.analyze_regions();
+        println!("{}", report.to_text());
 
-    println!("\nResult: {}", solve(&mut tree, 0, point_count as i32 - 2));
-}
+        match report.predicted(tree.whose_turn()) {
+            Some(score) => println!("Predicted result: {}", score),
+            None        => println!("Predicted result: unknown"),
+        }
 
-// Solve a board using alpha-beta pruning. The basic insight is that, when you are
-// examining one of a player's possible moves, if you find a refutation by the
-// opponent that makes the move worse than another one you've already examined, you
-// don't need to keep analyzing that move to compute exactly *how much* worse,
-// because you already know you aren't choosing this move.
-//
-// It also doesn't matter how far back the current player's alternative is. Black
-// is guaranteed a score of 0 or better by the No Disadvantage Theorem, and on some
-// boards, the only way for them to achieve it is to pass on move 1. However, no
-// matter how deep you are in the game tree, if you're analyzing White's moves and
-// have found that they can achieve -3 by some move, you don't need to keep
-// analyzing their options on this turn.
-// 
-// I think this is true: when a given analysis node of the maximizing player is
-// already known to have a value of at least alpha, values up to alpha are all
-// interchangeable with each other in all nodes below it in the game tree.
-//
-// No clue if this code is correct yet!
+        match report.played_out(tree.whose_turn()) {
+            Some(score) => println!("Played out: {}\n", score),
+            None        => println!("Played out: unknown\n"),
+        }
+    }
+
+    // Answer a yes-or-no question if we were asked one.
+
+    if let Some(goal) = &args.goal {
+        if args.threads > 1 || args.checkpoint.is_some() {
+            eprintln!("Error: cannot use --threads or --checkpoint with --goal.");
+            return;
+        }
+
+        let question = match parse_goal(goal, tree.board().point_count()) {
+            Ok(question) => question,
+            Err(err_string) => {
+                eprintln!("{}", err_string);
+                return;
+            }
+        };
 
-fn solve(tree: &mut GameTree, alpha: i32, beta: i32) -> i32 {
-    let color = tree.whose_turn();
-    let indent = "|   ".repeat(tree.turn_depth());
-    let color_str = match color {Black => "Black", White => "White", Empty => panic!()};
+        let pns = ProofNumberSearch::new(PnsConfig {
+            max_nodes: args.max_nodes,
+            max_time:  args.max_time.map(Duration::from_secs_f64),
+        });
 
-    if tree.game_over() {
-        let score = tree.score_delta_stone();
-        println!("{indent}Score: {score}");
-        return score;
+        let result = pns.answer(&tree, &question);
+
+        match result.answer {
+            Some(true)  => println!("\nProven."),
+            Some(false) => println!("\nDisproven."),
+            None        => println!("\nUnknown (search stopped)."),
+        }
+
+        if let Some(proof_tree) = result.tree {
+            println!("Proof tree size: {}", proof_tree.size());
+            if args.show_proof {
+                println!("{}", proof_tree.to_text());
+            }
+        }
+
+        println!("Nodes: {} in {:.1}s", result.nodes, result.elapsed.as_secs_f64());
+        return;
     }
 
-    println!("{indent}{color_str} pass:");
+    // If we were given a checkpoint file that already exists, pick up where it
+    // left off.
 
-    tree.turn(color, Pass);
-    let mut best = solve(tree, alpha, beta);
-    tree.undo();
+    let board_string = tree.board().to_string();
+    let mut resume = None;
 
-    let mut invoke_alpha_beta = false;
+    if let Some(filename) = &args.checkpoint {
+        if std::path::Path::new(filename).exists() {
+            let file = match CheckpointFile::load(filename) {
+                Ok(file) => file,
+                Err(err_string) => {
+                    eprintln!("{}", err_string);
+                    return;
+                }
+            };
 
-    for play in 0..tree.board().point_count() {
-        if color == Black && best >= beta  {
-            println!("{indent}Best = {best}, beta = {beta}, breaking now");
-            invoke_alpha_beta = true;
-            break;
+            if file.board != board_string || file.node != args.node {
+                eprintln!("Error: checkpoint '{}' is for a different board or node.", filename);
+                return;
+            }
+
+            if file.window != window || file.max_depth != args.max_depth {
+                eprintln!("Error: checkpoint '{}' is for a different window or depth limit.", filename);
+                return;
+            }
+
+            if let Some(result) = file.result {
+                let exact = if file.depth_limited {" (depth-limited, not exact)"} else {""};
+                println!("\nResult: {}{} (from checkpoint)", result, exact);
+                return;
+            }
+
+            println!("Resuming from checkpoint after {} nodes.", file.state.as_ref().map_or(0, |s| s.nodes()));
+            resume = file.state;
         }
+    }
+
+    // Set up the solver.
 
-        if color == White && best <= alpha {
-            println!("{indent}Best = {best}, alpha = {alpha}, breaking now");
-            invoke_alpha_beta = true;
-            break;
+    let config = SolverConfig {
+        threads:             args.threads,
+        window:              window,
+        max_nodes:           args.max_nodes,
+        max_time:            args.max_time.map(Duration::from_secs_f64),
+        max_depth:           args.max_depth,
+        progress_interval:   args.progress.map(Duration::from_secs_f64),
+        checkpoint_interval: args.checkpoint.as_ref().map(|_| Duration::from_secs_f64(args.checkpoint_every)),
+        trace:               args.trace,
+    };
+
+    let mut solver = Solver::new(config).on_progress(print_progress);
+
+    let template = CheckpointFile {
+        board:         board_string,
+        node:          args.node.clone(),
+        window:        window,
+        max_depth:     args.max_depth,
+        state:         None,
+        result:        None,
+        depth_limited: false,
+    };
+
+    if let Some(filename) = args.checkpoint.clone() {
+        let template = template.clone();
+        solver = solver.on_checkpoint(move |state| {
+            let file = CheckpointFile {state: Some(state.clone()), ..template.clone()};
+            if let Err(err_string) = file.save(&filename) {
+                eprintln!("{}", err_string);
+            }
+        });
+    }
+
+    // Run the search and report the outcome, saving it to the checkpoint file
+    // if there is one.
+
+    let outcome = match resume {
+        Some(state) => match solver.resume(&tree, state) {
+            Ok(outcome) => outcome,
+            Err(err_string) => {
+                eprintln!("Error: {}", err_string);
+                return;
+            }
+        },
+        None => solver.solve(&tree),
+    };
+
+    let mut file = template;
+
+    match outcome {
+        Ok(result) => {
+            file.result = Some(result.value);
+            file.depth_limited = result.stats.depth_limited;
+
+            let exact = if result.stats.depth_limited {" (depth-limited, not exact)"} else {""};
+            println!("\nResult: {}{}", result.value, exact);
+
+            if let Some(best_move) = result.best_move {
+                println!("Best move: {}", describe(best_move));
+            }
+
+            let pv: Vec<String> = result.principal_variation.into_iter().map(describe).collect();
+            println!("Principal variation: {}", pv.join(", "));
+            println!("Nodes: {} in {:.1}s", result.stats.nodes, result.stats.elapsed.as_secs_f64());
         }
+        Err(stopped) => {
+            println!("\nSearch stopped after {} nodes.", stopped.stats.nodes);
+            file.state = stopped.checkpoint;
+        }
+    }
 
-        let result = tree.turn(color, Play(play));
-        if result == Success || result == SuccessGameOver {
-            println!("{indent}{color_str} {play}:");
-            best = match color {
-                Black => max(best, solve(tree, max(alpha, best), beta)),
-                White => min(best, solve(tree, alpha, min(beta, best))),
-                Empty => panic!(),
+    if let Some(filename) = &args.checkpoint {
+        if let Err(err_string) = file.save(filename) {
+            eprintln!("{}", err_string);
+        }
+    } else if file.result.is_none() {
+        println!("No checkpoint file was given, so the search can't be resumed.");
+    }
+}
+
+// Walk from the root of a tree along a comma-separated list of turns. Each turn
+// is played with GameTree::turn(), so the ko history of the branch is checked
+// exactly as it is in the analyzer.
+
+fn walk_to_node(tree: &mut GameTree, path: &str) -> Result<(), String> {
+    tree.reset();
+
+    for (index, token) in path.split(",").map(|t| t.trim()).filter(|t| !t.is_empty()).enumerate() {
+        let turn = match token {
+            "pass" => Pass,
+            _ => match token.parse::<usize>() {
+                Ok(point) if point < tree.board().point_count() => Play(point),
+                _ => return Err(format!(
+                    "Could not parse turn {} ('{}') as a point index or 'pass'.",
+                    index + 1, token
+                )),
+            }
+        };
+
+        let result = tree.turn(tree.whose_turn(), turn);
+        if result != Success && result != SuccessGameOver {
+            return Err(format!("Turn {} ('{}') is not legal here.", index + 1, token));
+        }
+    }
+
+    Ok(())
+}
+
+// Parse a --goal argument into a question.
+
+fn parse_goal(goal: &str, point_count: usize) -> Result<Question, String> {
+    let parts: Vec<&str> = goal.split(":").collect();
+
+    let parse_color = |s: &str| match s {
+        "black" => Ok(Black),
+        "white" => Ok(White),
+        _ => Err(format!("Could not parse '{}' as a color (expected 'black' or 'white').", s)),
+    };
+
+    match parts.as_slice() {
+        ["score", color, margin] => {
+            let margin = margin.parse::<i32>()
+                .map_err(|_| format!("Could not parse '{}' as a score.", margin))?;
+            Ok(Question::score_at_least(parse_color(color)?, margin))
+        }
+        ["stone", point, color] => {
+            let point = match point.parse::<usize>() {
+                Ok(point) if point < point_count => point,
+                _ => return Err(format!("Could not parse '{}' as a point index.", point)),
             };
-            tree.undo();
+            Ok(Question::keeps_stone(point, parse_color(color)?))
         }
+        _ => Err(format!(
+            "Could not parse goal '{}'. Valid goals are:\n  - score:COLOR:K\n  - stone:POINT:COLOR",
+            goal
+        )),
+    }
+}
+
+// Print a progress report: a line of statistics for the periodic ones, or a
+// line for a step of the search when tracing.
+
+fn print_progress(progress: &Progress) {
+    let indent = "|   ".repeat(progress.depth);
+
+    let Some(step) = &progress.trace else {
+        let root_best = progress.root_best.map_or("?".to_string(), |v| v.to_string());
+        println!(
+            "[{:>9.1}s] nodes: {}, nodes/sec: {:.0}, depth: {}, root best: {}",
+            progress.elapsed.as_secs_f64(), progress.nodes,
+            progress.nodes as f64 / progress.elapsed.as_secs_f64(),
+            progress.depth, root_best
+        );
+        return;
+    };
+
+    let color_str = |color| match color {Black => "Black", White => "White", Empty => panic!()};
+
+    match *step {
+        Trace::Score(score)              => println!("{indent}Score: {score}"),
+        Trace::Move(color, turn)         => println!("{indent}{} {}:", color_str(color), describe(turn)),
+        Trace::Cutoff(Black, best, beta) => println!("{indent}Best = {best}, beta = {beta}, breaking now"),
+        Trace::Cutoff(_, best, alpha)    => println!("{indent}Best = {best}, alpha = {alpha}, breaking now"),
+        Trace::CheckedAll                => println!("{indent}Checked all moves."),
+        Trace::Return(best)              => println!("{indent}Return: {best}"),
     }
+}
 
-    if !invoke_alpha_beta {
-        println!("{indent}Checked all moves.");
+fn describe(turn: Turn) -> String {
+    match turn {
+        Pass => "pass".to_string(),
+        Play(point) => point.to_string(),
     }
+}
+
+// The checkpoint file records which board and node the search is for, and the
+// window and depth limit it was run with, since a search can only be resumed
+// (or its result reused) under the same ones. Along with that, it has either
+// the solver's state or the final result and whether it was depth-limited.
 
-    println!("{indent}Return: {best}");
-    best
+#[derive(Clone, Serialize, Deserialize)]
+struct CheckpointFile {
+    board:         String,
+    node:          Option<String>,
+    window:        Option<(i32, i32)>,
+    max_depth:     Option<usize>,
+    state:         Option<Checkpoint>,
+    result:        Option<i32>,
+    depth_limited: bool,
 }
 
+impl CheckpointFile {
+    fn load(filename: &str) -> Result<CheckpointFile, String> {
+        let text = fs::read_to_string(filename)
+            .map_err(|err| format!("Error: could not read checkpoint '{}': {}", filename, err))?;
+        serde_json::from_str(&text)
+            .map_err(|err| format!("Error: could not parse checkpoint '{}': {}", filename, err))
+    }
+
+    // Write to a temporary file first and rename it over the old one, so that
+    // a crash in the middle of saving doesn't destroy the last good checkpoint.
+
+    fn save(&self, filename: &str) -> Result<(), String> {
+        let temp = format!("{}.tmp", filename);
+        fs::write(&temp, serde_json::to_string(self).unwrap())
+            .and_then(|_| fs::rename(&temp, filename))
+            .map_err(|err| format!("Error: could not write checkpoint '{}': {}", filename, err))
+    }
+}
@@ -3,6 +3,7 @@ use stones::boards::Lae;
 use stones::boards::lae_from_spec;
 use stones::layout::Layout;
 use stones::layout::LayoutTrait;
+use stones::layout::{edge_segments, wrap_extent};
 use crate::Face::*;
 use indoc::*;
 use clap::Parser;
@@ -78,9 +79,14 @@ fn svg_from_lae(lae: Lae, rotation: f32, face: Face) -> String {
     
     let mut strokes = "".to_string();
 
+    let spacing = layout.min_point_separation();
+    let extent = wrap_extent(&layout);
+
     for edge in edges {
-        strokes.push_str(&format!("M {} {} ", layout[edge.0].0, layout[edge.0].1));
-        strokes.push_str(&format!("L {} {} ", layout[edge.1].0, layout[edge.1].1));
+        for (a, b) in edge_segments(layout[edge.0], layout[edge.1], spacing, extent) {
+            strokes.push_str(&format!("M {} {} ", a.0, a.1));
+            strokes.push_str(&format!("L {} {} ", b.0, b.1));
+        }
     }
 
     // SVG data.
@@ -0,0 +1,498 @@
+// Structural tests for stones::boards. Each board is checked for its number
+// of points and edges and for the degrees of its points, which is enough to
+// catch a missing or extra edge in a generator.
+
+use std::f64::consts::PI;
+use stones::boards::lae_from_spec;
+use stones::engine::Board;
+use stones::layout::{Camera, Layout, LayoutTrait};
+
+mod common;
+use common::board;
+
+fn degrees(board: &Board) -> Vec<usize> {
+    (0..board.point_count()).map(|p| board.get_neighbors(p).len()).collect()
+}
+
+fn edge_count(board: &Board) -> usize {
+    degrees(board).iter().sum::<usize>() / 2
+}
+
+// Whether every point can be reached from point 0.
+
+fn is_connected(board: &Board) -> bool {
+    let mut seen = vec![false; board.point_count()];
+    let mut stack = vec![0];
+
+    while let Some(point) = stack.pop() {
+        if !seen[point] {
+            seen[point] = true;
+            stack.extend(board.get_neighbors(point).iter().copied());
+        }
+    }
+
+    seen.iter().all(|&s| s)
+}
+
+type Segment = ((f32, f32), (f32, f32));
+
+// Whether two segments cross at a point inside both of them.
+
+fn crosses(s: Segment, t: Segment) -> bool {
+    let side = |p: (f32, f32), q: (f32, f32), r: (f32, f32)| {
+        (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+    };
+
+    side(s.0, s.1, t.0) * side(s.0, s.1, t.1) < -1e-6 &&
+    side(t.0, t.1, s.0) * side(t.0, t.1, s.1) < -1e-6
+}
+
+// The number of sides of each face of a board drawn in the plane, leaving
+// out the face around the outside. A face is traced by following edges and
+// turning as far left as possible at each point, and the outside is the one
+// that goes round clockwise.
+
+fn bounded_face_sizes(layout: &Layout, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut around = vec![vec![]; layout.len()];
+
+    for &(a, b) in edges {
+        around[a].push(b);
+        around[b].push(a);
+    }
+
+    for (point, neighbors) in around.iter_mut().enumerate() {
+        let angle = |&n: &usize| (layout[n].1 - layout[point].1).atan2(layout[n].0 - layout[point].0);
+        neighbors.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+        neighbors.dedup();
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    let mut sizes = vec![];
+
+    for &(a, b) in edges {
+        for (mut from, mut to) in [(a, b), (b, a)] {
+            let mut area = 0.0;
+            let mut size = 0;
+
+            while seen.insert((from, to)) {
+                area += layout[from].0 * layout[to].1 - layout[to].0 * layout[from].1;
+                size += 1;
+
+                let neighbors = &around[to];
+                let back = neighbors.iter().position(|&n| n == from).unwrap();
+                (from, to) = (to, neighbors[(back + neighbors.len() - 1) % neighbors.len()]);
+            }
+
+            if area > 0.0 {
+                sizes.push(size);
+            }
+        }
+    }
+
+    sizes
+}
+
+// Check a board's point count, edge count, and the number of points of each
+// degree, given as (degree, count) pairs.
+
+fn check(spec: &str, points: usize, edges: usize, degree_counts: &[(usize, usize)]) {
+    let board = board(spec);
+    let degrees = degrees(&board);
+
+    assert_eq!(board.point_count(), points, "Wrong number of points on {}.", spec);
+    assert_eq!(edge_count(&board), edges, "Wrong number of edges on {}.", spec);
+
+    for &(degree, count) in degree_counts {
+        assert_eq!(degrees.iter().filter(|&&d| d == degree).count(), count,
+                   "Wrong number of points of degree {} on {}.", degree, spec);
+    }
+}
+
+#[test]
+fn wrapped_grids() {
+    check("cylinder:5:3", 15, 25, &[(3, 10), (4, 5)]);
+    check("mobius:5:3",   15, 25, &[(3, 10), (4, 5)]);
+    check("torus:4:4",    16, 32, &[(4, 16)]);
+    check("klein:4:4",    16, 32, &[(4, 16)]);
+
+    // On a Mobius strip the right end of the top row joins the left end of
+    // the bottom row; on a cylinder it joins the left end of the same row.
+
+    assert!(board("mobius:5:3").get_neighbors(4).contains(&10));
+    assert!(board("cylinder:5:3").get_neighbors(4).contains(&0));
+    assert!(board("klein:4:4").get_neighbors(3).contains(&12));
+    assert!(board("klein:4:4").get_neighbors(12).contains(&0));
+}
+
+// A wrap of width or height 2 would join a point to one it's already joined
+// to, and a wrap of 1 would join a point to itself. Neither adds an edge.
+
+#[test]
+fn narrow_wrapped_grids() {
+    check("cylinder:2:3", 6, 7, &[(2, 4), (3, 2)]);
+    check("torus:2:2",    4, 4, &[(2, 4)]);
+    check("cylinder:1:3", 3, 2, &[(1, 2), (2, 1)]);
+}
+
+// Every vertex of a Platonic or Archimedean solid looks the same, so each
+// board has a single degree.
+
+#[test]
+fn polyhedra() {
+    let cases = [
+        ("tetrahedron",                 4,   6,   3),
+        ("hexahedron",                  8,   12,  3),
+        ("octahedron",                  6,   12,  4),
+        ("dodecahedron",                20,  30,  3),
+        ("icosahedron",                 12,  30,  5),
+        ("truncated-tetrahedron",       12,  18,  3),
+        ("cuboctahedron",               12,  24,  4),
+        ("truncated-cube",              24,  36,  3),
+        ("truncated-octahedron",        24,  36,  3),
+        ("rhombicuboctahedron",         24,  48,  4),
+        ("truncated-cuboctahedron",     48,  72,  3),
+        ("snub-cube",                   24,  60,  5),
+        ("icosidodecahedron",           30,  60,  4),
+        ("truncated-dodecahedron",      60,  90,  3),
+        ("truncated-icosahedron",       60,  90,  3),
+        ("rhombicosidodecahedron",      60,  120, 4),
+        ("truncated-icosidodecahedron", 120, 180, 3),
+        ("snub-dodecahedron",           60,  150, 5),
+    ];
+
+    for (spec, points, edges, degree) in cases {
+        check(spec, points, edges, &[(degree, points)]);
+    }
+}
+
+// A geodesic sphere of side N has 20 N^2 faces, so 30 N^2 edges and (by
+// Euler's formula) 10 N^2 + 2 points. The icosahedron's 12 corners have
+// degree 5 and the rest degree 6.
+
+#[test]
+fn geodesic_spheres() {
+    for n in 1..=4 {
+        let points = 10 * n * n + 2;
+        check(&format!("geodesic:{}", n), points, 30 * n * n, &[(5, 12), (6, points - 12)]);
+    }
+}
+
+// The layouts of the solids are Schlegel diagrams, which have no crossing
+// edges.
+
+#[test]
+fn polyhedra_are_drawn_without_crossings() {
+    for spec in ["hexahedron", "dodecahedron", "icosahedron", "cuboctahedron", "truncated-icosahedron",
+                 "snub-cube", "geodesic:2"] {
+        let (layout, edges) = lae_from_spec(spec).unwrap();
+
+        for (i, &(a, b)) in edges.iter().enumerate() {
+            for &(c, d) in &edges[i + 1..] {
+                if a == c || a == d || b == c || b == d {
+                    continue;
+                }
+
+                assert!(!crosses((layout[a], layout[b]), (layout[c], layout[d])),
+                        "Edges {}-{} and {}-{} cross on {}.", a, b, c, d, spec);
+            }
+        }
+    }
+}
+
+// Random boards should be connected whatever the seed, with the number of
+// points asked for, and with the closest two points 1 apart.
+
+#[test]
+fn random_boards_are_connected() {
+    for seed in 1..=10 {
+        for n in [2, 10, 40] {
+            for spec in [format!("voronoi:{}:{}", n, seed), format!("voronoi:{}:{}:50", n, seed),
+                         format!("planar:{}:{}", n, seed), format!("randomgrid:{}:3:40:{}", n, seed)] {
+                let (layout, _) = lae_from_spec(&spec).unwrap();
+                let board = board(&spec);
+                let points = if spec.starts_with("randomgrid") {3 * n} else {n};
+
+                assert_eq!(board.point_count(), points, "Wrong number of points on {}.", spec);
+                assert!(is_connected(&board), "{} is in pieces.", spec);
+                assert!((layout.min_point_separation() - 1.0).abs() < 0.001, "Points too close on {}.", spec);
+            }
+        }
+    }
+}
+
+// Removing every edge that can go leaves a spanning tree, and removing none
+// leaves the whole grid or triangulation.
+
+#[test]
+fn random_boards_remove_the_edges_asked_for() {
+    for seed in 1..=5 {
+        check(&format!("randomgrid:6:4:0:{}", seed), 24, 38, &[]);
+        check(&format!("randomgrid:6:4:100:{}", seed), 24, 23, &[]);
+        check(&format!("voronoi:30:{}:100", seed), 30, 29, &[]);
+
+        // A triangulation of N points has at most 3N - 6 edges, and at least
+        // 2N - 3 when the points are in general position.
+
+        let edges = edge_count(&board(&format!("voronoi:30:{}", seed)));
+        assert!((57..=84).contains(&edges), "Voronoi board {} has {} edges.", seed, edges);
+
+        // A planar board is thinned down to twice as many edges as points,
+        // and drawn without crossings.
+
+        let spec = format!("planar:30:{}", seed);
+        let (layout, edges) = lae_from_spec(&spec).unwrap();
+        assert!(edge_count(&board(&spec)) <= 60);
+
+        for (i, &(a, b)) in edges.iter().enumerate() {
+            for &(c, d) in &edges[i + 1..] {
+                if a != c && a != d && b != c && b != d {
+                    assert!(!crosses((layout[a], layout[b]), (layout[c], layout[d])), "Edges cross on {}.", spec);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn random_boards_depend_only_on_the_seed() {
+    for spec in ["voronoi:30", "planar:30", "randomgrid:6:6:30"] {
+        let first = lae_from_spec(&format!("{}:1", spec)).unwrap();
+        let again = lae_from_spec(&format!("{}:1", spec)).unwrap();
+        let other = lae_from_spec(&format!("{}:2", spec)).unwrap();
+
+        assert_eq!(first, again, "{} changed between runs.", spec);
+        assert_ne!(first.1, other.1, "{} ignores its seed.", spec);
+    }
+}
+
+// The random numbers come from a generator of our own, so these boards stay
+// the same across versions of the crate's dependencies. Saved games on them
+// depend on it.
+
+#[test]
+fn random_boards_are_pinned() {
+    let pinned: [(&str, &[(usize, usize)]); 3] = [
+        ("voronoi:6:1", &[(1, 3), (1, 5), (1, 2), (0, 3), (0, 4), (2, 5), (3, 5), (0, 5), (4, 5), (2, 4)]),
+        ("planar:6:1", &[(0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (1, 5), (2, 4), (3, 4), (3, 5), (4, 5)]),
+        ("randomgrid:3:2:30:1", &[(2, 5), (1, 4), (0, 1), (0, 3), (1, 2)]),
+    ];
+    for (spec, edges) in pinned {
+        assert_eq!(lae_from_spec(spec).unwrap().1, edges, "{} changed.", spec);
+    }
+}
+
+// A Penrose board is a sun of rhombs, so it keeps the sun's five-fold
+// symmetry, and no point of a rhomb tiling has more than seven neighbors.
+
+#[test]
+fn penrose_boards() {
+    let (cos, sin) = (72f32.to_radians().cos(), 72f32.to_radians().sin());
+    check("penrose:0", 11, 15, &[(2, 5), (3, 5), (5, 1)]);
+
+    for n in 0..=4 {
+        let spec = format!("penrose:{}", n);
+        let (layout, edges) = lae_from_spec(&spec).unwrap();
+        let board = board(&spec);
+
+        assert!(is_connected(&board), "{} is in pieces.", spec);
+        assert!(degrees(&board).iter().all(|d| (2..=7).contains(d)), "Wrong degrees on {}.", spec);
+
+        assert!(bounded_face_sizes(&layout, &edges).iter().all(|&size| size == 4),
+                "{} has a face that isn't a rhomb.", spec);
+
+        for (a, b) in edges {
+            assert!((f32::hypot(layout[a].0 - layout[b].0, layout[a].1 - layout[b].1) - 1.0).abs() < 0.1);
+        }
+
+        for &(x, y) in layout.iter() {
+            let turned = (x * cos - y * sin, x * sin + y * cos);
+            assert!(layout.iter().any(|p| f32::hypot(p.0 - turned.0, p.1 - turned.1) < 0.01),
+                    "{} isn't five-fold symmetric.", spec);
+        }
+    }
+}
+
+// The hats on a hat board fit together without gaps, so with the hats as its
+// faces the board satisfies Euler's formula V - E + F = 1 for a patch of the
+// plane. Each hat has 14 points around it, counting the one in the middle of
+// its longest side.
+
+#[test]
+fn hat_boards() {
+    check("hat:1", 14, 14, &[(2, 14)]);
+
+    for n in [2, 5, 10, 20, 50] {
+        let spec = format!("hat:{}", n);
+        let board = board(&spec);
+
+        assert!(is_connected(&board), "{} is in pieces.", spec);
+        assert_eq!(board.point_count() + n, edge_count(&board) + 1, "{} has gaps between its hats.", spec);
+        assert!(degrees(&board).iter().all(|d| (2..=4).contains(d)), "Wrong degrees on {}.", spec);
+    }
+}
+
+// The corners of the middle tile of a {P,Q} tiling, as a fraction of the way
+// to the rim of the Poincaré disk.
+
+fn hyperbolic_radius(p: usize, q: usize) -> f64 {
+    let cosh_r = 1.0 / ((PI / p as f64).tan() * (PI / q as f64).tan());
+    (cosh_r.acosh() / 2.0).tanh()
+}
+
+// Q tiles meet at every point that the last layer of tiles surrounds (the
+// points of the board one layer smaller, which are numbered first), and every
+// edge has the same length in the hyperbolic plane, however short it's drawn.
+
+#[test]
+fn hyperbolic_boards() {
+    for (p, q) in [(7, 3), (4, 5), (5, 4), (3, 7), (6, 4)] {
+        check(&format!("hyperbolic:{}:{}:0", p, q), p, p, &[(2, p)]);
+
+        let spec = format!("hyperbolic:{}:{}:2", p, q);
+        let (layout, edges) = lae_from_spec(&spec).unwrap();
+        let degrees = degrees(&board(&spec));
+        let surrounded = board(&format!("hyperbolic:{}:{}:1", p, q)).point_count();
+
+        assert!(degrees.iter().all(|&d| d <= q), "A point of {} has too many neighbors.", spec);
+        assert!(degrees[..surrounded].iter().all(|&d| d == q), "A point of {} isn't surrounded.", spec);
+
+        // Scale the layout back into the unit disk.
+
+        let scale = hyperbolic_radius(p, q) / f64::hypot(layout[0].0 as f64, layout[0].1 as f64);
+        let disk: Vec<(f64, f64)> = layout.iter().map(|&(x, y)| (x as f64 * scale, y as f64 * scale)).collect();
+        let distance = |a: (f64, f64), b: (f64, f64)| {
+            let norm2 = |z: (f64, f64)| z.0 * z.0 + z.1 * z.1;
+            let apart = norm2((a.0 - b.0, a.1 - b.1));
+            (1.0 + 2.0 * apart / ((1.0 - norm2(a)) * (1.0 - norm2(b)))).acosh()
+        };
+
+        assert!(disk.iter().all(|z| f64::hypot(z.0, z.1) < 1.0), "A point of {} is outside the disk.", spec);
+
+        let side = distance(disk[0], disk[1]);
+        for (a, b) in edges {
+            assert!((distance(disk[a], disk[b]) - side).abs() < 1e-3, "Edge {}-{} of {} is the wrong length.", a, b, spec);
+        }
+    }
+}
+
+// Deeper boards reach close to the rim, where corners that are far apart in
+// the hyperbolic plane are very close in the disk, and rounding errors would
+// show up as corners with more than Q neighbors. Deeper still, the corners
+// can't be drawn apart at all.
+
+#[test]
+fn deep_hyperbolic_boards() {
+    for (p, q, depth) in [(5, 5, 3), (7, 3, 5), (4, 5, 4), (6, 6, 2), (3, 7, 5)] {
+        let spec = format!("hyperbolic:{}:{}:{}", p, q, depth);
+        let degrees = degrees(&board(&spec));
+        let surrounded = board(&format!("hyperbolic:{}:{}:{}", p, q, depth - 1)).point_count();
+
+        assert!(degrees.iter().all(|&d| d <= q), "A point of {} has too many neighbors.", spec);
+        assert!(degrees[..surrounded].iter().all(|&d| d == q), "A point of {} isn't surrounded.", spec);
+    }
+
+    assert!(lae_from_spec("hyperbolic:30:30:1").unwrap_err().contains("too close to the rim"));
+}
+
+// Tilings of the sphere or the flat plane don't make hyperbolic boards.
+
+#[test]
+fn euclidean_tilings_make_no_hyperbolic_board() {
+    for spec in ["hyperbolic:4:4:2", "hyperbolic:6:3:2", "hyperbolic:3:6:2", "hyperbolic:3:5:2", "hyperbolic:4:4:3"] {
+        let err = lae_from_spec(spec).unwrap_err();
+        assert!(err.contains("isn't hyperbolic") && err.contains("(P - 2)(Q - 2) must be more than 4"), "Wrong error on {}: {}", spec, err);
+    }
+
+    assert!(lae_from_spec("hyperbolic:2:9:2").unwrap_err().contains("at least 3 sides"));
+    assert!(lae_from_spec("hyperbolic:3:6:2").unwrap_err().contains("6 3-gons to a corner"));
+}
+
+// A stack of D layers of a board with V points and E edges has DV points,
+// DE edges within the layers, and (D - 1)V edges between them, so a W by H by
+// D cube has 3WHD - WH - HD - WD edges. Each point sits right above the same
+// point of the layer below.
+
+#[test]
+fn stacked_boards() {
+    for (w, h, d) in [(3, 3, 3), (4, 2, 3), (2, 2, 2), (5, 1, 1)] {
+        let points = w * h * d;
+        check(&format!("cube:{}:{}:{}", w, h, d), points, 3 * points - w * h - h * d - w * d, &[]);
+    }
+
+    check("cube:3:3:3", 27, 54, &[(3, 8), (4, 12), (5, 6), (6, 1)]);
+
+    for (n, d) in [(1, 3), (2, 2), (3, 4)] {
+        let layer = board(&format!("hex:{}", n));
+        let (v, e) = (layer.point_count(), edge_count(&layer));
+        let spec = format!("hexstack:{}:{}", n, d);
+        let stack = board(&spec);
+
+        check(&spec, d * v, d * e + (d - 1) * v, &[]);
+
+        for p in 0..(d - 1) * v {
+            assert!(stack.get_neighbors(p).contains(&(p + v)), "Point {} of {} isn't joined upwards.", p, spec);
+        }
+    }
+}
+
+#[test]
+fn stacked_boards_have_layers() {
+    let (layout, _) = lae_from_spec("cube:2:3:4").unwrap();
+
+    assert!(layout.is_3d());
+    assert_eq!(layout.space.len(), 24);
+    assert_eq!(layout.layers(), vec![0.0, 1.0, 2.0, 3.0]);
+    assert!((0..24).all(|p| layout.space[p].2 == (p / 6) as f32));
+
+    // Each layer is drawn higher up (with smaller y) than the one below.
+
+    assert!((0..18).all(|p| layout[p + 6].1 < layout[p].1));
+
+    let (flat, _) = lae_from_spec("square:3").unwrap();
+    assert!(!flat.is_3d() && flat.layers().is_empty());
+
+    // A 3D layout is saved with its points in space, and read back the same.
+
+    let json = serde_json::to_string(&layout).unwrap();
+    assert!(json.starts_with("{\"points\":[[") && json.contains("\"space\":[[") && json.ends_with(",3.0]]}"));
+    assert_eq!(serde_json::from_str::<Layout>(&json).unwrap(), layout);
+}
+
+#[test]
+fn stacked_boards_can_be_seen_from_anywhere() {
+    let (layout, edges) = lae_from_spec("cube:2:3:4").unwrap();
+    let (turned, turned_edges) = lae_from_spec("cube:2:3:4:30:60").unwrap();
+
+    // Seen from another camera, the board is the same board.
+
+    assert_eq!(turned_edges, edges);
+    assert_eq!(turned.space, layout.space);
+    assert!(!(0..24).all(|p| layout[p] == turned[p]));
+
+    // Either layout gives the other from its points in space.
+
+    assert_eq!(turned.view(Camera::default()), layout);
+    assert_eq!(layout.view(Camera {yaw: 30.0 / 360.0, pitch: 60.0 / 360.0}), turned);
+    assert_eq!(lae_from_spec("hexstack:2:3:30:60").unwrap().0.view(Camera::default()), lae_from_spec("hexstack:2:3").unwrap().0);
+
+    // Looking straight down, the layers are drawn on top of each other.
+
+    let above = layout.view(Camera {yaw: 0.0, pitch: 0.25});
+    assert!((0..18).all(|p| (above[p].0 - above[p + 6].0).abs() < 1e-5 && (above[p].1 - above[p + 6].1).abs() < 1e-5));
+}
+
+// So a spec can't ask for a camera like that, or one looking along the rows of
+// a cube, and the default camera keeps the points of a cube well apart.
+
+#[test]
+fn cameras_keep_points_apart() {
+    for spec in ["cube:2:3:4:0:90", "cube:4:4:4:45:90", "cube:4:4:4:0:0", "cube:4:4:4:90:0", "hexstack:2:3:0:90"] {
+        let err = lae_from_spec(spec).unwrap_err();
+        assert!(err.contains("on top of each other"), "Wrong error on {}: {}", spec, err);
+    }
+
+    for n in 2..=9 {
+        let (layout, _) = lae_from_spec(&format!("cube:{}:{}:{}", n, n, n)).unwrap();
+        assert!(layout.min_point_separation() > 0.3, "The points of cube:{}:{}:{} are too close.", n, n, n);
+    }
+}
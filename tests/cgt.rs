@@ -0,0 +1,224 @@
+// Tests for stones::cgt. The arithmetic is checked against the values in the
+// usual tables of short games, and the predictions against the exact values
+// of small boards from stones::solver.
+
+use stones::boards::lae_from_spec;
+use stones::cgt::Game;
+use stones::engine::{Board, Color};
+use stones::engine::Color::*;
+use stones::gametree::GameTree;
+use stones::gametree::Turn::*;
+use stones::solver::{Solver, SolverConfig};
+
+const SMALL_BOARDS: &[&str] = &["square:2", "loop:4", "grid:3:1", "grid:4:1", "loop:3"];
+
+fn int(n: i64) -> Game {
+    Game::integer(n)
+}
+
+fn game(left: &[Game], right: &[Game]) -> Game {
+    Game::new(left.to_vec(), right.to_vec())
+}
+
+fn star() -> Game {
+    game(&[int(0)], &[int(0)])
+}
+
+fn half() -> Game {
+    game(&[int(0)], &[int(1)])
+}
+
+// Canonical forms don't keep their options in any particular order, so games
+// are compared as values rather than as forms.
+
+fn equal(a: &Game, b: &Game) -> bool {
+    a.le(b) && b.le(a)
+}
+
+#[test]
+fn simple_games_are_numbers() {
+    let cases = [
+        (game(&[], &[]),                  "0"),
+        (game(&[int(0)], &[]),            "1"),
+        (game(&[int(1)], &[]),            "2"),
+        (game(&[], &[int(0)]),            "-1"),
+        (game(&[int(0)], &[int(1)]),      "1/2"),
+        (game(&[int(0)], &[half()]),      "1/4"),
+        (game(&[int(-1)], &[int(0)]),     "-1/2"),
+        (game(&[int(-1)], &[int(1)]),     "0"),
+        (game(&[int(1)], &[int(4)]),      "2"),
+        (game(&[int(-3)], &[int(-1)]),    "-2"),
+        (game(&[half()], &[int(1)]),      "3/4"),
+    ];
+
+    for (value, text) in cases {
+        assert_eq!(value.to_string(), text);
+    }
+}
+
+#[test]
+fn infinitesimals_and_switches_keep_their_form() {
+    let up = game(&[int(0)], &[star()]);
+
+    assert_eq!(star().to_string(), "*");
+    assert_eq!(game(&[int(1)], &[int(1)]).to_string(), "1*");
+    assert_eq!(up.to_string(), "^");
+    assert_eq!(game(&[star()], &[int(0)]).to_string(), "v");
+    assert_eq!(game(&[int(3)], &[int(-1)]).to_string(), "{3 | -1}");
+
+    // A dominated option is dropped, but 0 and * can't be compared, so both
+    // stay in ^* = {0, * | 0}.
+
+    let up_star = game(&[int(0), star()], &[int(0)]);
+    assert_eq!(game(&[int(0), int(1)], &[int(-1)]).to_string(), "{1 | -1}");
+    assert!(matches!(&up_star, Game::Compound {left, ..} if left.len() == 2));
+}
+
+#[test]
+fn sums_match_known_values() {
+    let up = game(&[int(0)], &[star()]);
+    let down = game(&[star()], &[int(0)]);
+
+    assert_eq!(half().add(&half()), int(1));
+    assert_eq!(half().add(&int(-1)).to_string(), "-1/2");
+    assert_eq!(star().add(&star()), int(0));
+    assert_eq!(up.add(&down), int(0));
+    assert!(equal(&up.add(&star()), &game(&[int(0), star()], &[int(0)])));
+    assert_eq!(int(2).add(&star()).to_string(), "2*");
+    assert_eq!(game(&[int(3)], &[int(-1)]).add(&int(1)).to_string(), "{4 | 0}");
+}
+
+#[test]
+fn comparisons_match_known_values() {
+    let up = game(&[int(0)], &[star()]);
+
+    assert!(int(0).le(&up) && !up.le(&int(0)));
+    assert!(!int(0).le(&star()) && !star().le(&int(0)));
+    assert!(up.le(&half()) && !half().le(&up));
+    assert!(int(-1).le(&star()) && star().le(&int(1)));
+}
+
+#[test]
+fn stops_of_a_switch() {
+    let switch = game(&[int(3)], &[int(-1)]);
+
+    assert_eq!(switch.left_stop().to_string(), "3");
+    assert_eq!(switch.right_stop().to_string(), "-1");
+    assert_eq!(half().left_stop().to_string(), "1/2");
+}
+
+#[test]
+fn every_small_board_gets_a_value() {
+    for spec in SMALL_BOARDS {
+        let tree = GameTree::new(Board::new(lae_from_spec(spec).unwrap().1));
+        let report = tree.analyze_regions();
+        assert!(report.regions.iter().all(|region| region.value.is_some()), "No value on {}.", spec);
+    }
+}
+
+// The regions of an empty small board all have cycles, so they give no
+// prediction, but playing them out should still get the right result.
+
+#[test]
+fn played_out_results_match_the_solver() {
+    for spec in SMALL_BOARDS {
+        let tree = GameTree::new(Board::new(lae_from_spec(spec).unwrap().1));
+        let value = match Solver::new(SolverConfig::default()).solve(&tree) {
+            Ok(result) => result.value,
+            Err(_) => panic!("The search on {} stopped without any limits set.", spec),
+        };
+
+        let report = tree.analyze_regions();
+        assert_eq!(report.played_out(Black), Some(value), "Wrong result on {}.", spec);
+        assert!(report.predicted(Black).is_none_or(|score| score == value), "Wrong prediction on {}.", spec);
+    }
+}
+
+// Play Black's stones with White passing, then pass for White once more if
+// it should be Black's turn.
+
+fn setup(spec: &str, stones: &[usize], to_play: Color) -> GameTree {
+    let mut tree = GameTree::new(Board::new(lae_from_spec(spec).unwrap().1));
+
+    for (i, &point) in stones.iter().enumerate() {
+        if i > 0 {
+            tree.turn(White, Pass);
+        }
+        tree.turn(Black, Play(point));
+    }
+
+    if to_play == Black {
+        tree.turn(White, Pass);
+    }
+
+    tree
+}
+
+#[test]
+fn predicted_results_match_the_solver() {
+    let cases = [
+        ("loop:4",   &[0, 2][..]),
+        ("square:2", &[0, 3][..]),
+        ("grid:4:1", &[1, 2][..]),
+    ];
+
+    for (spec, stones) in cases {
+        for to_play in [Black, White] {
+            let tree = setup(spec, stones, to_play);
+            let value = match Solver::new(SolverConfig::default()).solve(&tree) {
+                Ok(result) => result.value,
+                Err(_) => panic!("The search on {} stopped without any limits set.", spec),
+            };
+
+            let report = tree.analyze_regions();
+            assert!(report.regions.len() >= 2, "Only one region on {}.", spec);
+            assert_eq!(report.predicted(to_play), Some(value), "Wrong prediction on {}.", spec);
+        }
+    }
+}
+
+// Two stones that can't be captured, Black's on 0 with eyes at 1 and 2, and
+// White's on 3 with eyes at 4 and 5. Between them are three regions: a
+// single point at 6 touching both, and two paths of two points where one end
+// touches both stones and the other touches only one (7 next to Black, 9
+// next to White), so neither path has a ko. The whole board is too big for
+// the solver, so the predictions are checked against playing the regions out
+// instead.
+
+#[test]
+fn predicted_results_add_up_regions() {
+    let edges = vec![(0, 1), (0, 2), (3, 4), (3, 5), (0, 6), (6, 3),
+                     (0, 7), (7, 8), (8, 0), (8, 3), (3, 9), (9, 10), (10, 0), (10, 3)];
+    let cases = [
+        (&[][..],                       Black, "*",                         1),
+        (&[][..],                       White, "*",                        -1),
+        (&[(White, 7)][..],             Black, "{{1 | *} | {-1 | -2*}}",    1),
+        (&[(Black, 8)][..],             Black, "{1 | *}",                   3),
+        (&[(Black, 8)][..],             White, "{1 | *}",                   1),
+        (&[(White, 10)][..],            White, "{* | -1}",                 -3),
+        (&[(Black, 6), (White, 8)][..], Black, "{0 | -1*}",                 1),
+    ];
+
+    for (stones, to_play, sum, score) in cases {
+        let mut tree = GameTree::new(Board::new(edges.clone()));
+        tree.turn(Black, Play(0));
+        tree.turn(White, Play(3));
+
+        for &(color, point) in stones {
+            if tree.whose_turn() != color {
+                tree.turn(tree.whose_turn(), Pass);
+            }
+            tree.turn(color, Play(point));
+        }
+
+        if tree.whose_turn() != to_play {
+            tree.turn(tree.whose_turn(), Pass);
+        }
+
+        let report = tree.analyze_regions();
+        assert!(report.regions.iter().all(|region| region.value.is_some()));
+        assert_eq!(report.sum().map(|sum| sum.to_string()), Some(sum.to_string()));
+        assert_eq!(report.predicted(to_play), Some(score), "Wrong prediction for {:?}.", stones);
+        assert_eq!(report.played_out(to_play), Some(score), "Wrong play out for {:?}.", stones);
+    }
+}
@@ -0,0 +1,15 @@
+// Fixtures shared by the integration tests. Each test file only uses some of
+// them.
+
+#![allow(dead_code)]
+
+use stones::boards::{lae_from_spec, Lae};
+use stones::engine::Board;
+
+pub fn board(spec: &str) -> Board {
+    Board::new(lae_from_spec(spec).unwrap().1)
+}
+
+pub fn lae(spec: &str) -> Lae {
+    lae_from_spec(spec).unwrap()
+}
@@ -0,0 +1,60 @@
+// Tests for stones::count. The dynamic programming is checked against the
+// brute-force count on boards small enough to go through every coloring, and
+// both against the published counts for small square boards.
+
+use stones::count::{count_legal_positions, count_legal_positions_brute_force, BigCount};
+
+mod common;
+use common::board;
+
+const SMALL_BOARDS: &[&str] = &[
+    "square:2",
+    "square:3",
+    "grid:4:1",
+    "grid:4:3",
+    "loop:7",
+    "torus:3:3",
+    "tetrahedron",
+    "hexahedron",
+    "octahedron",
+];
+
+#[test]
+fn counts_agree_with_brute_force() {
+    for spec in SMALL_BOARDS {
+        let board = board(spec);
+        let count = count_legal_positions(&board).unwrap().count;
+        let brute_force = count_legal_positions_brute_force(&board);
+
+        assert_eq!(count, BigCount::from_u64(brute_force), "Counts disagree on {}.", spec);
+    }
+}
+
+// The counts for small square boards from Tromp and Farnebäck's tables.
+
+#[test]
+fn small_square_boards_have_their_known_counts() {
+    for (spec, known) in [("square:2", 57), ("square:3", 12675)] {
+        let board = board(spec);
+        assert_eq!(count_legal_positions(&board).unwrap().count.to_string(), known.to_string());
+        assert_eq!(count_legal_positions_brute_force(&board), known);
+    }
+}
+
+// The 5x5 count is too big for one 32-bit digit of a BigCount, and too big
+// to check by brute force.
+
+#[test]
+fn five_by_five_has_its_known_count() {
+    let count = count_legal_positions(&board("square:5")).unwrap().count;
+    assert_eq!(count.to_string(), "414295148741");
+}
+
+// Frontier codes only have room for so many chain labels, so a board that
+// needs a wider frontier is turned down before any counting starts.
+
+#[test]
+fn wide_boards_are_an_error() {
+    let result = count_legal_positions(&board("square:64"));
+    assert_eq!(result.err().as_deref(), Some("The frontier is 65 points wide, and counting only works up to 61."));
+}
@@ -0,0 +1,195 @@
+// Property-based tests for the engine. Each test case is a random board (a
+// random connected graph) and a random sequence of moves, and the invariants
+// are checked after every Board::play:
+//
+//     - every chain of stones has at least one liberty,
+//     - Board::chain() agrees with the colors on the board (every point's
+//       chain is exactly the connected group of same-colored points around
+//       it, and all the points in a chain report the same chain),
+//     - the stones removed by a move are exactly the enemy chains left without
+//       liberties (or, if there are none, the mover's own chain if it has no
+//       liberties), which is checked against a simple flood-fill model,
+//     - keep_only_immortal only removes stones and is idempotent,
+//     - the area score is the stone score plus every bubble of empty points
+//       whose neighbors are all one color, counted for that color.
+
+use proptest::prelude::*;
+use stones::engine::{Board, Color, Position};
+use stones::engine::Color::*;
+
+// A random connected graph on 2 to 24 points: a random spanning tree (so that
+// every point has an edge) plus some extra edges.
+
+fn arb_edges() -> impl Strategy<Value = Vec<(usize, usize)>> {
+    (2..=24usize).prop_flat_map(|n| {
+        let tree = prop::collection::vec(any::<prop::sample::Index>(), n - 1);
+        let extra = prop::collection::vec((0..n, 0..n), 0..2 * n);
+        (tree, extra).prop_map(move |(tree, extra)| {
+            let mut edges: Vec<(usize, usize)> =
+                tree.iter().enumerate().map(|(i, parent)| (parent.index(i + 1), i + 1)).collect();
+            edges.extend(extra.into_iter().filter(|(a, b)| a != b));
+            edges
+        })
+    })
+}
+
+// Moves are a color and a point, taken modulo the board's point count. Moves
+// on occupied points are skipped.
+
+fn arb_moves() -> impl Strategy<Value = Vec<(bool, usize)>> {
+    prop::collection::vec((any::<bool>(), any::<usize>()), 0..80)
+}
+
+fn colors(board: &Board, pos: &Position) -> Vec<Color> {
+    (0..board.point_count()).map(|p| pos[p]).collect()
+}
+
+// The connected group of same-colored points containing a given point, found
+// by flood fill on a plain list of colors.
+
+fn flood(board: &Board, colors: &[Color], point: usize) -> Vec<usize> {
+    let mut group = vec![point];
+    let mut i = 0;
+
+    while i < group.len() {
+        for n in board.get_neighbors(group[i]) {
+            if colors[n] == colors[point] && !group.contains(&n) {
+                group.push(n);
+            }
+        }
+        i += 1;
+    }
+
+    group.sort();
+    group
+}
+
+fn has_liberty(board: &Board, colors: &[Color], group: &[usize]) -> bool {
+    group.iter().any(|&p| board.get_neighbors(p).iter().any(|&n| colors[n] == Empty))
+}
+
+// What a move should do: place the stone, remove the enemy chains with no
+// liberties, and then remove the mover's own chain if it has none.
+
+fn expected_after_play(board: &Board, before: &[Color], color: Color, point: usize) -> Vec<Color> {
+    let mut after = before.to_vec();
+    after[point] = color;
+
+    for side in [color.reverse(), color] {
+        let dead: Vec<usize> =
+            (0..after.len())
+                .filter(|&p| after[p] == side)
+                .filter(|&p| !has_liberty(board, &after, &flood(board, &after, p)))
+                .collect();
+
+        for p in dead {
+            after[p] = Empty;
+        }
+    }
+
+    after
+}
+
+fn check_chains(board: &Board, pos: &Position) -> Result<(), TestCaseError> {
+    let colors = colors(board, pos);
+
+    for p in 0..board.point_count() {
+        let mut chain = board.chain(pos, p).to_vec();
+        chain.sort();
+        prop_assert_eq!(&chain, &flood(board, &colors, p), "chain of point {} is wrong", p);
+
+        for &q in &chain {
+            let mut other = board.chain(pos, q).to_vec();
+            other.sort();
+            prop_assert_eq!(&other, &chain, "points {} and {} disagree about their chain", p, q);
+        }
+
+        if colors[p] != Empty {
+            prop_assert!(board.liberty_count(pos, p) > 0, "chain at {} has no liberties", p);
+        }
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn play_keeps_invariants(edges in arb_edges(), moves in arb_moves()) {
+        let board = Board::new(edges);
+        let mut pos = board.empty_position();
+        check_chains(&board, &pos)?;
+
+        for (black, point) in moves {
+            let point = point % board.point_count();
+            let color = if black {Black} else {White};
+            if pos[point] != Empty {
+                continue;
+            }
+
+            let expected = expected_after_play(&board, &colors(&board, &pos), color, point);
+            board.play(&mut pos, color, point);
+
+            prop_assert_eq!(colors(&board, &pos), expected, "wrong captures after {:?} at {}", color, point);
+            check_chains(&board, &pos)?;
+        }
+    }
+
+    #[test]
+    fn keep_only_immortal_only_removes_stones(edges in arb_edges(), moves in arb_moves()) {
+        let board = Board::new(edges);
+        let mut pos = board.empty_position();
+
+        for (black, point) in moves {
+            let point = point % board.point_count();
+            if pos[point] == Empty {
+                board.play(&mut pos, if black {Black} else {White}, point);
+            }
+        }
+
+        let before = colors(&board, &pos);
+        let mut once = pos.clone();
+        board.keep_only_immortal(&mut once);
+
+        for p in 0..board.point_count() {
+            prop_assert!(once[p] == before[p] || once[p] == Empty, "point {} changed color", p);
+        }
+
+        check_chains(&board, &once)?;
+
+        let mut twice = once.clone();
+        board.keep_only_immortal(&mut twice);
+        prop_assert_eq!(colors(&board, &twice), colors(&board, &once), "keep_only_immortal isn't idempotent");
+    }
+
+    #[test]
+    fn area_score_counts_surrounded_bubbles(edges in arb_edges(), moves in arb_moves()) {
+        let board = Board::new(edges);
+        let mut pos = board.empty_position();
+
+        for (black, point) in moves {
+            let point = point % board.point_count();
+            if pos[point] == Empty {
+                board.play(&mut pos, if black {Black} else {White}, point);
+            }
+        }
+
+        let colors = colors(&board, &pos);
+        let mut expected = board.score_delta_stone(&pos);
+
+        for p in 0..board.point_count() {
+            if colors[p] != Empty {continue;}
+
+            let bubble = flood(&board, &colors, p);
+            let around: Vec<Color> = bubble.iter().flat_map(|&q| board.get_neighbors(q))
+                                           .map(|n| colors[n]).filter(|&c| c != Empty).collect();
+
+            // Each empty point counts for the color that surrounds its bubble,
+            // if only one does.
+
+            if !around.is_empty() && around.iter().all(|&c| c == Black) {expected += 1;}
+            if !around.is_empty() && around.iter().all(|&c| c == White) {expected -= 1;}
+        }
+
+        prop_assert_eq!(board.score_delta_area(&pos), expected);
+    }
+}
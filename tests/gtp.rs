@@ -0,0 +1,128 @@
+// Tests for the stones_gtp binary, talking to it over stdin and stdout the way
+// a GTP client would.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Send a list of commands and return the responses. Successful responses lose
+// their "=" and any whitespace, and failures keep their "?".
+
+fn session(commands: &[&str]) -> Vec<String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_stones_gtp"))
+        .args(["--size", "3", "--think", "0.05"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let input = commands.join("\n") + "\nquit\n";
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = String::from_utf8(child.wait_with_output().unwrap().stdout).unwrap();
+
+    let mut responses: Vec<String> =
+        output.split("\n\n")
+              .filter(|r| !r.is_empty())
+              .map(|r| r.strip_prefix('=').map_or(r, |rest| rest.trim()).to_string())
+              .collect();
+
+    responses.pop();
+    responses
+}
+
+fn showboard(commands: &[&str]) -> String {
+    let commands = [commands, &["showboard"]].concat();
+    session(&commands).pop().unwrap()
+}
+
+#[test]
+fn illegal_move_leaves_no_pass_behind() {
+    let commands = ["play b B2", "play b B2", "undo"];
+    let responses = session(&commands);
+
+    assert_eq!(responses[0], "");
+    assert!(responses[1].starts_with('?'));
+    assert_eq!(responses[2], "");
+    assert_eq!(showboard(&commands), showboard(&[]));
+}
+
+#[test]
+fn undo_takes_back_a_move_with_its_pass() {
+    let responses = session(&["play b A1", "play b C3", "undo", "undo", "undo"]);
+
+    assert_eq!(&responses[..4], ["", "", "", ""]);
+    assert!(responses[4].starts_with('?'));
+    assert_eq!(showboard(&["play b A1", "play b C3", "undo"]), showboard(&["play b A1"]));
+}
+
+#[test]
+fn play_goes_on_after_two_passes() {
+    let responses = session(&["play b pass", "play b A1", "play w A1", "play w B2", "play b pass", "play w pass", "play b C3"]);
+
+    assert_eq!(&responses[..2], ["", ""]);
+    assert!(responses[2].starts_with('?'));
+    assert_eq!(&responses[3..], ["", "", "", ""]);
+    assert_eq!(showboard(&["play b pass", "play b A1", "undo"]), showboard(&[]));
+}
+
+#[test]
+fn genmove_goes_on_after_two_passes() {
+    let responses = session(&["play b pass", "play w pass", "genmove b", "undo", "undo", "undo", "undo"]);
+
+    assert_eq!(&responses[..2], ["", ""]);
+    assert!(!responses[2].starts_with('?'));
+    assert_eq!(&responses[3..6], ["", "", ""]);
+    assert!(responses[6].starts_with('?'));
+}
+
+#[test]
+fn genmove_after_a_move_of_the_same_color() {
+    let responses = session(&["play b B2", "genmove b", "undo", "undo"]);
+
+    assert_eq!(responses[0], "");
+    assert!(!responses[1].starts_with('?'));
+    assert_eq!(&responses[2..], ["", ""]);
+    assert_eq!(showboard(&["play b B2", "genmove b", "undo", "undo"]), showboard(&[]));
+}
+
+#[test]
+fn load_board_checks_the_edge_list() {
+    let responses = session(&[
+        "stones-load_board []",
+        "stones-load_board [[0,1],[1,1]]",
+        "stones-load_board [[0,2]]",
+        "stones-load_board [[0,1],[1,18446744073709551615]]",
+        "stones-load_board [[0,1],[1,2]]",
+        "stones-point_count",
+    ]);
+
+    assert_eq!(&responses[..4], ["? invalid edge list"; 4]);
+    assert_eq!(&responses[4..], ["", "3"]);
+}
+
+#[test]
+fn load_board_takes_an_edge_list_with_spaces() {
+    let responses = session(&[
+        "stones-load_board [[0, 1], [1, 2]]",
+        "stones-point_count",
+        "7 stones-load_board  [[0, 1], [1, 2], [2, 3], [3, 0]] ",
+        "stones-point_count",
+        "stones-load_board",
+    ]);
+
+    assert_eq!(&responses[..4], ["", "3", "7", "4"]);
+    assert!(responses[4].starts_with('?'));
+}
+
+#[test]
+fn load_board_takes_a_spec_with_spaces() {
+    let responses = session(&[
+        "stones-load_board square:3 | remove(4)",
+        "stones-point_count",
+        "7 stones-load_board  hex:1 + loop:4 @ (5, 0) ",
+        "stones-point_count",
+        "stones-load_board",
+    ]);
+
+    assert_eq!(&responses[..4], ["", "8", "7", "11"]);
+    assert!(responses[4].starts_with('?'));
+}
@@ -0,0 +1,105 @@
+// Tests for the ghost points of stones::layout. A wrapped board's ghosts show
+// the points across the wrap, so that the edges joining them can be drawn at
+// the usual length, and the edges that don't reach a ghost are drawn as stubs.
+
+use stones::layout::{edge_segments, wrap_extent, Layout, LayoutTrait, STUB_RATIO};
+
+mod common;
+use common::lae;
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    f32::hypot(a.0 - b.0, a.1 - b.1)
+}
+
+#[test]
+fn only_wrapped_boards_have_ghosts() {
+    for spec in ["square:3", "hex:2", "loop:6"] {
+        let (layout, _) = lae(spec);
+        assert!(layout.ghosts.is_empty(), "Ghosts on {}.", spec);
+        assert_eq!(wrap_extent(&layout), f32::INFINITY);
+    }
+
+    // A ghost down the right side for each row and along the bottom for each
+    // column, plus one in the corner unless the wraps disagree about it.
+
+    let cases = [
+        ("cylinder:5:3", 3),
+        ("mobius:5:3",   3),
+        ("torus:4:4",    9),
+        ("klein:4:4",    8),
+    ];
+
+    for (spec, ghosts) in cases {
+        let (layout, _) = lae(spec);
+        assert_eq!(layout.ghosts.len(), ghosts, "Wrong number of ghosts on {}.", spec);
+        assert!(wrap_extent(&layout).is_finite());
+    }
+}
+
+// Every edge of a wrapped board can be drawn at the spacing between points,
+// from a point to either the other point or one of its ghosts.
+
+#[test]
+fn every_wrapped_edge_reaches_a_ghost() {
+    for spec in ["cylinder:5:3", "mobius:5:3", "torus:4:4", "klein:4:4"] {
+        let (layout, edges) = lae(spec);
+        let spacing = layout.min_point_separation();
+
+        for (a, b) in edges {
+            let shortest = layout.positions(a).into_iter()
+                .flat_map(|p| layout.positions(b).into_iter().map(move |q| distance(p, q)))
+                .reduce(f32::min).unwrap();
+
+            assert!((shortest - spacing).abs() < 0.01, "Edge {}-{} on {} reaches no ghost.", a, b, spec);
+        }
+    }
+}
+
+#[test]
+fn positions_start_with_the_primary_one() {
+    let (layout, _) = lae("torus:4:4");
+
+    assert_eq!(layout.positions(0), vec![(0.0, 0.0), (4.0, 0.0), (0.0, 4.0), (4.0, 4.0)]);
+    assert_eq!(layout.positions(5), vec![(1.0, 1.0)]);
+    assert_eq!(layout.all_positions().iter().filter(|p| p.2).count(), layout.ghosts.len());
+}
+
+#[test]
+fn wrapped_edges_are_drawn_as_stubs() {
+    let (layout, edges) = lae("torus:4:4");
+    let extent = wrap_extent(&layout);
+    let segments = |a: usize, b: usize| edge_segments(layout[a], layout[b], 1.0, extent);
+
+    assert_eq!(segments(0, 1), vec![(layout[0], layout[1])]);
+    assert_eq!(segments(0, 4), vec![(layout[0], layout[4])]);
+
+    let stubs = segments(0, 3);
+    assert_eq!(stubs.len(), 2);
+    for (start, end) in stubs {
+        assert!((distance(start, end) - STUB_RATIO).abs() < 0.001);
+    }
+
+    // The edges across the wrap are the only ones split.
+
+    let split = edges.iter().filter(|&&(a, b)| segments(a, b).len() == 2).count();
+    assert_eq!(split, 8);
+
+    // The same long edge on a board that doesn't wrap is drawn in full.
+
+    let (plain, _) = lae("square:4");
+    assert_eq!(edge_segments(plain[0], plain[3], 1.0, wrap_extent(&plain)).len(), 1);
+}
+
+#[test]
+fn ghosts_survive_a_round_trip_through_json() {
+    let (layout, _) = lae("klein:4:4");
+    let json = serde_json::to_string(&layout).unwrap();
+
+    assert!(json.contains("\"ghosts\""));
+    assert_eq!(serde_json::from_str::<Layout>(&json).unwrap(), layout);
+
+    // A layout without ghosts keeps the plain list format.
+
+    let (plain, _) = lae("square:2");
+    assert_eq!(serde_json::to_string(&plain).unwrap(), "[[0.0,0.0],[1.0,0.0],[0.0,1.0],[1.0,1.0]]");
+}
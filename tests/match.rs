@@ -0,0 +1,74 @@
+// Tests for the stones_match binary, which plays a short match on a tiny
+// board and writes the games to a temporary directory.
+
+use std::process::Command;
+use stones::gametree::{GameTree, Turn};
+use stones::san::read_san_file;
+
+// The tree saved in a .san file has the one game in it; replay it to the end.
+
+fn final_position(filename: &str) -> GameTree {
+    let (saved, _) = read_san_file(filename).unwrap();
+    let (root, nodes): (usize, Vec<serde_json::Value>) = serde_json::from_str(&saved.to_string()).unwrap();
+    let mut tree = GameTree::new(saved.board().clone());
+    let mut node = root;
+
+    while let Some(child) = nodes[node]["children"].get(0) {
+        let turn: Turn = serde_json::from_value(child[0].clone()).unwrap();
+        tree.turn(tree.whose_turn(), turn);
+        node = child[1].as_u64().unwrap() as usize;
+    }
+
+    tree
+}
+
+#[test]
+fn match_writes_each_game_and_tallies_the_results() {
+    let dir = std::env::temp_dir().join(format!("stones_match_test_{}", std::process::id()));
+    let dir = dir.to_str().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_stones_match"))
+        .args(["grid:3:1", "random", "mcts:20", "-n", "2", "--seed", "5", "--scoring", "area", "-o", dir])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    let results: Vec<String> = (1..=2).map(|game| {
+        let tree = final_position(&format!("{}/game-{:03}.san", dir, game));
+        assert!(tree.game_over(), "Game {} didn't end.", game);
+
+        match tree.score_delta_area() {
+            0 => "0".to_string(),
+            score if score > 0 => format!("B+{}", score),
+            score => format!("W+{}", -score),
+        }
+    }).collect();
+
+    std::fs::remove_dir_all(dir).unwrap();
+
+    assert!(stdout.contains(&format!("Game   1: random (B) vs mcts:20 (W): {}\n", results[0])), "{}", stdout);
+    assert!(stdout.contains(&format!("Game   2: mcts:20 (B) vs random (W): {}\n", results[1])), "{}", stdout);
+    assert!(stdout.contains("2 games on grid:3:1 (area scoring, komi 0)"), "{}", stdout);
+
+    // Every game is a win for one player or a draw.
+
+    let tally = |prefix: &str| {
+        let line = stdout.lines().find(|line| line.trim_start().starts_with(prefix)).unwrap();
+        line.trim_start()[prefix.len()..].split(' ').next().unwrap().parse::<usize>().unwrap()
+    };
+
+    assert_eq!(tally("random: ") + tally("mcts:20: ") + tally("Draws: "), 2, "{}", stdout);
+}
+
+#[test]
+fn unknown_scoring_is_an_error() {
+    let output = Command::new(env!("CARGO_BIN_EXE_stones_match"))
+        .args(["grid:3:1", "random", "random", "--scoring", "japanese"])
+        .output()
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error: scoring 'japanese' does not exist. Valid scorings are stone and area.\n"
+    );
+}
@@ -0,0 +1,118 @@
+// Tests for stones::mcts. Its answers are only estimates, so these check that
+// with plenty of playouts it finds a best move on small boards, according to
+// the exact values from stones::solver.
+
+use std::time::Duration;
+use stones::analysis::{analyze, analysis_at, store_analysis, Analysis, Evaluation};
+use stones::boards::lae_from_spec;
+use stones::engine::Board;
+use stones::engine::Color::*;
+use stones::gametree::GameTree;
+use stones::gametree::Turn::*;
+use stones::gametree::TurnResult::*;
+use stones::mcts::{Mcts, MctsConfig, MctsResult};
+use stones::solver::{Solver, SolverConfig};
+
+const SMALL_BOARDS: &[&str] = &["square:2", "loop:4", "grid:3:1", "grid:4:1", "loop:3"];
+
+fn tree(spec: &str) -> GameTree {
+    GameTree::new(Board::new(lae_from_spec(spec).unwrap().1))
+}
+
+fn value(tree: &GameTree) -> i32 {
+    match Solver::new(SolverConfig::default()).solve(tree) {
+        Ok(result) => result.value,
+        Err(_) => panic!("The search stopped without any limits set."),
+    }
+}
+
+fn mcts(playouts: u64, seed: u64) -> Mcts {
+    Mcts::new(MctsConfig {playouts: Some(playouts), seed: Some(seed), ..MctsConfig::default()})
+}
+
+#[test]
+fn best_move_keeps_the_value_of_the_position() {
+    for spec in SMALL_BOARDS {
+        let mut tree = tree(spec);
+        let before = value(&tree);
+
+        let turn = mcts(3000, 31).search(&tree).best_move().unwrap();
+        tree.turn(tree.whose_turn(), turn);
+
+        assert_eq!(value(&tree), before, "MCTS chose {:?} on {}, which isn't a best move.", turn, spec);
+    }
+}
+
+#[test]
+fn search_reports_every_playout() {
+    let result = mcts(500, 1).search(&tree("square:3"));
+    let visits: u64 = result.moves.iter().map(|m| m.visits).sum();
+
+    assert_eq!(result.playouts, 500);
+    assert_eq!(visits, 500);
+    assert!(result.moves.windows(2).all(|pair| pair[0].visits >= pair[1].visits));
+    assert!(result.moves.iter().all(|m| (0.0..=1.0).contains(&m.win_rate)));
+}
+
+#[test]
+fn same_seed_gives_same_search() {
+    let first = mcts(300, 7).search(&tree("square:3"));
+    let second = mcts(300, 7).search(&tree("square:3"));
+    let summary = |result: &MctsResult| {
+        result.moves.iter().map(|m| (m.turn, m.visits)).collect::<Vec<_>>()
+    };
+
+    assert_eq!(summary(&first), summary(&second));
+}
+
+// On a line of five with Black at 0 and White at 2, Black playing at 1 is
+// suicide, which GameTree allows. The search should consider exactly the
+// moves GameTree does.
+
+#[test]
+fn root_moves_follow_the_rules_of_the_game_tree() {
+    let mut tree = tree("grid:5:1");
+    tree.turn(Black, Play(0));
+    tree.turn(White, Play(2));
+
+    let legal: Vec<_> = [Pass].into_iter().chain((0..5).map(Play)).filter(|&turn| {
+        let mut copy = tree.clone();
+        copy.turn(Black, turn) == Success
+    }).collect();
+
+    let tried: Vec<_> = mcts(200, 3).search(&tree).moves.iter().map(|m| m.turn).collect();
+
+    assert!(legal.contains(&Play(1)));
+    assert_eq!(tried.len(), legal.len());
+    assert!(legal.iter().all(|turn| tried.contains(turn)), "MCTS tried {:?}, not {:?}.", tried, legal);
+}
+
+// An analysis is kept in its node as an annotation, which is saved with the
+// tree under the name "analysis".
+
+#[test]
+fn analyses_are_saved_with_the_tree() {
+    let mut tree = tree("square:3");
+    tree.turn(Black, Play(4));
+
+    let node = tree.node_id();
+    let analysis = analyze(&tree, Duration::from_millis(50));
+    store_analysis(&mut tree, node, &analysis);
+
+    let saved = tree.to_string();
+    let mut loaded = GameTree::from_string(tree.board().clone(), saved.clone());
+    loaded.turn(Black, Play(4));
+
+    // Win rates can lose their last bit on the way through the text, so only
+    // the moves and visits are compared.
+
+    let summary = |analysis: &Analysis| analysis.moves.iter().map(|m| match m.1 {
+        Evaluation::Exact(value)      => (m.0, value as i64),
+        Evaluation::Mcts {visits, ..} => (m.0, visits as i64),
+    }).collect::<Vec<_>>();
+
+    assert!(saved.contains("\"analysis\":{\"moves\""), "{}", saved);
+    assert_eq!(analysis_at(&loaded).map(|a| summary(&a)), Some(summary(&analysis)));
+    loaded.undo();
+    assert_eq!(analysis_at(&loaded), None);
+}
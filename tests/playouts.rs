@@ -0,0 +1,81 @@
+// Regression tests for the engine, using the seeded random games from
+// stones::bench. A small board of each type there was when these were first
+// written has its games pinned down by their move count, capture count, and
+// checksum. New board types don't need adding: these are here to check the
+// engine, and the boards themselves are checked in tests/boards.rs. If a
+// change to the engine makes any of these fail, it changed which moves are
+// legal or what they capture; if that was deliberate, update the numbers with
+// stones_bench.
+
+use stones::boards::board_types;
+use stones::bench::{run_playouts, PlayoutConfig};
+
+mod common;
+use common::board;
+
+const GAMES: usize = 10;
+const SEED:  u64   = 39;
+
+const PINNED: &[(&str, u64, u64, u64)] = &[
+    ("square:7",       1085, 638,  0xf35b8d1208fc3b23),
+    ("diamond:4",      316,  180,  0x852aee0dd173884c),
+    ("grid:5:3",       271,  141,  0x99792aa447523698),
+    ("cylinder:5:3",   308,  180,  0xa4fb8b762b165bf6),
+    ("torus:4:4",      317,  179,  0x3fd19e187060114b),
+    ("mobius:5:3",     269,  141,  0xe7c77d0e4aee1335),
+    ("klein:4:4",      321,  186,  0x77f18896c51dd313),
+    ("loop:12",        281,  190,  0x7eef54bfe4a383bb),
+    ("hex:2",          329,  161,  0xa94cfa73dd3b28b0),
+    ("honeycomb:1",    489,  271,  0xfe4b86242c996c14),
+    ("sixfourthree:1", 1338, 646,  0xeb8c7b9ae3d11557),
+    ("turtle:3:3",     1280, 731,  0xd35f17cc7c606a28),
+    ("wheels:2:2",     1407, 799,  0xef5dfa767a94b957),
+    ("donut:6:6:2:2",  683,  409,  0xe765ff410d86cd88),
+    ("conga:10",       241,  175,  0x87e5a128851d2e67),
+    ("pack",           2916, 1798, 0x4d3c0a9c81dfa3fa),
+    ("pack2",          4518, 3273, 0xc02e23d950691182),
+];
+
+#[test]
+fn every_pinned_board_is_a_board_type() {
+    for pinned in PINNED {
+        let name = pinned.0.split(':').next().unwrap();
+        assert!(
+            board_types().iter().any(|template| template.split(':').next().unwrap() == name),
+            "Pinned board '{}' isn't a board type.", pinned.0
+        );
+    }
+}
+
+#[test]
+fn pinned_checksums() {
+    let config = PlayoutConfig {games: GAMES, seed: SEED};
+
+    for &(spec, moves, captures, checksum) in PINNED {
+        let stats = run_playouts(&board(spec), &config);
+        assert_eq!(
+            (stats.moves, stats.captures, stats.checksum), (moves, captures, checksum),
+            "Playouts on {} changed.", spec
+        );
+    }
+}
+
+#[test]
+fn playouts_are_deterministic() {
+    let config = PlayoutConfig {games: 3, seed: 12345};
+    let board = board("square:9");
+    let first = run_playouts(&board, &config);
+    let second = run_playouts(&board, &config);
+
+    assert_eq!(first.checksum, second.checksum);
+    assert_eq!(first.moves, second.moves);
+}
+
+#[test]
+fn different_seeds_give_different_games() {
+    let board = board("square:9");
+    let first = run_playouts(&board, &PlayoutConfig {games: 3, seed: 1});
+    let second = run_playouts(&board, &PlayoutConfig {games: 3, seed: 2});
+
+    assert_ne!(first.checksum, second.checksum);
+}
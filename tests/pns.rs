@@ -0,0 +1,82 @@
+// Tests for stones::pns. The questions are about boards small enough to solve
+// exactly, so the answers can be checked against their known values.
+
+use stones::boards::lae_from_spec;
+use stones::engine::Board;
+use stones::engine::Color::*;
+use stones::gametree::GameTree;
+use stones::gametree::Turn::*;
+use stones::pns::{ProofNumberSearch, PnsConfig, Question};
+
+// The values of the empty boards under stone scoring, with Black to play (see
+// tests/solver.rs).
+
+const KNOWN_VALUES: &[(&str, i32)] = &[
+    ("square:2", 1),
+    ("loop:4",   1),
+    ("grid:3:1", 1),
+    ("grid:4:1", 2),
+    ("loop:3",   0),
+];
+
+fn tree(spec: &str) -> GameTree {
+    GameTree::new(Board::new(lae_from_spec(spec).unwrap().1))
+}
+
+fn answer(spec: &str, question: &Question) -> Option<bool> {
+    ProofNumberSearch::new(PnsConfig::default()).answer(&tree(spec), question).answer
+}
+
+#[test]
+fn score_questions_agree_with_known_values() {
+    for &(spec, value) in KNOWN_VALUES {
+        assert_eq!(answer(spec, &Question::score_at_least(Black, value)), Some(true),
+                   "Black should get {} on {}.", value, spec);
+        assert_eq!(answer(spec, &Question::score_at_least(Black, value + 1)), Some(false),
+                   "Black shouldn't get {} on {}.", value + 1, spec);
+        assert_eq!(answer(spec, &Question::score_at_least(White, -value)), Some(true),
+                   "White should hold Black to {} on {}.", value, spec);
+        assert_eq!(answer(spec, &Question::score_at_least(White, -value + 1)), Some(false),
+                   "White shouldn't hold Black below {} on {}.", value, spec);
+    }
+}
+
+#[test]
+fn black_keeps_the_middle_of_a_line_of_three() {
+    assert_eq!(answer("grid:3:1", &Question::keeps_stone(1, Black)), Some(true));
+    assert_eq!(answer("grid:3:1", &Question::keeps_stone(0, White)), Some(false));
+}
+
+// A proof tree has one winning reply at each of the attacker's turns, so a
+// proof that Black gets the middle of a line of three starts with that move.
+
+#[test]
+fn proof_tree_starts_with_the_winning_move() {
+    let pns = ProofNumberSearch::new(PnsConfig::default());
+    let result = pns.answer(&tree("grid:3:1"), &Question::score_at_least(Black, 1));
+    let proof = result.tree.unwrap();
+
+    assert_eq!(proof.children.len(), 1);
+    assert_eq!(proof.children[0].turn, Some(Play(1)));
+}
+
+#[test]
+fn node_limit_stops_the_search() {
+    let pns = ProofNumberSearch::new(PnsConfig {max_nodes: Some(5), ..PnsConfig::default()});
+    let result = pns.answer(&tree("square:2"), &Question::score_at_least(Black, 1));
+    assert_eq!(result.answer, None);
+    assert!(result.tree.is_none());
+}
+
+// A goal that never gives an answer counts as not reached once the game is
+// over, so the search still finishes.
+
+#[test]
+fn unanswered_goal_is_disproved_at_the_end_of_the_game() {
+    assert_eq!(answer("grid:2:1", &Question::new(Black, |_| None)), Some(false));
+
+    let black_on_point_0 = Question::new(Black, |tree| {
+        if tree.color_at(0) == Black {Some(true)} else {None}
+    });
+    assert_eq!(answer("grid:2:1", &black_on_point_0), Some(true));
+}
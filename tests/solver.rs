@@ -0,0 +1,217 @@
+// Tests for stones::solver, on boards small enough to solve in a moment.
+
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use stones::boards::lae_from_spec;
+use stones::engine::Board;
+use stones::gametree::GameTree;
+use stones::gametree::Turn::*;
+use stones::engine::Color::*;
+use stones::solver::{Solver, SolverConfig, SolveResult, Trace};
+
+const SMALL_BOARDS: &[&str] = &["square:2", "loop:4", "grid:3:1", "grid:4:1", "loop:3"];
+
+// The values of the empty small boards under stone scoring, with Black to
+// play. On the 2x2 board and the 4-point loop Black gets one stone that White
+// can't take away; on a line of 3, Black takes the middle point.
+
+const KNOWN_VALUES: &[(&str, i32)] = &[
+    ("square:2", 1),
+    ("loop:4",   1),
+    ("grid:3:1", 1),
+    ("grid:4:1", 2),
+    ("loop:3",   0),
+];
+
+fn tree(spec: &str) -> GameTree {
+    GameTree::new(Board::new(lae_from_spec(spec).unwrap().1))
+}
+
+fn solve(spec: &str, config: SolverConfig) -> SolveResult {
+    match Solver::new(config).solve(&tree(spec)) {
+        Ok(result) => result,
+        Err(_) => panic!("The search on {} stopped without any limits set.", spec),
+    }
+}
+
+#[test]
+fn parallel_search_agrees_with_single_threaded() {
+    for spec in SMALL_BOARDS {
+        let point_count = tree(spec).board().point_count() as i32;
+
+        for window in [None, Some((0, point_count - 2))] {
+            let single = solve(spec, SolverConfig {window: window, ..SolverConfig::default()});
+
+            for threads in [2, 3, 8] {
+                let config = SolverConfig {threads: threads, window: window, ..SolverConfig::default()};
+                let parallel = solve(spec, config);
+                assert_eq!(
+                    parallel.value, single.value,
+                    "{} threads on {} (window {:?}) disagree with one thread.", threads, spec, window
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn small_boards_have_their_known_values() {
+    for &(spec, value) in KNOWN_VALUES {
+        let result = solve(spec, SolverConfig::default());
+        assert_eq!(result.value, value, "Wrong value for {}.", spec);
+    }
+}
+
+#[test]
+fn principal_variation_starts_with_the_best_move() {
+    let result = solve("grid:3:1", SolverConfig::default());
+    assert_eq!(result.best_move, Some(Play(1)));
+    assert_eq!(result.principal_variation.first().copied(), result.best_move);
+}
+
+// A search stopped by a node limit and then resumed from its checkpoint gets
+// the same value as one that ran straight through, and carries on reporting
+// the best root value it had already found.
+
+#[test]
+fn resumed_search_matches_uninterrupted_search() {
+    let spec = "square:2";
+    let full = solve(spec, SolverConfig::default());
+
+    let reports = Arc::new(Mutex::new(vec![]));
+    let config = SolverConfig {
+        max_nodes:         Some(full.stats.nodes * 3 / 4),
+        progress_interval: Some(Duration::ZERO),
+        ..SolverConfig::default()
+    };
+
+    let log = reports.clone();
+    let solver = Solver::new(config).on_progress(move |progress| log.lock().unwrap().push(progress.root_best));
+    let stopped = match solver.solve(&tree(spec)) {
+        Ok(_) => panic!("The search on {} should have hit its node limit.", spec),
+        Err(stopped) => stopped,
+    };
+
+    let best_before = *reports.lock().unwrap().last().unwrap();
+    assert!(best_before.is_some(), "The root had no value after three quarters of the search.");
+    reports.lock().unwrap().clear();
+
+    let log = reports.clone();
+    let config = SolverConfig {progress_interval: Some(Duration::ZERO), ..SolverConfig::default()};
+    let solver = Solver::new(config).on_progress(move |progress| log.lock().unwrap().push(progress.root_best));
+    let resumed = match solver.resume(&tree(spec), stopped.checkpoint.unwrap()).unwrap() {
+        Ok(result) => result,
+        Err(_) => panic!("The resumed search on {} stopped without any limits set.", spec),
+    };
+
+    assert_eq!(resumed.value, full.value);
+    assert_eq!(reports.lock().unwrap().first().copied(), Some(best_before));
+}
+
+// A depth-limited search stays depth-limited when it's resumed, even if
+// nothing after the checkpoint is cut off at the depth limit.
+
+#[test]
+fn resumed_search_stays_depth_limited() {
+    let spec = "square:2";
+    let limited = solve(spec, SolverConfig {max_depth: Some(2), ..SolverConfig::default()});
+    assert!(limited.stats.depth_limited);
+
+    let config = SolverConfig {max_depth: Some(2), max_nodes: Some(limited.stats.nodes / 2), ..SolverConfig::default()};
+    let checkpoint = match Solver::new(config).solve(&tree(spec)) {
+        Ok(_) => panic!("The search on {} should have hit its node limit.", spec),
+        Err(stopped) => stopped.checkpoint.unwrap(),
+    };
+
+    assert!(checkpoint.depth_limited());
+
+    let config = SolverConfig {max_depth: Some(2), ..SolverConfig::default()};
+    let resumed = match Solver::new(config).resume(&tree(spec), checkpoint).unwrap() {
+        Ok(result) => result,
+        Err(_) => panic!("The resumed search on {} stopped without any limits set.", spec),
+    };
+
+    assert!(resumed.stats.depth_limited);
+}
+
+// A checkpoint can only be resumed on the position it was made for, reached
+// by the same branch, and with the same window and depth limit.
+
+#[test]
+fn resume_rejects_checkpoints_that_dont_match() {
+    let config = SolverConfig {max_nodes: Some(20), ..SolverConfig::default()};
+    let checkpoint = match Solver::new(config).solve(&tree("square:2")) {
+        Ok(_) => panic!("The search on square:2 should have hit its node limit."),
+        Err(stopped) => stopped.checkpoint.unwrap(),
+    };
+
+    let resume = |tree: &GameTree, config: SolverConfig| Solver::new(config).resume(tree, checkpoint.clone()).err();
+    let mut moved = tree("square:2");
+    moved.turn(Black, Play(0));
+
+    let different_position = Some("Checkpoint is for a different board or position.".to_string());
+    let different_config = Some("Checkpoint is for a different window or depth limit.".to_string());
+
+    assert_eq!(resume(&tree("loop:4"), SolverConfig::default()), different_position);
+    assert_eq!(resume(&moved, SolverConfig::default()), different_position);
+    assert_eq!(resume(&tree("square:2"), SolverConfig {window: Some((0, 2)), ..SolverConfig::default()}), different_config);
+    assert_eq!(resume(&tree("square:2"), SolverConfig {max_depth: Some(3), ..SolverConfig::default()}), different_config);
+    assert!(resume(&tree("square:2"), SolverConfig {threads: 2, ..SolverConfig::default()}).is_some());
+    assert_eq!(resume(&tree("square:2"), SolverConfig::default()), None);
+}
+
+// A trace reports every step of the search, starting with the root's pass
+// and ending with its value.
+
+#[test]
+fn trace_reports_every_step() {
+    let steps = Arc::new(Mutex::new(vec![]));
+    let log = steps.clone();
+    let config = SolverConfig {trace: true, ..SolverConfig::default()};
+    let solver = Solver::new(config).on_progress(move |progress| {
+        log.lock().unwrap().push((progress.depth, progress.trace.clone().unwrap()));
+    });
+
+    let result = solver.solve(&tree("grid:3:1")).ok().unwrap();
+    let steps = steps.lock().unwrap();
+
+    assert_eq!(steps.first(), Some(&(0, Trace::Move(Black, Pass))));
+    assert_eq!(steps.last(), Some(&(0, Trace::Return(result.value))));
+    assert!(steps.contains(&(1, Trace::Move(White, Pass))));
+    assert!(steps.iter().any(|(_, step)| matches!(step, Trace::Cutoff(..))));
+
+    // Every node but the root is reached by a move.
+
+    let moves = steps.iter().filter(|(_, step)| matches!(step, Trace::Move(..))).count();
+    assert_eq!(moves as u64, result.stats.nodes - 1);
+}
+
+// The stones_solver binary only reuses a checkpoint file made with the same
+// depth limit, and says so when the result it reuses is depth-limited.
+
+#[test]
+fn checkpoint_file_keeps_the_depth_limit() {
+    let path = std::env::temp_dir().join(format!("stones_solver_test_{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let run = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_stones_solver"))
+            .args(["grid:4:1", "--checkpoint", path])
+            .args(extra)
+            .output()
+            .unwrap();
+        (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+    };
+
+    let (first, _) = run(&["--max-depth", "2"]);
+    let (again, _) = run(&["--max-depth", "2"]);
+    let (_, deeper) = run(&["--max-depth", "3"]);
+    let (_, unlimited) = run(&[]);
+    std::fs::remove_file(path).unwrap();
+
+    assert!(first.contains("(depth-limited, not exact)"), "{}", first);
+    assert!(again.contains("(depth-limited, not exact) (from checkpoint)"), "{}", again);
+    assert!(deeper.contains("different window or depth limit"), "{}", deeper);
+    assert!(unlimited.contains("different window or depth limit"), "{}", unlimited);
+}
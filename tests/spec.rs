@@ -0,0 +1,148 @@
+// Tests for stones::spec: what board specs put together, and the error
+// messages for specs that can't be parsed, which point at the problem.
+
+use stones::boards::lae_from_spec;
+
+mod common;
+use common::board;
+
+fn counts(spec: &str) -> (usize, usize) {
+    let board = board(spec);
+    let degrees: usize = (0..board.point_count()).map(|p| board.get_neighbors(p).len()).sum();
+    (board.point_count(), degrees / 2)
+}
+
+fn error(spec: &str) -> String {
+    lae_from_spec(spec).unwrap_err()
+}
+
+#[test]
+fn plain_numbers_are_squares() {
+    assert_eq!(lae_from_spec("7").unwrap(), lae_from_spec("square:7").unwrap());
+    assert_eq!(lae_from_spec(" ( 7 ) ").unwrap(), lae_from_spec("square:7").unwrap());
+}
+
+#[test]
+fn operators_combine_boards() {
+    let cases = [
+        ("square:3 + square:2 @ (5, 0)",                    (13, 16)),
+        ("square:3 | remove(4)",                            (8,  8)),
+        ("square:3 | remove(4, 4, 0)",                      (7,  6)),
+        ("square:3 | keep(0, 1, 3, 4)",                     (4,  4)),
+        ("square:3 | keep(4, 3, 1, 0, 0)",                  (4,  4)),
+        ("square:2 + square:2 @ (5, 0) | join(1, 4, 3, 6)", (8,  10)),
+        ("grid:4:1 | remove(0) | remove(0)",                (2,  1)),
+        ("loop:3 * loop:4",                                 (12, 24)),
+        ("loop:3 ** grid:2:1",                              (6,  15)),
+        ("square:3 | scale(2) | rotate(0.25)",              (9,  12)),
+    ];
+
+    for (spec, expected) in cases {
+        assert_eq!(counts(spec), expected, "Wrong counts on {}.", spec);
+    }
+
+    assert!(board("square:2 + square:2 @ (5, 0) | join(1, 4)").get_neighbors(1).contains(&4));
+}
+
+// | applies to everything to its left, * binds tighter than +, and @ tighter
+// than either.
+
+#[test]
+fn operators_bind_in_order() {
+    assert_eq!(counts("loop:3 @ (9, 0) + loop:3 * loop:3"),        (12, 21));
+    assert_eq!(counts("(loop:3 + loop:3 @ (9, 0)) * loop:3"),      (18, 36));
+    assert_eq!(counts("loop:4 + grid:3:1 @ (9, 0) | remove(0)"),   (6,  4));
+    assert_eq!(counts("loop:4 + (grid:3:1 @ (9, 0) | remove(0))"), (6,  5));
+
+    let (layout, _) = lae_from_spec("square:2 + square:2 @ (5, -1.5)").unwrap();
+    assert_eq!(layout[4], (layout[0].0 + 5.0, layout[0].1 - 1.5));
+}
+
+// A + B leaves both boards where they are, so they mustn't land on each other.
+
+#[test]
+fn unions_must_not_overlap() {
+    assert_eq!(
+        error("square:3 + square:2"),
+        "Bad board spec: point 0 of the board before '+' is on top of point 0 of the board after it \
+         (move one of them with @).\n  square:3 + square:2\n           ^"
+    );
+    assert_eq!(counts("square:3 + square:2 @ (0.5, 0.5)"), (13, 16));
+}
+
+// The example at the top of spec.rs.
+
+#[test]
+fn the_example_spec_joins_the_hex_to_the_loop() {
+    let (points, edges) = counts("hex:3");
+    assert_eq!(points, 37);
+    assert_eq!(counts("hex:3 + loop:6 @ (10, 0) | join(5, 42)"), (points + 6, edges + 6 + 1));
+    assert!(board("hex:3 + loop:6 @ (10, 0) | join(5, 42)").get_neighbors(5).contains(&42));
+
+    assert!(error("hex:3 + loop:6 @ (10, 0) | join(5, 72)").starts_with(
+        "Bad board spec: join() was given 72, which isn't a point on the board (it has points 0 to 42)."
+    ));
+}
+
+#[test]
+fn parse_errors_point_at_the_problem() {
+    let cases = [
+        ("9x",                   1,  "unexpected 'x' (a plain number N is short for square:N)"),
+        ("grid:3:",              7,  "expected an argument after ':'"),
+        ("square:3 +",           10, "expected a board (like grid:9:9 or 19)"),
+        ("square:3 + )",         11, "expected a board (like grid:9:9 or 19), not ')'"),
+        ("(square:3",            9,  "expected ')'"),
+        ("square:3 @ (1 2)",     14, "expected ',', not '2'"),
+        ("square:3 | remove(a)", 18, "expected a number"),
+        ("square:3 |",           10, "expected an operation after '|'"),
+        ("square:3 square:2",    9,  "unexpected 's'"),
+    ];
+
+    for (spec, position, message) in cases {
+        assert_eq!(
+            error(spec),
+            format!("Bad board spec: {}.\n  {}\n  {}^", message, spec, " ".repeat(position)),
+            "Wrong error for '{}'.", spec
+        );
+    }
+}
+
+#[test]
+fn operation_errors_name_the_operation() {
+    let cases = [
+        ("square:3 | remove(9)",   "remove() was given 9, which isn't a point on the board (it has points 0 to 8)"),
+        ("square:3 | keep(1.5)",   "keep() was given 1.5, which isn't a point on the board (it has points 0 to 8)"),
+        ("square:3 | remove()",    "remove() takes at least 1 point (0 given)"),
+        ("square:3 | remove",      "remove() takes at least 1 point (0 given)"),
+        ("square:3 | keep()",      "keep() takes at least 1 point (0 given)"),
+        ("square:3 | join(1)",     "join() takes pairs of points (1 given)"),
+        ("square:3 | join(2, 2)",  "join() can't join point 2 to itself"),
+        ("square:3 | dual(1)",     "dual() takes no arguments (1 given)"),
+        ("square:3 | scale",       "scale() takes 1 argument (0 given)"),
+    ];
+
+    for (spec, message) in cases {
+        assert_eq!(
+            error(spec),
+            format!("Bad board spec: {}.\n  {}\n             ^", message, spec),
+            "Wrong error for '{}'.", spec
+        );
+    }
+
+    assert_eq!(error("2 | remove()"), "Bad board spec: remove() takes at least 1 point (0 given).\n  2 | remove()\n      ^");
+
+    assert!(error("square:3 | frob").starts_with(
+        "Bad board spec: unknown operation 'frob'.\n  square:3 | frob\n             ^\nValid operations are:\n  - remove(P...)\n"
+    ));
+}
+
+// A spec can parse but still make a board that isn't any use.
+
+#[test]
+fn empty_boards_are_rejected() {
+    assert_eq!(error("square:3 | keep(4)"), "Board spec 'square:3 | keep(4)' makes a board with no edges.");
+    assert_eq!(
+        error("square:2 + square:1 @ (5, 0)"),
+        "Board spec 'square:2 + square:1 @ (5, 0)' makes a board where point 4 has no edges."
+    );
+}
@@ -0,0 +1,147 @@
+// Tests for stones::tiling. Away from the edge of the board, the polygons
+// around every point of a tiling should be the ones its vertex configuration
+// calls for, which shows up in the angles between the point's edges.
+
+use std::f64::consts::PI;
+use stones::boards::lae_from_spec;
+use stones::layout::Layout;
+use stones::tiling::{lae_tiling, parse_configurations, Region};
+
+const ARCHIMEDEAN: [&str; 11] = [
+    "3^6", "4^4", "6^3", "3^4.6", "3^3.4^2", "3^2.4.3.4", "3.4.6.4", "3.6.3.6", "3.12^2", "4.6.12", "4.8^2",
+];
+
+const RADIUS: f64 = 8.0;
+
+// Far enough inside the disc that every polygon around the point is there,
+// since none of them is more than 4 edge lengths across.
+
+const INTERIOR: f64 = RADIUS - 4.0;
+
+fn interior_angle(sides: usize) -> f64 {
+    PI * (sides - 2) as f64 / sides as f64
+}
+
+// The angles between the edges at each point inside the interior, in order
+// around the point.
+
+fn interior_corners(layout: &Layout, edges: &[(usize, usize)]) -> Vec<Vec<f64>> {
+    let mut corners = vec![];
+
+    for (point, &(x, y)) in layout.iter().enumerate() {
+        if f64::hypot(x as f64, y as f64) > INTERIOR {
+            continue;
+        }
+
+        let mut directions: Vec<f64> =
+            edges.iter()
+                 .filter_map(|&(a, b)| if a == point {Some(b)} else if b == point {Some(a)} else {None})
+                 .map(|other| f64::atan2((layout[other].1 - y) as f64, (layout[other].0 - x) as f64))
+                 .collect();
+
+        directions.sort_by(f64::total_cmp);
+        let n = directions.len();
+        corners.push((0..n).map(|i| (directions[(i + 1) % n] - directions[i]).rem_euclid(2.0 * PI)).collect());
+    }
+
+    corners
+}
+
+// Whether the angles around a point match a configuration, starting anywhere
+// and going either way around.
+
+fn matches(corners: &[f64], config: &[usize]) -> bool {
+    let n = config.len();
+    let angles: Vec<f64> = config.iter().map(|&sides| interior_angle(sides)).collect();
+
+    corners.len() == n && (0..n).any(|start| {
+        (0..n).all(|i| (corners[i] - angles[(start + i) % n]).abs() < 0.01) ||
+        (0..n).all(|i| (corners[i] - angles[(start + n - i) % n]).abs() < 0.01)
+    })
+}
+
+#[test]
+fn configurations_parse() {
+    assert_eq!(parse_configurations("3^2.4.3.4").unwrap(), vec![vec![3, 3, 4, 3, 4]]);
+    assert_eq!(parse_configurations("3^6,4.8^2").unwrap(), vec![vec![3; 6], vec![4, 8, 8]]);
+
+    assert_eq!(
+        parse_configurations("3.4.x").unwrap_err(),
+        "Could not parse 'x' in vertex configuration '3.4.x'; expected something like 3.4.6.4 or 3^2.4.3.4."
+    );
+    assert_eq!(
+        parse_configurations("4^4,4.4.4").unwrap_err(),
+        "The angles in vertex configuration '4.4.4' add up to 270.0 degrees, not 360."
+    );
+    assert!(parse_configurations("2.6^3").is_err());
+}
+
+#[test]
+fn archimedean_tilings_are_uniform() {
+    for text in ARCHIMEDEAN {
+        let config = &parse_configurations(text).unwrap()[0];
+        let (layout, edges) = lae_tiling(text, Region::Disc(RADIUS)).unwrap();
+        let corners = interior_corners(&layout, &edges);
+
+        assert!(!corners.is_empty(), "No interior points on {}.", text);
+
+        for point in &corners {
+            assert!(matches(point, config), "A point of {} has the wrong polygons around it.", text);
+        }
+
+        for &(a, b) in &edges {
+            let length = f32::hypot(layout[a].0 - layout[b].0, layout[a].1 - layout[b].1);
+            assert!((length - 1.0).abs() < 0.001, "An edge of {} has length {}.", text, length);
+        }
+    }
+}
+
+// A 2-uniform tiling uses both of its configurations, and nothing else.
+
+#[test]
+fn two_uniform_tilings_use_both_configurations() {
+    for text in ["3^6,3^2.4.3.4", "3.4.6.4,3.4^2.6", "3^6,3^2.6^2", "3.4^2.6,3.6.3.6"] {
+        let configs = parse_configurations(text).unwrap();
+        let (layout, edges) = lae_tiling(text, Region::Disc(RADIUS)).unwrap();
+        let corners = interior_corners(&layout, &edges);
+
+        for point in &corners {
+            assert!(configs.iter().any(|config| matches(point, config)),
+                    "A point of {} matches neither configuration.", text);
+        }
+
+        for config in &configs {
+            assert!(corners.iter().any(|point| matches(point, config)),
+                    "{} never uses {:?}.", text, config);
+        }
+    }
+}
+
+#[test]
+fn rectangles_stay_inside_their_bounds() {
+    let (layout, _) = lae_tiling("4.8^2", Region::Rectangle(10.0, 6.0)).unwrap();
+
+    assert!(layout.iter().all(|p| p.0.abs() <= 5.001 && p.1.abs() <= 3.001));
+}
+
+#[test]
+fn configurations_that_dont_tile_are_rejected() {
+    assert_eq!(
+        lae_tiling("5^2.10", Region::Disc(3.0)).unwrap_err(),
+        "Vertex configuration '5^2.10' doesn't tile the plane (or not in any way this could find)."
+    );
+}
+
+#[test]
+fn regions_that_are_too_big_are_rejected() {
+    assert_eq!(
+        lae_tiling("4^4", Region::Rectangle(100.0, 100.0)).unwrap_err(),
+        "The region for a tiling can cover at most 2500 square edge lengths, not 10000."
+    );
+    assert_eq!(
+        lae_tiling("4^4", Region::Disc(f64::INFINITY)).unwrap_err(),
+        "The region for a tiling can cover at most 2500 square edge lengths, not inf."
+    );
+    assert!(lae_from_spec("tiling:4.4.4.4:1e9").is_err());
+    assert!(lae_from_spec("tiling:4.4.4.4:inf").is_err());
+}
@@ -0,0 +1,242 @@
+// Tests for stones::transform. Each graph transformation has a count of
+// points and edges that follows from the board it's given, and some of them
+// turn one known board into another.
+
+use std::collections::HashSet;
+use stones::boards::{lae_from_spec, Lae};
+use stones::transform::*;
+
+mod common;
+use common::lae;
+
+// Each edge once, smaller point first.
+
+fn edge_set(edges: &[(usize, usize)]) -> HashSet<(usize, usize)> {
+    edges.iter().map(|&(a, b)| (a.min(b), a.max(b))).collect()
+}
+
+fn degrees((layout, edges): &Lae) -> Vec<usize> {
+    let mut degrees = vec![0; layout.len()];
+    for &(a, b) in &edge_set(edges) {
+        degrees[a] += 1;
+        degrees[b] += 1;
+    }
+    degrees
+}
+
+fn counts(lae: &Lae) -> (usize, usize) {
+    (lae.0.len(), edge_set(&lae.1).len())
+}
+
+// Subdividing adds a point for every edge, which then becomes two edges.
+
+#[test]
+fn subdivision() {
+    for spec in ["square:3", "loop:5", "hex:2", "torus:3:3", "cube:2:2:2"] {
+        let (points, edges) = counts(&lae(spec));
+        let subdivided = lae_subdivided(lae(spec));
+
+        assert_eq!(counts(&subdivided), (points + edges, 2 * edges), "Wrong counts on {} | subdivide.", spec);
+        assert!(degrees(&subdivided)[points..].iter().all(|&d| d == 2));
+        assert_eq!(&degrees(&subdivided)[..points], &degrees(&lae(spec))[..]);
+    }
+
+    assert_eq!(counts(&lae("loop:5 | subdivide")), (10, 10));
+    assert!(lae("cube:2:2:2 | subdivide").0.is_3d());
+}
+
+// The line graph has a point for every edge, and an edge for every pair of
+// edges that meet, which is D(D - 1)/2 of them at a point of degree D.
+
+#[test]
+fn line_graphs() {
+    for spec in ["square:3", "loop:5", "hex:2", "tetrahedron", "grid:4:1"] {
+        let board = lae(spec);
+        let meetings: usize = degrees(&board).iter().map(|&d| d * (d - 1) / 2).sum();
+
+        assert_eq!(counts(&lae_line_graph(board.clone())), (counts(&board).1, meetings), "Wrong counts on {} | line.", spec);
+    }
+
+    assert_eq!(counts(&lae("square:3 | line")), (12, 22));
+
+    // A loop's line graph is the same loop, and a tetrahedron's is an
+    // octahedron.
+
+    assert_eq!(degrees(&lae("loop:5 | line")), vec![2; 5]);
+    assert_eq!(counts(&lae("tetrahedron | line")), counts(&lae("octahedron")));
+    assert_eq!(degrees(&lae("tetrahedron | line")), vec![4; 6]);
+}
+
+// The dual of a 5x5 grid is a 4x4 grid, with a point in the middle of each
+// square, so it's the 4x4 grid moved by half a square in each direction.
+
+#[test]
+fn dual_of_a_grid_is_a_grid() {
+    let (layout, edges) = lae("grid:5:5 | dual");
+    let (grid_layout, grid_edges) = lae("grid:4:4");
+
+    let index: Vec<usize> = layout.iter().map(|&(x, y)| {
+        grid_layout.iter().position(|&(gx, gy)| (gx + 0.5 - x).abs() < 0.01 && (gy + 0.5 - y).abs() < 0.01)
+                   .expect("A point of the dual isn't in the middle of a square.")
+    }).collect();
+
+    let mapped: Vec<(usize, usize)> = edges.iter().map(|&(a, b)| (index[a], index[b])).collect();
+
+    assert_eq!(index.iter().collect::<HashSet<_>>().len(), 16);
+    assert_eq!(edge_set(&mapped), edge_set(&grid_edges));
+}
+
+#[test]
+fn duals() {
+    // A triangular board's faces are triangles, so its dual has degree at
+    // most 3; a hexagonal board's faces are hexagons, so its dual has degree
+    // at most 6.
+
+    assert!(degrees(&lae("hex:3 | dual")).iter().all(|&d| d <= 3));
+    assert!(degrees(&lae("tiling:6^3:5 | dual")).iter().all(|&d| d <= 6));
+
+    // Each dual of a grid is one square smaller.
+
+    assert_eq!(counts(&lae("square:4 | dual")), counts(&lae("square:3")));
+    assert_eq!(counts(&lae("grid:5:5 | dual | dual")), counts(&lae("square:3")));
+
+    // A board with no faces has an empty dual.
+
+    assert_eq!(counts(&lae_dual(lae("grid:6:1")).unwrap()), (0, 0));
+
+    // A torus's faces are all squares, wrap-around or not.
+
+    assert_eq!(counts(&lae("torus:4:5 | dual")), counts(&lae("torus:4:5")));
+    assert_eq!(degrees(&lae("torus:4:5 | dual")), vec![4; 20]);
+}
+
+// A Mobius strip or Klein bottle wraps around with a twist, so its ghosts
+// across the twist are upside down and its faces can't be traced from the
+// layout.
+
+#[test]
+fn no_dual_across_a_twist() {
+    for spec in ["mobius:5:3", "mobius:4:4", "klein:4:4", "klein:3:5"] {
+        let err = lae_dual(lae(spec)).expect_err(&format!("{} has a dual.", spec));
+        assert!(err.contains("twist"), "Wrong error on {}: {}", spec, err);
+        assert!(lae_closed_dual(lae(spec)).is_err());
+    }
+
+    let err = lae_from_spec("mobius:5:3 | dual").unwrap_err();
+    assert!(err.contains("dual() doesn't work on boards that wrap around with a twist"), "Wrong error: {}", err);
+
+    // The parts of them that don't wrap around are fine.
+
+    assert_eq!(counts(&lae("mobius:5:3 | remove(4, 9, 14) | dual")), counts(&lae("grid:3:2")));
+}
+
+// The dual of a torus wraps around too, so its edges across the wrap go
+// through ghosts, and are as short as the rest.
+
+#[test]
+fn duals_of_wrapped_boards_wrap() {
+    let (layout, edges) = lae("torus:4:4 | dual");
+    assert!(!layout.ghosts.is_empty());
+
+    for (a, b) in edges {
+        let shortest = layout.positions(a).into_iter()
+            .flat_map(|p| layout.positions(b).into_iter().map(move |q| f32::hypot(q.0 - p.0, q.1 - p.1)))
+            .fold(f32::INFINITY, f32::min);
+        assert!((shortest - 1.0).abs() < 1e-3, "Edge {}-{} of the dual is {} long.", a, b, shortest);
+    }
+
+    assert_eq!(counts(&lae("torus:4:4 | dual | dual")), counts(&lae("torus:4:4")));
+    assert_eq!(degrees(&lae("torus:5:3 | dual | dual")), vec![4; 15]);
+}
+
+// Faces can't be traced on a board whose edges cross, like a cube seen from
+// the side.
+
+#[test]
+fn no_dual_with_crossing_edges() {
+    for spec in ["cube:2:2:2", "cube:3:3:2", "square:3 | join(0, 4, 1, 3)"] {
+        let err = lae_from_spec(&format!("{} | dual", spec)).unwrap_err();
+        assert!(err.contains("dual() doesn't work on boards whose edges cross"), "Wrong error on {}: {}", spec, err);
+    }
+}
+
+// The closed dual has a point for the outside face too, so it turns a solid
+// into its dual solid.
+
+#[test]
+fn closed_duals() {
+    let pairs = [("hexahedron", "octahedron"), ("octahedron", "hexahedron"), ("dodecahedron", "icosahedron"),
+                 ("icosahedron", "dodecahedron"), ("tetrahedron", "tetrahedron")];
+
+    for (solid, dual) in pairs {
+        let closed_dual = lae_closed_dual(lae(solid)).unwrap();
+        let mut dual_degrees = degrees(&lae(dual));
+        let mut closed_dual_degrees = degrees(&closed_dual);
+        dual_degrees.sort();
+        closed_dual_degrees.sort();
+
+        assert_eq!(counts(&closed_dual), counts(&lae(dual)), "Wrong counts on {} | closed-dual.", solid);
+        assert_eq!(closed_dual_degrees, dual_degrees, "Wrong degrees on {} | closed-dual.", solid);
+    }
+
+    assert_eq!(counts(&lae("hexahedron | closed-dual")), (6, 12));
+    assert_eq!(degrees(&lae("hexahedron | closed-dual")), vec![4; 6]);
+    assert_eq!(counts(&lae("hexahedron | dual")), (5, 8));
+
+    // The plain dual is the closed dual without the outside face's point,
+    // which comes last.
+
+    let (layout, edges) = lae("square:4 | closed-dual");
+    let (dual_layout, dual_edges) = lae("square:4 | dual");
+
+    assert_eq!(layout.len(), 10);
+    assert_eq!(&layout[..9], &dual_layout[..]);
+    assert_eq!(edge_set(&edges).into_iter().filter(|&(_, b)| b < 9).collect::<HashSet<_>>(), edge_set(&dual_edges));
+    assert_eq!(degrees(&(layout, edges))[9], 8);
+}
+
+// A product of boards with V and W points has VW points. The Cartesian
+// product of boards with E and F edges has EW + VF edges, and the strong
+// product another 2EF for the diagonals.
+
+#[test]
+fn products() {
+    let pairs = [("grid:3:3", "loop:3"), ("loop:4", "grid:2:1"), ("hex:1", "square:2"), ("tetrahedron", "loop:5")];
+
+    for (a, b) in pairs {
+        let ((v, e), (w, f)) = (counts(&lae(a)), counts(&lae(b)));
+
+        assert_eq!(counts(&lae_product(lae(a), lae(b))), (v * w, e * w + v * f), "Wrong counts on {} * {}.", a, b);
+        assert_eq!(counts(&lae_strong_product(lae(a), lae(b))), (v * w, e * w + v * f + 2 * e * f),
+                   "Wrong counts on {} ** {}.", a, b);
+    }
+
+    // The product of two paths is a grid, and the strong product of two
+    // edges is a tetrahedron.
+
+    let path = |n: usize| lae(&format!("grid:{}:1", n));
+    assert_eq!(edge_set(&lae_product(path(3), path(4)).1), edge_set(&lae("grid:4:3").1));
+    assert_eq!(degrees(&lae_strong_product(path(2), path(2))), vec![3; 4]);
+
+    // Point (a, b) is numbered a * (size of B) + b.
+
+    let (_, edges) = lae_product(path(3), lae("loop:4"));
+    let edges = edge_set(&edges);
+    assert!(edges.contains(&(4, 8)) && edges.contains(&(4, 5)) && edges.contains(&(4, 7)));
+    assert!(!edges.contains(&(4, 9)));
+}
+
+// Removing points keeps the rest in order, with their ghosts.
+
+#[test]
+fn induced_boards() {
+    let (layout, edges) = lae_without(lae("torus:3:3"), &[0]);
+
+    assert_eq!(layout.len(), 8);
+    assert_eq!(layout[0], lae("torus:3:3").0[1]);
+    assert_eq!(edge_set(&edges).len(), 18 - 4);
+    assert!(layout.ghosts.iter().all(|&(point, _)| point < 8));
+    assert_eq!(layout.ghosts.len(), lae("torus:3:3").0.ghosts.iter().filter(|g| g.0 != 0).count());
+
+    assert_eq!(counts(&lae_joined(lae("grid:3:1"), &[(0, 2), (2, 0), (0, 1)])), (3, 3));
+}